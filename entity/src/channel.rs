@@ -11,6 +11,8 @@ pub struct Model {
     pub description: Option<String>,
     pub deleted: bool,
     pub organization_id: Uuid,
+    pub message_ttl_seconds: Option<i64>,
+    pub message_retention_days: Option<i64>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -15,14 +15,37 @@ pub struct Model {
     pub message_type: String,
     pub recipient_type: String,
     pub reference_id: Uuid,
+    pub parent_id: Option<Uuid>,
+    pub expires_at: Option<DateTime>,
+    pub client_message_id: Option<String>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
 pub enum Relation {
+    #[sea_orm(has_many = "super::bookmark::Entity")]
+    Bookmark,
     #[sea_orm(has_many = "super::media::Entity")]
     Media,
-    #[sea_orm(has_many = "super::seen_message::Entity")]
-    SeenMessage,
+    #[sea_orm(has_many = "super::message_mention::Entity")]
+    MessageMention,
+    #[sea_orm(has_many = "super::message_reaction::Entity")]
+    MessageReaction,
+    #[sea_orm(has_many = "super::message_revision::Entity")]
+    MessageRevision,
+    #[sea_orm(has_many = "super::pinned_message::Entity")]
+    PinnedMessage,
+    #[sea_orm(has_many = "super::poll::Entity")]
+    Poll,
+    #[sea_orm(has_many = "super::scheduled_message::Entity")]
+    ScheduledMessage,
+    #[sea_orm(
+        belongs_to = "Entity",
+        from = "Column::ParentId",
+        to = "Column::Id",
+        on_update = "Cascade",
+        on_delete = "Cascade"
+    )]
+    SelfRef,
     #[sea_orm(
         belongs_to = "super::user::Entity",
         from = "Column::UserId",
@@ -31,6 +54,14 @@ pub enum Relation {
         on_delete = "Cascade"
     )]
     User,
+    #[sea_orm(has_many = "super::user_channel_view::Entity")]
+    UserChannelView,
+}
+
+impl Related<super::bookmark::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Bookmark.def()
+    }
 }
 
 impl Related<super::media::Entity> for Entity {
@@ -39,9 +70,39 @@ impl Related<super::media::Entity> for Entity {
     }
 }
 
-impl Related<super::seen_message::Entity> for Entity {
+impl Related<super::message_mention::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::MessageMention.def()
+    }
+}
+
+impl Related<super::message_reaction::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::MessageReaction.def()
+    }
+}
+
+impl Related<super::message_revision::Entity> for Entity {
     fn to() -> RelationDef {
-        Relation::SeenMessage.def()
+        Relation::MessageRevision.def()
+    }
+}
+
+impl Related<super::pinned_message::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::PinnedMessage.def()
+    }
+}
+
+impl Related<super::poll::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Poll.def()
+    }
+}
+
+impl Related<super::scheduled_message::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::ScheduledMessage.def()
     }
 }
 
@@ -51,4 +112,10 @@ impl Related<super::user::Entity> for Entity {
     }
 }
 
+impl Related<super::user_channel_view::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::UserChannelView.def()
+    }
+}
+
 impl ActiveModelBehavior for ActiveModel {}
@@ -3,13 +3,13 @@
 use sea_orm::entity::prelude::*;
 
 #[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq)]
-#[sea_orm(table_name = "seen_message")]
+#[sea_orm(table_name = "message_mention")]
 pub struct Model {
     #[sea_orm(primary_key, auto_increment = false)]
     pub id: Uuid,
-    pub user_id: Uuid,
     pub message_id: Uuid,
-    pub date_seen: DateTime,
+    pub user_id: Uuid,
+    pub mention_type: String,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -2,15 +2,27 @@
 
 pub mod prelude;
 
+pub mod bookmark;
 pub mod channel;
 pub mod channel_role_access;
+pub mod conversation;
+pub mod conversation_participant;
 pub mod flag;
 pub mod media;
 pub mod message;
+pub mod message_mention;
+pub mod message_reaction;
+pub mod message_revision;
 pub mod organization;
+pub mod pinned_message;
+pub mod poll;
+pub mod poll_option;
+pub mod poll_vote;
 pub mod profile_image_key;
+pub mod retention_action;
 pub mod role;
-pub mod seen_message;
+pub mod scheduled_message;
+pub mod slash_command;
 pub mod user;
 pub mod user_channel_view;
 pub mod user_role_access;
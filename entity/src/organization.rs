@@ -7,14 +7,23 @@ use sea_orm::entity::prelude::*;
 pub struct Model {
     #[sea_orm(primary_key, auto_increment = false)]
     pub id: Uuid,
+    pub message_edit_window_seconds: Option<i64>,
+    pub message_retention_days: Option<i64>,
+    pub dm_retention_days: Option<i64>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
 pub enum Relation {
     #[sea_orm(has_many = "super::channel::Entity")]
     Channel,
+    #[sea_orm(has_many = "super::conversation::Entity")]
+    Conversation,
+    #[sea_orm(has_many = "super::retention_action::Entity")]
+    RetentionAction,
     #[sea_orm(has_many = "super::role::Entity")]
     Role,
+    #[sea_orm(has_many = "super::slash_command::Entity")]
+    SlashCommand,
     #[sea_orm(has_many = "super::user::Entity")]
     User,
 }
@@ -25,12 +34,30 @@ impl Related<super::channel::Entity> for Entity {
     }
 }
 
+impl Related<super::conversation::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Conversation.def()
+    }
+}
+
+impl Related<super::retention_action::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::RetentionAction.def()
+    }
+}
+
 impl Related<super::role::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::Role.def()
     }
 }
 
+impl Related<super::slash_command::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::SlashCommand.def()
+    }
+}
+
 impl Related<super::user::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::User.def()
@@ -1,14 +1,26 @@
 //! `SeaORM` Entity, @generated by sea-orm-codegen 1.0.0
 
+pub use super::bookmark::Entity as Bookmark;
 pub use super::channel::Entity as Channel;
 pub use super::channel_role_access::Entity as ChannelRoleAccess;
+pub use super::conversation::Entity as Conversation;
+pub use super::conversation_participant::Entity as ConversationParticipant;
 pub use super::flag::Entity as Flag;
 pub use super::media::Entity as Media;
 pub use super::message::Entity as Message;
+pub use super::message_mention::Entity as MessageMention;
+pub use super::message_reaction::Entity as MessageReaction;
+pub use super::message_revision::Entity as MessageRevision;
 pub use super::organization::Entity as Organization;
+pub use super::pinned_message::Entity as PinnedMessage;
+pub use super::poll::Entity as Poll;
+pub use super::poll_option::Entity as PollOption;
+pub use super::poll_vote::Entity as PollVote;
 pub use super::profile_image_key::Entity as ProfileImageKey;
+pub use super::retention_action::Entity as RetentionAction;
 pub use super::role::Entity as Role;
-pub use super::seen_message::Entity as SeenMessage;
+pub use super::scheduled_message::Entity as ScheduledMessage;
+pub use super::slash_command::Entity as SlashCommand;
 pub use super::user::Entity as User;
 pub use super::user_channel_view::Entity as UserChannelView;
 pub use super::user_role_access::Entity as UserRoleAccess;
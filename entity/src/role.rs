@@ -13,6 +13,8 @@ pub struct Model {
     pub manage_users: bool,
     pub manage_channels: bool,
     pub manage_roles: bool,
+    pub pin_messages: bool,
+    pub mention_everyone: bool,
     pub organization_id: Uuid,
 }
 
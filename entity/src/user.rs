@@ -16,10 +16,22 @@ pub struct Model {
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
 pub enum Relation {
+    #[sea_orm(has_many = "super::bookmark::Entity")]
+    Bookmark,
+    #[sea_orm(has_many = "super::conversation::Entity")]
+    Conversation,
+    #[sea_orm(has_many = "super::conversation_participant::Entity")]
+    ConversationParticipant,
     #[sea_orm(has_many = "super::media::Entity")]
     Media,
     #[sea_orm(has_many = "super::message::Entity")]
     Message,
+    #[sea_orm(has_many = "super::message_mention::Entity")]
+    MessageMention,
+    #[sea_orm(has_many = "super::message_reaction::Entity")]
+    MessageReaction,
+    #[sea_orm(has_many = "super::message_revision::Entity")]
+    MessageRevision,
     #[sea_orm(
         belongs_to = "super::organization::Entity",
         from = "Column::OrganizationId",
@@ -28,16 +40,40 @@ pub enum Relation {
         on_delete = "Cascade"
     )]
     Organization,
+    #[sea_orm(has_many = "super::pinned_message::Entity")]
+    PinnedMessage,
+    #[sea_orm(has_many = "super::poll_vote::Entity")]
+    PollVote,
     #[sea_orm(has_many = "super::profile_image_key::Entity")]
     ProfileImageKey,
-    #[sea_orm(has_many = "super::seen_message::Entity")]
-    SeenMessage,
+    #[sea_orm(has_many = "super::scheduled_message::Entity")]
+    ScheduledMessage,
+    #[sea_orm(has_many = "super::slash_command::Entity")]
+    SlashCommand,
     #[sea_orm(has_many = "super::user_channel_view::Entity")]
     UserChannelView,
     #[sea_orm(has_many = "super::user_role_access::Entity")]
     UserRoleAccess,
 }
 
+impl Related<super::bookmark::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Bookmark.def()
+    }
+}
+
+impl Related<super::conversation::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Conversation.def()
+    }
+}
+
+impl Related<super::conversation_participant::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::ConversationParticipant.def()
+    }
+}
+
 impl Related<super::media::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::Media.def()
@@ -50,21 +86,57 @@ impl Related<super::message::Entity> for Entity {
     }
 }
 
+impl Related<super::message_mention::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::MessageMention.def()
+    }
+}
+
+impl Related<super::message_reaction::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::MessageReaction.def()
+    }
+}
+
+impl Related<super::message_revision::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::MessageRevision.def()
+    }
+}
+
 impl Related<super::organization::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::Organization.def()
     }
 }
 
+impl Related<super::pinned_message::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::PinnedMessage.def()
+    }
+}
+
+impl Related<super::poll_vote::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::PollVote.def()
+    }
+}
+
 impl Related<super::profile_image_key::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::ProfileImageKey.def()
     }
 }
 
-impl Related<super::seen_message::Entity> for Entity {
+impl Related<super::scheduled_message::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::ScheduledMessage.def()
+    }
+}
+
+impl Related<super::slash_command::Entity> for Entity {
     fn to() -> RelationDef {
-        Relation::SeenMessage.def()
+        Relation::SlashCommand.def()
     }
 }
 
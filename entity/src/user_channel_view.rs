@@ -11,10 +11,20 @@ pub struct Model {
     pub recipient_type: String,
     pub reference_id: Uuid,
     pub last_viewed: DateTime,
+    pub muted_until: Option<DateTime>,
+    pub last_read_message_id: Option<Uuid>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
 pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::message::Entity",
+        from = "Column::LastReadMessageId",
+        to = "super::message::Column::Id",
+        on_update = "Cascade",
+        on_delete = "SetNull"
+    )]
+    Message,
     #[sea_orm(
         belongs_to = "super::user::Entity",
         from = "Column::UserId",
@@ -25,6 +35,12 @@ pub enum Relation {
     User,
 }
 
+impl Related<super::message::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Message.def()
+    }
+}
+
 impl Related<super::user::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::User.def()
@@ -11,6 +11,24 @@ mod m20240827_121544_create_user_channel_view_table;
 mod m20240829_110635_create_seen_message_table;
 mod m20240830_125050_create_profile_image_key_table;
 mod m20240924_101830_create_flag_table;
+mod m20241007_091512_add_parent_id_to_message_table;
+mod m20241010_134205_create_message_reaction_table;
+mod m20241014_102233_create_message_revision_table;
+mod m20241014_103510_add_message_edit_window_to_organization_table;
+mod m20241016_084112_add_pin_messages_to_role_table;
+mod m20241016_085530_create_pinned_message_table;
+mod m20241017_091204_add_content_tsv_to_message_table;
+mod m20241018_074502_add_mention_everyone_to_role_table;
+mod m20241018_075130_create_message_mention_table;
+mod m20241021_101544_create_conversation_table;
+mod m20241023_140318_create_scheduled_message_table;
+mod m20241024_093012_add_message_ttl;
+mod m20241025_081247_add_client_message_id_to_message_table;
+mod m20241028_102315_create_bookmark_table;
+mod m20241029_091530_create_poll_table;
+mod m20241031_083522_create_retention_action_table;
+mod m20241104_101455_create_slash_command_table;
+mod m20241106_090214_convert_seen_messages_to_read_cursors;
 pub struct Migrator;
 
 #[async_trait::async_trait]
@@ -29,6 +47,24 @@ impl MigratorTrait for Migrator {
             Box::new(m20240829_110635_create_seen_message_table::Migration),
             Box::new(m20240830_125050_create_profile_image_key_table::Migration),
             Box::new(m20240924_101830_create_flag_table::Migration),
+            Box::new(m20241007_091512_add_parent_id_to_message_table::Migration),
+            Box::new(m20241010_134205_create_message_reaction_table::Migration),
+            Box::new(m20241014_102233_create_message_revision_table::Migration),
+            Box::new(m20241014_103510_add_message_edit_window_to_organization_table::Migration),
+            Box::new(m20241016_084112_add_pin_messages_to_role_table::Migration),
+            Box::new(m20241016_085530_create_pinned_message_table::Migration),
+            Box::new(m20241017_091204_add_content_tsv_to_message_table::Migration),
+            Box::new(m20241018_074502_add_mention_everyone_to_role_table::Migration),
+            Box::new(m20241018_075130_create_message_mention_table::Migration),
+            Box::new(m20241021_101544_create_conversation_table::Migration),
+            Box::new(m20241023_140318_create_scheduled_message_table::Migration),
+            Box::new(m20241024_093012_add_message_ttl::Migration),
+            Box::new(m20241025_081247_add_client_message_id_to_message_table::Migration),
+            Box::new(m20241028_102315_create_bookmark_table::Migration),
+            Box::new(m20241029_091530_create_poll_table::Migration),
+            Box::new(m20241031_083522_create_retention_action_table::Migration),
+            Box::new(m20241104_101455_create_slash_command_table::Migration),
+            Box::new(m20241106_090214_convert_seen_messages_to_read_cursors::Migration),
         ]
     }
 }
@@ -67,6 +67,7 @@ impl MigrationTrait for Migration {
 }
 
 #[derive(DeriveIden)]
+#[allow(clippy::enum_variant_names)]
 pub(crate) enum Message {
     Table,
     Id,
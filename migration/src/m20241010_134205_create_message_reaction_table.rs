@@ -0,0 +1,89 @@
+use super::m20240802_093625_create_user_table::User;
+use super::m20240805_132555_create_message_table::Message;
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(MessageReaction::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(MessageReaction::Id)
+                            .uuid()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(MessageReaction::MessageId).uuid().not_null())
+                    .col(ColumnDef::new(MessageReaction::UserId).uuid().not_null())
+                    .col(ColumnDef::new(MessageReaction::Emoji).string().not_null())
+                    .col(
+                        ColumnDef::new(MessageReaction::DateCreated)
+                            .timestamp()
+                            .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-message_reaction-message_id")
+                            .from(MessageReaction::Table, MessageReaction::MessageId)
+                            .to(Message::Table, Message::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-message_reaction-user_id")
+                            .from(MessageReaction::Table, MessageReaction::UserId)
+                            .to(User::Table, User::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .table(MessageReaction::Table)
+                    .name("idx-message_reaction-message_id-user_id-emoji")
+                    .col(MessageReaction::MessageId)
+                    .col(MessageReaction::UserId)
+                    .col(MessageReaction::Emoji)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .table(MessageReaction::Table)
+                    .name("idx-message_reaction-user_id")
+                    .col(MessageReaction::UserId)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(MessageReaction::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum MessageReaction {
+    Table,
+    Id,
+    MessageId,
+    UserId,
+    Emoji,
+    DateCreated,
+}
@@ -0,0 +1,76 @@
+use super::m20240802_093625_create_user_table::User;
+use super::m20240805_132555_create_message_table::Message;
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(MessageRevision::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(MessageRevision::Id)
+                            .uuid()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(MessageRevision::MessageId).uuid().not_null())
+                    .col(ColumnDef::new(MessageRevision::Content).string())
+                    .col(ColumnDef::new(MessageRevision::EditedBy).uuid().not_null())
+                    .col(
+                        ColumnDef::new(MessageRevision::DateEdited)
+                            .timestamp()
+                            .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-message_revision-message_id")
+                            .from(MessageRevision::Table, MessageRevision::MessageId)
+                            .to(Message::Table, Message::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-message_revision-edited_by")
+                            .from(MessageRevision::Table, MessageRevision::EditedBy)
+                            .to(User::Table, User::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .table(MessageRevision::Table)
+                    .name("idx-message_revision-message_id")
+                    .col(MessageRevision::MessageId)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(MessageRevision::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum MessageRevision {
+    Table,
+    Id,
+    MessageId,
+    Content,
+    EditedBy,
+    DateEdited,
+}
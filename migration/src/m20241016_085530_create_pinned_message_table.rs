@@ -0,0 +1,94 @@
+use super::m20240802_093625_create_user_table::User;
+use super::m20240805_132555_create_message_table::Message;
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(PinnedMessage::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(PinnedMessage::Id)
+                            .uuid()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(PinnedMessage::MessageId).uuid().not_null())
+                    .col(
+                        ColumnDef::new(PinnedMessage::RecipientType)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(PinnedMessage::ReferenceId).uuid().not_null())
+                    .col(ColumnDef::new(PinnedMessage::PinnedBy).uuid().not_null())
+                    .col(
+                        ColumnDef::new(PinnedMessage::DatePinned)
+                            .timestamp()
+                            .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-pinned_message-message_id")
+                            .from(PinnedMessage::Table, PinnedMessage::MessageId)
+                            .to(Message::Table, Message::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-pinned_message-pinned_by")
+                            .from(PinnedMessage::Table, PinnedMessage::PinnedBy)
+                            .to(User::Table, User::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .table(PinnedMessage::Table)
+                    .name("idx-pinned_message-message_id")
+                    .col(PinnedMessage::MessageId)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .table(PinnedMessage::Table)
+                    .name("idx-pinned_message-recipient_type-reference_id")
+                    .col(PinnedMessage::RecipientType)
+                    .col(PinnedMessage::ReferenceId)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(PinnedMessage::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum PinnedMessage {
+    Table,
+    Id,
+    MessageId,
+    RecipientType,
+    ReferenceId,
+    PinnedBy,
+    DatePinned,
+}
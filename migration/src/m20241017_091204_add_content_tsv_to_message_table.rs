@@ -0,0 +1,61 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Message::Table)
+                    .add_column(
+                        ColumnDef::new(Message::ContentTsv)
+                            .custom(Alias::new("tsvector"))
+                            .extra(
+                                "GENERATED ALWAYS AS (to_tsvector('english', coalesce(content, ''))) STORED",
+                            ),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .table(Message::Table)
+                    .name("idx-message-content_tsv")
+                    .col(Message::ContentTsv)
+                    .full_text()
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .table(Message::Table)
+                    .name("idx-message-content_tsv")
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Message::Table)
+                    .drop_column(Message::ContentTsv)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Message {
+    Table,
+    ContentTsv,
+}
@@ -0,0 +1,86 @@
+use super::m20240802_093625_create_user_table::User;
+use super::m20240805_132555_create_message_table::Message;
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(MessageMention::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(MessageMention::Id)
+                            .uuid()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(MessageMention::MessageId).uuid().not_null())
+                    .col(ColumnDef::new(MessageMention::UserId).uuid().not_null())
+                    .col(
+                        ColumnDef::new(MessageMention::MentionType)
+                            .string()
+                            .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-message_mention-message_id")
+                            .from(MessageMention::Table, MessageMention::MessageId)
+                            .to(Message::Table, Message::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-message_mention-user_id")
+                            .from(MessageMention::Table, MessageMention::UserId)
+                            .to(User::Table, User::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .table(MessageMention::Table)
+                    .name("idx-message_mention-message_id-user_id")
+                    .col(MessageMention::MessageId)
+                    .col(MessageMention::UserId)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .table(MessageMention::Table)
+                    .name("idx-message_mention-user_id")
+                    .col(MessageMention::UserId)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(MessageMention::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum MessageMention {
+    Table,
+    Id,
+    MessageId,
+    UserId,
+    MentionType,
+}
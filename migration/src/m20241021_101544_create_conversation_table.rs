@@ -0,0 +1,168 @@
+use super::m20240801_133022_create_organization_table::Organization;
+use super::m20240802_093625_create_user_table::User;
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Conversation::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(Conversation::Id)
+                            .uuid()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(Conversation::Name).string())
+                    .col(
+                        ColumnDef::new(Conversation::OrganizationId)
+                            .uuid()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(Conversation::CreatedBy).uuid().not_null())
+                    .col(
+                        ColumnDef::new(Conversation::DateCreated)
+                            .timestamp()
+                            .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-conversation-organization_id")
+                            .from(Conversation::Table, Conversation::OrganizationId)
+                            .to(Organization::Table, Organization::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-conversation-created_by")
+                            .from(Conversation::Table, Conversation::CreatedBy)
+                            .to(User::Table, User::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(ConversationParticipant::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(ConversationParticipant::Id)
+                            .uuid()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(ConversationParticipant::ConversationId)
+                            .uuid()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(ConversationParticipant::UserId)
+                            .uuid()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(ConversationParticipant::DateJoined)
+                            .timestamp()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(ConversationParticipant::Deleted)
+                            .boolean()
+                            .not_null()
+                            .default(false),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-conversation_participant-conversation_id")
+                            .from(
+                                ConversationParticipant::Table,
+                                ConversationParticipant::ConversationId,
+                            )
+                            .to(Conversation::Table, Conversation::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-conversation_participant-user_id")
+                            .from(
+                                ConversationParticipant::Table,
+                                ConversationParticipant::UserId,
+                            )
+                            .to(User::Table, User::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .table(ConversationParticipant::Table)
+                    .name("idx-conversation_participant-conversation_id-user_id")
+                    .col(ConversationParticipant::ConversationId)
+                    .col(ConversationParticipant::UserId)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .table(ConversationParticipant::Table)
+                    .name("idx-conversation_participant-user_id")
+                    .col(ConversationParticipant::UserId)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(
+                Table::drop()
+                    .table(ConversationParticipant::Table)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(Conversation::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Conversation {
+    Table,
+    Id,
+    Name,
+    OrganizationId,
+    CreatedBy,
+    DateCreated,
+}
+
+#[derive(DeriveIden)]
+enum ConversationParticipant {
+    Table,
+    Id,
+    ConversationId,
+    UserId,
+    DateJoined,
+    Deleted,
+}
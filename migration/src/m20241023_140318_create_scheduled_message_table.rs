@@ -0,0 +1,115 @@
+use super::m20240802_093625_create_user_table::User;
+use super::m20240805_132555_create_message_table::Message;
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(ScheduledMessage::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(ScheduledMessage::Id)
+                            .uuid()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(ScheduledMessage::UserId).uuid().not_null())
+                    .col(ColumnDef::new(ScheduledMessage::Content).string())
+                    .col(
+                        ColumnDef::new(ScheduledMessage::MessageType)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(ScheduledMessage::RecipientType)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(ScheduledMessage::ReferenceId)
+                            .uuid()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(ScheduledMessage::ParentId).uuid())
+                    .col(
+                        ColumnDef::new(ScheduledMessage::MediaIds)
+                            .json_binary()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(ScheduledMessage::SendAt)
+                            .timestamp()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(ScheduledMessage::Status).string().not_null())
+                    .col(ColumnDef::new(ScheduledMessage::MessageId).uuid())
+                    .col(ColumnDef::new(ScheduledMessage::FailureReason).string())
+                    .col(ColumnDef::new(ScheduledMessage::ClaimedAt).timestamp())
+                    .col(
+                        ColumnDef::new(ScheduledMessage::DateCreated)
+                            .timestamp()
+                            .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-scheduled_message-user_id")
+                            .from(ScheduledMessage::Table, ScheduledMessage::UserId)
+                            .to(User::Table, User::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-scheduled_message-message_id")
+                            .from(ScheduledMessage::Table, ScheduledMessage::MessageId)
+                            .to(Message::Table, Message::Id)
+                            .on_delete(ForeignKeyAction::SetNull)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .table(ScheduledMessage::Table)
+                    .name("idx-scheduled_message-status-send_at")
+                    .col(ScheduledMessage::Status)
+                    .col(ScheduledMessage::SendAt)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(ScheduledMessage::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum ScheduledMessage {
+    Table,
+    Id,
+    UserId,
+    Content,
+    MessageType,
+    RecipientType,
+    ReferenceId,
+    ParentId,
+    MediaIds,
+    SendAt,
+    Status,
+    MessageId,
+    FailureReason,
+    ClaimedAt,
+    DateCreated,
+}
@@ -0,0 +1,102 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Channel::Table)
+                    .add_column(ColumnDef::new(Channel::MessageTtlSeconds).big_integer())
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Message::Table)
+                    .add_column(ColumnDef::new(Message::ExpiresAt).timestamp())
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .table(Message::Table)
+                    .name("idx-message-expires_at")
+                    .col(Message::ExpiresAt)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(ScheduledMessage::Table)
+                    .add_column(ColumnDef::new(ScheduledMessage::TtlSeconds).big_integer())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(ScheduledMessage::Table)
+                    .drop_column(ScheduledMessage::TtlSeconds)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .drop_index(
+                Index::drop()
+                    .table(Message::Table)
+                    .name("idx-message-expires_at")
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Message::Table)
+                    .drop_column(Message::ExpiresAt)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Channel::Table)
+                    .drop_column(Channel::MessageTtlSeconds)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Channel {
+    Table,
+    MessageTtlSeconds,
+}
+
+#[derive(DeriveIden)]
+enum Message {
+    Table,
+    ExpiresAt,
+}
+
+#[derive(DeriveIden)]
+enum ScheduledMessage {
+    Table,
+    TtlSeconds,
+}
@@ -0,0 +1,103 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Message::Table)
+                    .add_column(ColumnDef::new(Message::ClientMessageId).string())
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .table(Message::Table)
+                    .name("idx-message-user_id-client_message_id")
+                    .col(Message::UserId)
+                    .col(Message::ClientMessageId)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(ScheduledMessage::Table)
+                    .add_column(ColumnDef::new(ScheduledMessage::ClientMessageId).string())
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .table(ScheduledMessage::Table)
+                    .name("idx-scheduled_message-user_id-client_message_id")
+                    .col(ScheduledMessage::UserId)
+                    .col(ScheduledMessage::ClientMessageId)
+                    .unique()
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .table(ScheduledMessage::Table)
+                    .name("idx-scheduled_message-user_id-client_message_id")
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(ScheduledMessage::Table)
+                    .drop_column(ScheduledMessage::ClientMessageId)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .drop_index(
+                Index::drop()
+                    .table(Message::Table)
+                    .name("idx-message-user_id-client_message_id")
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Message::Table)
+                    .drop_column(Message::ClientMessageId)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Message {
+    Table,
+    UserId,
+    ClientMessageId,
+}
+
+#[derive(DeriveIden)]
+enum ScheduledMessage {
+    Table,
+    UserId,
+    ClientMessageId,
+}
@@ -0,0 +1,78 @@
+use super::m20240802_093625_create_user_table::User;
+use super::m20240805_132555_create_message_table::Message;
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Bookmark::Table)
+                    .if_not_exists()
+                    .col(ColumnDef::new(Bookmark::Id).uuid().not_null().primary_key())
+                    .col(ColumnDef::new(Bookmark::UserId).uuid().not_null())
+                    .col(ColumnDef::new(Bookmark::MessageId).uuid())
+                    .col(ColumnDef::new(Bookmark::Note).string())
+                    .col(ColumnDef::new(Bookmark::Folder).string())
+                    .col(
+                        ColumnDef::new(Bookmark::Unavailable)
+                            .boolean()
+                            .not_null()
+                            .default(false),
+                    )
+                    .col(ColumnDef::new(Bookmark::DateCreated).timestamp().not_null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-bookmark-user_id")
+                            .from(Bookmark::Table, Bookmark::UserId)
+                            .to(User::Table, User::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-bookmark-message_id")
+                            .from(Bookmark::Table, Bookmark::MessageId)
+                            .to(Message::Table, Message::Id)
+                            .on_delete(ForeignKeyAction::SetNull)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .table(Bookmark::Table)
+                    .name("idx-bookmark-user_id-message_id")
+                    .col(Bookmark::UserId)
+                    .col(Bookmark::MessageId)
+                    .unique()
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(Bookmark::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Bookmark {
+    Table,
+    Id,
+    UserId,
+    MessageId,
+    Note,
+    Folder,
+    Unavailable,
+    DateCreated,
+}
@@ -0,0 +1,173 @@
+use super::m20240802_093625_create_user_table::User;
+use super::m20240805_132555_create_message_table::Message;
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Poll::Table)
+                    .if_not_exists()
+                    .col(ColumnDef::new(Poll::Id).uuid().not_null().primary_key())
+                    .col(ColumnDef::new(Poll::MessageId).uuid().not_null())
+                    .col(ColumnDef::new(Poll::MultipleChoice).boolean().not_null())
+                    .col(ColumnDef::new(Poll::Anonymous).boolean().not_null())
+                    .col(ColumnDef::new(Poll::ClosesAt).timestamp())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-poll-message_id")
+                            .from(Poll::Table, Poll::MessageId)
+                            .to(Message::Table, Message::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .table(Poll::Table)
+                    .name("idx-poll-message_id")
+                    .col(Poll::MessageId)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(PollOption::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(PollOption::Id)
+                            .uuid()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(PollOption::PollId).uuid().not_null())
+                    .col(ColumnDef::new(PollOption::Text).string().not_null())
+                    .col(ColumnDef::new(PollOption::Position).integer().not_null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-poll_option-poll_id")
+                            .from(PollOption::Table, PollOption::PollId)
+                            .to(Poll::Table, Poll::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(PollVote::Table)
+                    .if_not_exists()
+                    .col(ColumnDef::new(PollVote::Id).uuid().not_null().primary_key())
+                    .col(ColumnDef::new(PollVote::PollId).uuid().not_null())
+                    .col(ColumnDef::new(PollVote::PollOptionId).uuid().not_null())
+                    .col(ColumnDef::new(PollVote::UserId).uuid().not_null())
+                    .col(ColumnDef::new(PollVote::DateCreated).timestamp().not_null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-poll_vote-poll_id")
+                            .from(PollVote::Table, PollVote::PollId)
+                            .to(Poll::Table, Poll::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-poll_vote-poll_option_id")
+                            .from(PollVote::Table, PollVote::PollOptionId)
+                            .to(PollOption::Table, PollOption::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-poll_vote-user_id")
+                            .from(PollVote::Table, PollVote::UserId)
+                            .to(User::Table, User::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .table(PollVote::Table)
+                    .name("idx-poll_vote-poll_option_id-user_id")
+                    .col(PollVote::PollOptionId)
+                    .col(PollVote::UserId)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .table(PollVote::Table)
+                    .name("idx-poll_vote-poll_id")
+                    .col(PollVote::PollId)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(PollVote::Table).to_owned())
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(PollOption::Table).to_owned())
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(Poll::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Poll {
+    Table,
+    Id,
+    MessageId,
+    MultipleChoice,
+    Anonymous,
+    ClosesAt,
+}
+
+#[derive(DeriveIden)]
+enum PollOption {
+    Table,
+    Id,
+    PollId,
+    Text,
+    Position,
+}
+
+#[derive(DeriveIden)]
+enum PollVote {
+    Table,
+    Id,
+    PollId,
+    PollOptionId,
+    UserId,
+    DateCreated,
+}
@@ -0,0 +1,157 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Organization::Table)
+                    .add_column(ColumnDef::new(Organization::MessageRetentionDays).big_integer())
+                    .add_column(ColumnDef::new(Organization::DmRetentionDays).big_integer())
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Channel::Table)
+                    .add_column(ColumnDef::new(Channel::MessageRetentionDays).big_integer())
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(RetentionAction::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(RetentionAction::Id)
+                            .uuid()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(RetentionAction::OrganizationId)
+                            .uuid()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(RetentionAction::RecipientType)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(RetentionAction::ReferenceId).uuid())
+                    .col(
+                        ColumnDef::new(RetentionAction::Cutoff)
+                            .timestamp()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(RetentionAction::MessagesDeleted)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(RetentionAction::MediaDeleted)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(RetentionAction::SeenMessagesDeleted)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(RetentionAction::UserChannelViewsDeleted)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(RetentionAction::DateCreated)
+                            .timestamp()
+                            .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-retention_action-organization_id")
+                            .from(RetentionAction::Table, RetentionAction::OrganizationId)
+                            .to(Organization::Table, Organization::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .table(RetentionAction::Table)
+                    .name("idx-retention_action-organization_id-date_created")
+                    .col(RetentionAction::OrganizationId)
+                    .col(RetentionAction::DateCreated)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(RetentionAction::Table).to_owned())
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Channel::Table)
+                    .drop_column(Channel::MessageRetentionDays)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Organization::Table)
+                    .drop_column(Organization::MessageRetentionDays)
+                    .drop_column(Organization::DmRetentionDays)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Organization {
+    Table,
+    Id,
+    MessageRetentionDays,
+    DmRetentionDays,
+}
+
+#[derive(DeriveIden)]
+enum Channel {
+    Table,
+    MessageRetentionDays,
+}
+
+#[derive(DeriveIden)]
+enum RetentionAction {
+    Table,
+    Id,
+    OrganizationId,
+    RecipientType,
+    ReferenceId,
+    Cutoff,
+    MessagesDeleted,
+    MediaDeleted,
+    SeenMessagesDeleted,
+    UserChannelViewsDeleted,
+    DateCreated,
+}
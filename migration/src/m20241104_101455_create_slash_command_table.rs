@@ -0,0 +1,115 @@
+use sea_orm_migration::prelude::*;
+
+use super::m20240801_133022_create_organization_table::Organization;
+use super::m20240802_093625_create_user_table::User;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(SlashCommand::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(SlashCommand::Id)
+                            .uuid()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(SlashCommand::OrganizationId)
+                            .uuid()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(SlashCommand::Name).string().not_null())
+                    .col(ColumnDef::new(SlashCommand::Description).string())
+                    .col(ColumnDef::new(SlashCommand::Usage).string())
+                    .col(ColumnDef::new(SlashCommand::WebhookUrl).string().not_null())
+                    .col(ColumnDef::new(SlashCommand::Token).string().not_null())
+                    .col(ColumnDef::new(SlashCommand::CreatedBy).uuid().not_null())
+                    .col(
+                        ColumnDef::new(SlashCommand::DateCreated)
+                            .timestamp()
+                            .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-slash_command-organization_id")
+                            .from(SlashCommand::Table, SlashCommand::OrganizationId)
+                            .to(Organization::Table, Organization::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-slash_command-created_by")
+                            .from(SlashCommand::Table, SlashCommand::CreatedBy)
+                            .to(User::Table, User::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .table(SlashCommand::Table)
+                    .name("idx-slash_command-organization_id-name")
+                    .col(SlashCommand::OrganizationId)
+                    .col(SlashCommand::Name)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(UserChannelView::Table)
+                    .add_column(ColumnDef::new(UserChannelView::MutedUntil).timestamp())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(UserChannelView::Table)
+                    .drop_column(UserChannelView::MutedUntil)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(SlashCommand::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum SlashCommand {
+    Table,
+    Id,
+    OrganizationId,
+    Name,
+    Description,
+    Usage,
+    WebhookUrl,
+    Token,
+    CreatedBy,
+    DateCreated,
+}
+
+#[derive(DeriveIden)]
+enum UserChannelView {
+    Table,
+    MutedUntil,
+}
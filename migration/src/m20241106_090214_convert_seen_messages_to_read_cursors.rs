@@ -0,0 +1,271 @@
+use super::m20240802_093625_create_user_table::User;
+use super::m20240805_132555_create_message_table::Message;
+use sea_orm_migration::prelude::*;
+
+/// The newest message each user has seen per conversation. DMs are keyed by
+/// the other participant, the same way `user_channel_view` stores them.
+const LATEST_SEEN_MESSAGES: &str = r#"
+    SELECT DISTINCT ON (seen.user_id, seen.recipient_type, seen.reference_id)
+        seen.user_id, seen.recipient_type, seen.reference_id, seen.message_id, seen.date_created
+    FROM (
+        SELECT
+            seen_message.user_id,
+            message.recipient_type,
+            CASE
+                WHEN message.recipient_type <> 'USER' THEN message.reference_id
+                WHEN message.user_id = seen_message.user_id THEN message.reference_id
+                ELSE message.user_id
+            END AS reference_id,
+            message.id AS message_id,
+            message.date_created
+        FROM seen_message
+        INNER JOIN message ON message.id = seen_message.message_id
+    ) AS seen
+    ORDER BY seen.user_id, seen.recipient_type, seen.reference_id, seen.date_created DESC
+"#;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(UserChannelView::Table)
+                    .add_column(ColumnDef::new(UserChannelView::LastReadMessageId).uuid())
+                    .add_foreign_key(
+                        TableForeignKey::new()
+                            .name("fk-user_channel_view-last_read_message_id")
+                            .from_tbl(UserChannelView::Table)
+                            .from_col(UserChannelView::LastReadMessageId)
+                            .to_tbl(Message::Table)
+                            .to_col(Message::Id)
+                            .on_delete(ForeignKeyAction::SetNull)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        let db = manager.get_connection();
+
+        // Concurrent first reads could add a second view for the same
+        // conversation. The most recent one is kept, along with the latest mute.
+        db.execute_unprepared(
+            r#"
+            UPDATE user_channel_view
+            SET muted_until = duplicates.muted_until
+            FROM (
+                SELECT user_id, recipient_type, reference_id, MAX(muted_until) AS muted_until
+                FROM user_channel_view
+                GROUP BY user_id, recipient_type, reference_id
+                HAVING COUNT(*) > 1
+            ) AS duplicates
+            WHERE user_channel_view.user_id = duplicates.user_id
+                AND user_channel_view.recipient_type = duplicates.recipient_type
+                AND user_channel_view.reference_id = duplicates.reference_id
+            "#,
+        )
+        .await?;
+
+        db.execute_unprepared(
+            r#"
+            DELETE FROM user_channel_view
+            WHERE id IN (
+                SELECT id FROM (
+                    SELECT
+                        id,
+                        ROW_NUMBER() OVER (
+                            PARTITION BY user_id, recipient_type, reference_id
+                            ORDER BY last_viewed DESC, id
+                        ) AS row_number
+                    FROM user_channel_view
+                ) AS ranked
+                WHERE ranked.row_number > 1
+            )
+            "#,
+        )
+        .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .table(UserChannelView::Table)
+                    .name("idx-user_channel_view-user_id-recipient_type-reference_id")
+                    .col(UserChannelView::UserId)
+                    .col(UserChannelView::RecipientType)
+                    .col(UserChannelView::ReferenceId)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        // Cursors only ever move forward, so existing views that are already
+        // past the newest seen message are left alone
+        db.execute_unprepared(&format!(
+            r#"
+            UPDATE user_channel_view
+            SET last_viewed = latest_seen.date_created,
+                last_read_message_id = latest_seen.message_id
+            FROM ({LATEST_SEEN_MESSAGES}) AS latest_seen
+            WHERE user_channel_view.user_id = latest_seen.user_id
+                AND user_channel_view.recipient_type = latest_seen.recipient_type
+                AND user_channel_view.reference_id = latest_seen.reference_id
+                AND user_channel_view.last_viewed < latest_seen.date_created
+            "#
+        ))
+        .await?;
+
+        db.execute_unprepared(&format!(
+            r#"
+            INSERT INTO user_channel_view
+                (id, user_id, recipient_type, reference_id, last_viewed, last_read_message_id)
+            SELECT
+                gen_random_uuid(),
+                latest_seen.user_id,
+                latest_seen.recipient_type,
+                latest_seen.reference_id,
+                latest_seen.date_created,
+                latest_seen.message_id
+            FROM ({LATEST_SEEN_MESSAGES}) AS latest_seen
+            WHERE NOT EXISTS (
+                SELECT 1 FROM user_channel_view
+                WHERE user_channel_view.user_id = latest_seen.user_id
+                    AND user_channel_view.recipient_type = latest_seen.recipient_type
+                    AND user_channel_view.reference_id = latest_seen.reference_id
+            )
+            "#
+        ))
+        .await?;
+
+        manager
+            .drop_table(Table::drop().table(SeenMessage::Table).to_owned())
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(RetentionAction::Table)
+                    .drop_column(RetentionAction::SeenMessagesDeleted)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .table(UserChannelView::Table)
+                    .name("idx-user_channel_view-user_id-recipient_type-reference_id")
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(RetentionAction::Table)
+                    .add_column(
+                        ColumnDef::new(RetentionAction::SeenMessagesDeleted)
+                            .big_integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // Individual seen rows can't be rebuilt from cursors, the table comes
+        // back empty
+        manager
+            .create_table(
+                Table::create()
+                    .table(SeenMessage::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(SeenMessage::Id)
+                            .uuid()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(SeenMessage::UserId).uuid().not_null())
+                    .col(ColumnDef::new(SeenMessage::MessageId).uuid().not_null())
+                    .col(ColumnDef::new(SeenMessage::DateSeen).timestamp().not_null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-seen_message-user_id")
+                            .from(SeenMessage::Table, SeenMessage::UserId)
+                            .to(User::Table, User::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-seen_message-message_id")
+                            .from(SeenMessage::Table, SeenMessage::MessageId)
+                            .to(Message::Table, Message::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .table(SeenMessage::Table)
+                    .name("idx-seen_message-user_id")
+                    .col(SeenMessage::UserId)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .table(SeenMessage::Table)
+                    .name("idx-seen_message-message_id")
+                    .col(SeenMessage::MessageId)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(UserChannelView::Table)
+                    .drop_foreign_key(Alias::new("fk-user_channel_view-last_read_message_id"))
+                    .drop_column(UserChannelView::LastReadMessageId)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum UserChannelView {
+    Table,
+    UserId,
+    RecipientType,
+    ReferenceId,
+    LastReadMessageId,
+}
+
+#[derive(DeriveIden)]
+enum SeenMessage {
+    Table,
+    Id,
+    UserId,
+    MessageId,
+    DateSeen,
+}
+
+#[derive(DeriveIden)]
+enum RetentionAction {
+    Table,
+    SeenMessagesDeleted,
+}
@@ -8,6 +8,7 @@ use std::sync::Arc;
 use utils::app_state::AppState;
 use utils::chat::ChatRoom;
 use utils::s3::configure_and_return_s3_client;
+use utils::webhook::build_webhook_client;
 
 pub mod middlewares;
 mod routes;
@@ -30,10 +31,16 @@ async fn main() -> std::io::Result<()> {
         utils::seed::seed_data(&db).await;
     }
 
-    let chat_room = Arc::new(ChatRoom::new());
+    let app_state = web::Data::new(AppState {
+        db,
+        webhook_client: build_webhook_client(),
+    });
+    let chat_room = web::Data::new(Arc::new(ChatRoom::new()));
 
     let s3_client = web::Data::new(configure_and_return_s3_client().await);
 
+    utils::scheduler::start_scheduler(app_state.clone(), chat_room.clone(), s3_client.clone());
+
     HttpServer::new(move || {
         App::new()
             .wrap(
@@ -43,8 +50,8 @@ async fn main() -> std::io::Result<()> {
                     .allow_any_header()
                     .max_age(3600),
             )
-            .app_data(web::Data::new(AppState { db: db.clone() }))
-            .app_data(web::Data::new(chat_room.clone()))
+            .app_data(app_state.clone())
+            .app_data(chat_room.clone())
             .app_data(s3_client.clone())
             .configure(routes::auth_routes::config)
             .configure(routes::channel_routes::config)
@@ -60,6 +67,14 @@ async fn main() -> std::io::Result<()> {
             .configure(routes::seen_message_routes::config)
             .configure(routes::organization_routes::config)
             .configure(routes::flag_routes::config)
+            .configure(routes::reaction_routes::config)
+            .configure(routes::pin_routes::config)
+            .configure(routes::conversation_routes::config)
+            .configure(routes::scheduled_message_routes::config)
+            .configure(routes::bookmark_routes::config)
+            .configure(routes::poll_routes::config)
+            .configure(routes::export_routes::config)
+            .configure(routes::command_routes::config)
     })
     .bind(("0.0.0.0", 8080))?
     .run()
@@ -14,7 +14,7 @@ pub async fn get_s3_flag_on(
         .app_data::<web::Data<AppState>>()
         .ok_or_else(|| Error::from(ApiResponse::new(500, "App state not available".to_string())))?;
 
-    let s3_flag = is_flag_on("s3", &app_state).await;
+    let s3_flag = is_flag_on("s3", app_state).await;
 
     if s3_flag {
         next.call(req).await
@@ -0,0 +1,14 @@
+use super::handlers;
+use crate::middlewares;
+use actix_web::web;
+use actix_web_lab::middleware::from_fn;
+
+pub fn config(config: &mut web::ServiceConfig) {
+    config.service(
+        web::scope("/bookmark")
+            .wrap(from_fn(middlewares::auth_middleware::check_auth_middleware))
+            .service(handlers::bookmark_handler::save_bookmark)
+            .service(handlers::bookmark_handler::delete_bookmark)
+            .service(handlers::bookmark_handler::get_bookmarks),
+    );
+}
@@ -0,0 +1,14 @@
+use super::handlers;
+use crate::middlewares;
+use actix_web::web;
+use actix_web_lab::middleware::from_fn;
+
+pub fn config(config: &mut web::ServiceConfig) {
+    config.service(
+        web::scope("/command")
+            .wrap(from_fn(middlewares::auth_middleware::check_auth_middleware))
+            .service(handlers::command_handler::get_commands)
+            .service(handlers::command_handler::register_command)
+            .service(handlers::command_handler::delete_command),
+    );
+}
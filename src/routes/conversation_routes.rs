@@ -0,0 +1,15 @@
+use super::handlers;
+use crate::middlewares;
+use actix_web::web;
+use actix_web_lab::middleware::from_fn;
+
+pub fn config(config: &mut web::ServiceConfig) {
+    config.service(
+        web::scope("/conversation")
+            .wrap(from_fn(middlewares::auth_middleware::check_auth_middleware))
+            .service(handlers::conversation_handler::create_conversation)
+            .service(handlers::conversation_handler::get_conversations)
+            .service(handlers::conversation_handler::add_participant)
+            .service(handlers::conversation_handler::leave_conversation),
+    );
+}
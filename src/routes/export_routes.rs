@@ -0,0 +1,12 @@
+use super::handlers;
+use crate::middlewares;
+use actix_web::web;
+use actix_web_lab::middleware::from_fn;
+
+pub fn config(config: &mut web::ServiceConfig) {
+    config.service(
+        web::scope("/export")
+            .wrap(from_fn(middlewares::auth_middleware::check_auth_middleware))
+            .service(handlers::export_handler::export_transcript),
+    );
+}
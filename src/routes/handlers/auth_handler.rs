@@ -82,6 +82,7 @@ pub async fn create_organization(
 
     let new_organization = organization::ActiveModel {
         id: Set(Uuid::new_v4()),
+        ..Default::default()
     }
     .insert(&app_state.db)
     .await
@@ -0,0 +1,307 @@
+use crate::routes::handlers::media_handler::MediaUploadDTO;
+use crate::routes::handlers::message_handler::{check_user_can_read_message, MessageDTO};
+use crate::utils::api_response::ApiResponse;
+use crate::utils::app_state;
+use crate::utils::jwt::get_user_id_from_http_request;
+use crate::utils::logging::log_info;
+use actix_web::{delete, get, post, web, HttpRequest, Result};
+use chrono::Utc;
+use entity::{bookmark, media, message};
+use sea_orm::sea_query::Expr;
+use sea_orm::ActiveValue::Set;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, IntoActiveModel, ModelTrait,
+    QueryFilter, QueryOrder,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+const MAX_BOOKMARK_FOLDER_LENGTH: usize = 64;
+
+#[derive(Serialize, Deserialize)]
+struct BookmarkSaveDTO {
+    message_id: Uuid,
+    note: Option<String>,
+    folder: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct BookmarkIdDTO {
+    id: Uuid,
+}
+
+#[derive(Serialize, Deserialize)]
+struct BookmarkDTO {
+    id: Uuid,
+    message_id: Option<Uuid>,
+    note: Option<String>,
+    folder: Option<String>,
+    unavailable: bool,
+    date_created: String,
+    message: Option<MessageDTO>,
+    media: Vec<MediaUploadDTO>,
+}
+
+impl BookmarkDTO {
+    fn new(
+        model: bookmark::Model,
+        message: Option<MessageDTO>,
+        media: Vec<MediaUploadDTO>,
+    ) -> Self {
+        Self {
+            id: model.id,
+            message_id: model.message_id,
+            note: model.note,
+            folder: model.folder,
+            unavailable: model.unavailable,
+            date_created: model.date_created.to_string(),
+            message,
+            media,
+        }
+    }
+}
+
+/// Saves a message for the current user, or updates the note and folder if it
+/// is already saved.
+#[post("/")]
+async fn save_bookmark(
+    req: HttpRequest,
+    app_state: web::Data<app_state::AppState>,
+    bookmark_save_dto: web::Json<BookmarkSaveDTO>,
+) -> Result<ApiResponse, ApiResponse> {
+    let user_id = get_user_id_from_http_request(req.clone())?;
+
+    if bookmark_save_dto
+        .folder
+        .as_ref()
+        .is_some_and(|folder| folder.is_empty() || folder.len() > MAX_BOOKMARK_FOLDER_LENGTH)
+    {
+        return Err(ApiResponse::new(
+            400,
+            format!(
+                "Folder name must be between 1 and {} characters.",
+                MAX_BOOKMARK_FOLDER_LENGTH
+            ),
+        ));
+    }
+
+    let message_model = message::Entity::find()
+        .filter(message::Column::Id.eq(bookmark_save_dto.message_id))
+        .filter(message::Column::Deleted.eq(false))
+        .one(&app_state.db)
+        .await
+        .map_err(|e| ApiResponse::new(500, e.to_string()))?
+        .ok_or(ApiResponse::new(404, "Message not found".to_string()))?;
+
+    check_user_can_read_message(&app_state, req.clone(), user_id, &message_model).await?;
+
+    let existing_bookmark = bookmark::Entity::find()
+        .filter(bookmark::Column::UserId.eq(user_id))
+        .filter(bookmark::Column::MessageId.eq(message_model.id))
+        .one(&app_state.db)
+        .await
+        .map_err(|e| ApiResponse::new(500, e.to_string()))?;
+
+    let bookmark_model = match existing_bookmark {
+        Some(existing_bookmark) => {
+            let mut existing_bookmark = existing_bookmark.into_active_model();
+            existing_bookmark.note = Set(bookmark_save_dto.note.clone());
+            existing_bookmark.folder = Set(bookmark_save_dto.folder.clone());
+
+            existing_bookmark
+                .update(&app_state.db)
+                .await
+                .map_err(|e| ApiResponse::new(500, e.to_string()))?
+        }
+        None => bookmark::ActiveModel {
+            id: Set(Uuid::new_v4()),
+            user_id: Set(user_id),
+            message_id: Set(Some(message_model.id)),
+            note: Set(bookmark_save_dto.note.clone()),
+            folder: Set(bookmark_save_dto.folder.clone()),
+            unavailable: Set(false),
+            date_created: Set(Utc::now().naive_utc()),
+        }
+        .insert(&app_state.db)
+        .await
+        .map_err(|e| ApiResponse::new(500, e.to_string()))?,
+    };
+
+    log_info(
+        req,
+        format!(
+            "Saved message {} as bookmark {}",
+            message_model.id, bookmark_model.id
+        ),
+    );
+
+    let media_dtos: Vec<MediaUploadDTO> = media::Entity::find()
+        .filter(media::Column::MessageId.eq(message_model.id))
+        .filter(media::Column::Deleted.eq(false))
+        .all(&app_state.db)
+        .await
+        .map_err(|e| ApiResponse::new(500, e.to_string()))?
+        .into_iter()
+        .map(MediaUploadDTO::from)
+        .collect();
+
+    let response_dto = BookmarkDTO::new(bookmark_model, Some(message_model.into()), media_dtos);
+
+    Ok(ApiResponse::new(
+        200,
+        serde_json::to_string(&response_dto).unwrap(),
+    ))
+}
+
+#[delete("/")]
+async fn delete_bookmark(
+    req: HttpRequest,
+    app_state: web::Data<app_state::AppState>,
+    bookmark_id_dto: web::Json<BookmarkIdDTO>,
+) -> Result<ApiResponse, ApiResponse> {
+    let user_id = get_user_id_from_http_request(req.clone())?;
+
+    let bookmark_model = bookmark::Entity::find()
+        .filter(bookmark::Column::Id.eq(bookmark_id_dto.id))
+        .filter(bookmark::Column::UserId.eq(user_id))
+        .one(&app_state.db)
+        .await
+        .map_err(|e| ApiResponse::new(500, e.to_string()))?
+        .ok_or(ApiResponse::new(404, "Bookmark not found.".to_string()))?;
+
+    bookmark_model
+        .clone()
+        .delete(&app_state.db)
+        .await
+        .map_err(|e| ApiResponse::new(500, e.to_string()))?;
+
+    log_info(req, format!("Deleted bookmark {}", bookmark_model.id));
+
+    let response_dto = BookmarkDTO::new(bookmark_model, None, vec![]);
+
+    Ok(ApiResponse::new(
+        200,
+        serde_json::to_string(&response_dto).unwrap(),
+    ))
+}
+
+/// Lists the current user's bookmarks, newest first, optionally in one folder.
+/// Bookmarks on messages the user can no longer read are left out.
+#[get("/")]
+async fn get_bookmarks(
+    req: HttpRequest,
+    app_state: web::Data<app_state::AppState>,
+    query: web::Query<HashMap<String, String>>,
+) -> Result<ApiResponse, ApiResponse> {
+    let user_id = get_user_id_from_http_request(req.clone())?;
+
+    let mut bookmark_query = bookmark::Entity::find()
+        .find_also_related(message::Entity)
+        .filter(bookmark::Column::UserId.eq(user_id))
+        .order_by_desc(bookmark::Column::DateCreated);
+
+    if let Some(folder) = query.get("folder") {
+        bookmark_query = bookmark_query.filter(bookmark::Column::Folder.eq(folder.as_str()));
+    }
+
+    let bookmarks = bookmark_query
+        .all(&app_state.db)
+        .await
+        .map_err(|e| ApiResponse::new(500, e.to_string()))?;
+
+    // Access is checked once per channel, DM or conversation
+    let mut readable_recipients: HashMap<(String, Uuid), bool> = HashMap::new();
+    let mut readable_bookmarks = vec![];
+
+    for (bookmark_model, message_model) in bookmarks {
+        let Some(message_model) = message_model.filter(|_| !bookmark_model.unavailable) else {
+            readable_bookmarks.push((bookmark_model, None));
+            continue;
+        };
+
+        let recipient = (
+            message_model.recipient_type.clone(),
+            if message_model.recipient_type == "USER" && message_model.reference_id == user_id {
+                message_model.user_id
+            } else {
+                message_model.reference_id
+            },
+        );
+
+        let can_read = match readable_recipients.get(&recipient) {
+            Some(can_read) => *can_read,
+            None => {
+                let can_read =
+                    check_user_can_read_message(&app_state, req.clone(), user_id, &message_model)
+                        .await
+                        .is_ok();
+
+                readable_recipients.insert(recipient, can_read);
+                can_read
+            }
+        };
+
+        if can_read {
+            readable_bookmarks.push((bookmark_model, Some(message_model)));
+        }
+    }
+
+    let message_ids: Vec<Uuid> = readable_bookmarks
+        .iter()
+        .filter_map(|(_, message_model)| message_model.as_ref().map(|message| message.id))
+        .collect();
+
+    let mut media_by_message_id: HashMap<Uuid, Vec<MediaUploadDTO>> = HashMap::new();
+
+    for media_model in media::Entity::find()
+        .filter(media::Column::MessageId.is_in(message_ids))
+        .filter(media::Column::Deleted.eq(false))
+        .all(&app_state.db)
+        .await
+        .map_err(|e| ApiResponse::new(500, e.to_string()))?
+    {
+        if let Some(message_id) = media_model.message_id {
+            media_by_message_id
+                .entry(message_id)
+                .or_default()
+                .push(media_model.into());
+        }
+    }
+
+    let response_dtos: Vec<BookmarkDTO> = readable_bookmarks
+        .into_iter()
+        .map(|(bookmark_model, message_model)| {
+            let media_dtos = message_model
+                .as_ref()
+                .and_then(|message| media_by_message_id.remove(&message.id))
+                .unwrap_or_default();
+
+            BookmarkDTO::new(
+                bookmark_model,
+                message_model.map(MessageDTO::from),
+                media_dtos,
+            )
+        })
+        .collect();
+
+    Ok(ApiResponse::new(
+        200,
+        serde_json::to_string(&response_dtos).unwrap(),
+    ))
+}
+
+/// Marks every bookmark of a message as unavailable once the message is gone.
+pub(crate) async fn mark_bookmarks_unavailable(
+    db: &DatabaseConnection,
+    message_ids: Vec<Uuid>,
+) -> Result<(), ApiResponse> {
+    bookmark::Entity::update_many()
+        .col_expr(bookmark::Column::Unavailable, Expr::value(true))
+        .filter(bookmark::Column::MessageId.is_in(message_ids))
+        .exec(db)
+        .await
+        .map_err(|e| ApiResponse::new(500, e.to_string()))?;
+
+    Ok(())
+}
@@ -1,13 +1,17 @@
+use crate::routes::handlers::message_handler::MAX_MESSAGE_TTL_SECONDS;
 use crate::utils::api_response::ApiResponse;
 use crate::utils::app_state;
 use crate::utils::chat::ChatRoom;
+use crate::utils::event::ChatEvent;
 use crate::utils::jwt::get_user_id_from_http_request;
 use crate::utils::logging::log_info;
 use crate::utils::message::{
-    send_update_status_from_channel_id, send_update_status_from_role_id_and_org_id,
+    get_user_ids_from_channel_id, get_user_ids_from_role_id_and_org_id, send_event_from_channel_id,
 };
 use crate::utils::organization_util::get_organization_id_from_user_id;
 use crate::utils::permissions::{check_permission, Permission};
+use crate::utils::retention::MAX_RETENTION_DAYS;
+use crate::utils::serde_util::deserialize_some;
 use actix_web::{delete, get, patch, post, web, HttpRequest, Result};
 use entity::{channel, channel_role_access, role, user, user_role_access};
 use sea_orm::ActiveValue::Set;
@@ -24,6 +28,22 @@ struct ChannelDTO {
     name: String,
     description: Option<String>,
     deleted: Option<bool>,
+    #[serde(default)]
+    message_ttl_seconds: Option<i64>,
+    #[serde(default)]
+    message_retention_days: Option<i64>,
+}
+
+/// Settings left out of the request keep their stored value, `null` clears them.
+#[derive(Serialize, Deserialize)]
+struct ChannelUpdateDTO {
+    id: Option<Uuid>,
+    name: String,
+    description: Option<String>,
+    #[serde(default, deserialize_with = "deserialize_some")]
+    message_ttl_seconds: Option<Option<i64>>,
+    #[serde(default, deserialize_with = "deserialize_some")]
+    message_retention_days: Option<Option<i64>>,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -38,6 +58,8 @@ impl From<channel::Model> for ChannelDTO {
             name: model.name,
             description: model.description,
             deleted: Some(model.deleted),
+            message_ttl_seconds: model.message_ttl_seconds,
+            message_retention_days: model.message_retention_days,
         }
     }
 }
@@ -72,10 +94,42 @@ pub async fn create_channel(
         ));
     }
 
+    if channel_dto
+        .message_ttl_seconds
+        .is_some_and(|message_ttl_seconds| {
+            !(1..=MAX_MESSAGE_TTL_SECONDS).contains(&message_ttl_seconds)
+        })
+    {
+        return Err(ApiResponse::new(
+            400,
+            format!(
+                "Message TTL must be between 1 and {} seconds.",
+                MAX_MESSAGE_TTL_SECONDS
+            ),
+        ));
+    }
+
+    if channel_dto
+        .message_retention_days
+        .is_some_and(|message_retention_days| {
+            !(1..=MAX_RETENTION_DAYS).contains(&message_retention_days)
+        })
+    {
+        return Err(ApiResponse::new(
+            400,
+            format!(
+                "Message retention must be between 1 and {} days.",
+                MAX_RETENTION_DAYS
+            ),
+        ));
+    }
+
     let channel_model = channel::ActiveModel {
         id: Set(Uuid::new_v4()),
         name: Set(channel_dto.name.clone()),
         description: Set(channel_dto.description.clone()),
+        message_ttl_seconds: Set(channel_dto.message_ttl_seconds),
+        message_retention_days: Set(channel_dto.message_retention_days),
         organization_id: Set(organization_id),
         ..Default::default()
     }
@@ -85,7 +139,15 @@ pub async fn create_channel(
 
     log_info(req, format!("Created channel {}", channel_model.id));
 
-    send_update_status_from_channel_id(channel_model.id, &app_state, &chat_room).await;
+    send_event_from_channel_id(
+        &ChatEvent::ChannelCreated {
+            channel: channel_model.clone().into(),
+        },
+        channel_model.id,
+        &app_state,
+        &chat_room,
+    )
+    .await;
 
     let response_dto: ChannelDTO = channel_model.into();
 
@@ -190,6 +252,8 @@ pub async fn delete_channel(
         description: Set(channel_model.description.clone()),
         deleted: Set(true),
         organization_id: Set(user_organization_id),
+        message_ttl_seconds: Set(channel_model.message_ttl_seconds),
+        message_retention_days: Set(channel_model.message_retention_days),
     };
 
     active_model
@@ -205,14 +269,19 @@ pub async fn delete_channel(
         .await
         .map_err(|e| ApiResponse::new(500, e.to_string()))?;
 
+    // Recipients are collected while the accesses still exist, so everyone is
+    // told once
+    let mut user_ids = get_user_ids_from_channel_id(channel_id_dto.id, &app_state).await;
+
     for channel_role_access in channel_role_accesses {
-        send_update_status_from_role_id_and_org_id(
-            channel_role_access.role_id,
-            user_organization_id,
-            &app_state,
-            &chat_room,
-        )
-        .await;
+        user_ids.extend(
+            get_user_ids_from_role_id_and_org_id(
+                channel_role_access.role_id,
+                user_organization_id,
+                &app_state,
+            )
+            .await,
+        );
         let active_model = channel_role_access::ActiveModel {
             id: Set(channel_role_access.id),
             channel_id: Set(channel_role_access.channel_id),
@@ -228,7 +297,14 @@ pub async fn delete_channel(
 
     log_info(req, format!("Deleted channel {}", channel_id_dto.id));
 
-    send_update_status_from_channel_id(channel_id_dto.id, &app_state, &chat_room).await;
+    let channel_deleted_event = ChatEvent::ChannelDeleted {
+        channel_id: channel_id_dto.id,
+    };
+
+    chat_room.send_message(
+        &user_ids.into_iter().collect::<Vec<Uuid>>(),
+        &channel_deleted_event.to_json(),
+    );
 
     Ok(ApiResponse::new(
         200,
@@ -240,7 +316,7 @@ pub async fn delete_channel(
 pub async fn update_channel(
     app_state: web::Data<app_state::AppState>,
     req: HttpRequest,
-    channel_dto: web::Json<ChannelDTO>,
+    channel_dto: web::Json<ChannelUpdateDTO>,
     chat_room: web::Data<Arc<ChatRoom>>,
 ) -> Result<ApiResponse, ApiResponse> {
     let has_manage_channels =
@@ -260,6 +336,38 @@ pub async fn update_channel(
         ));
     }
 
+    if channel_dto
+        .message_ttl_seconds
+        .flatten()
+        .is_some_and(|message_ttl_seconds| {
+            !(1..=MAX_MESSAGE_TTL_SECONDS).contains(&message_ttl_seconds)
+        })
+    {
+        return Err(ApiResponse::new(
+            400,
+            format!(
+                "Message TTL must be between 1 and {} seconds.",
+                MAX_MESSAGE_TTL_SECONDS
+            ),
+        ));
+    }
+
+    if channel_dto
+        .message_retention_days
+        .flatten()
+        .is_some_and(|message_retention_days| {
+            !(1..=MAX_RETENTION_DAYS).contains(&message_retention_days)
+        })
+    {
+        return Err(ApiResponse::new(
+            400,
+            format!(
+                "Message retention must be between 1 and {} days.",
+                MAX_RETENTION_DAYS
+            ),
+        ));
+    }
+
     let user_organization_id = get_organization_id_from_user_id(
         &app_state.db,
         get_user_id_from_http_request(req.clone())?,
@@ -274,19 +382,25 @@ pub async fn update_channel(
         .await
         .map_err(|e| ApiResponse::new(500, e.to_string()))?;
 
-    if existing_channel.is_none() {
+    let Some(existing_channel) = existing_channel else {
         return Err(ApiResponse::new(404, "Channel not found.".to_string()));
-    }
+    };
 
     let channel_model = channel::ActiveModel {
         id: Set(channel_dto.id.unwrap()),
         name: Set(channel_dto.name.clone()),
         description: Set(channel_dto.description.clone()),
+        message_ttl_seconds: Set(channel_dto
+            .message_ttl_seconds
+            .unwrap_or(existing_channel.message_ttl_seconds)),
+        message_retention_days: Set(channel_dto
+            .message_retention_days
+            .unwrap_or(existing_channel.message_retention_days)),
         organization_id: Set(user_organization_id),
         ..Default::default()
     };
 
-    channel_model
+    let channel_model = channel_model
         .update(&app_state.db)
         .await
         .map_err(|e| ApiResponse::new(500, e.to_string()))?;
@@ -298,9 +412,19 @@ pub async fn update_channel(
         name: channel_dto.name.clone(),
         description: channel_dto.description.clone(),
         deleted: None,
+        message_ttl_seconds: channel_model.message_ttl_seconds,
+        message_retention_days: channel_model.message_retention_days,
     };
 
-    send_update_status_from_channel_id(channel_dto.id.unwrap(), &app_state, &chat_room).await;
+    send_event_from_channel_id(
+        &ChatEvent::ChannelUpdated {
+            channel: channel_model.into(),
+        },
+        channel_dto.id.unwrap(),
+        &app_state,
+        &chat_room,
+    )
+    .await;
 
     Ok(ApiResponse::new(
         200,
@@ -1,9 +1,10 @@
 use crate::utils::api_response::ApiResponse;
 use crate::utils::app_state;
 use crate::utils::chat::ChatRoom;
+use crate::utils::event::ChatEvent;
 use crate::utils::jwt::get_user_id_from_http_request;
 use crate::utils::logging::log_info;
-use crate::utils::message::send_update_status_from_role_id_and_org_id;
+use crate::utils::message::send_event_from_role_id_and_org_id;
 use crate::utils::organization_util::get_organization_id_from_user_id;
 use crate::utils::permissions::{check_permission, Permission};
 use actix_web::{delete, get, post, web, HttpRequest, Result};
@@ -114,7 +115,11 @@ pub async fn create_channel_role_access(
 
     let response_dto: ChannelRoleAccessDTO = channel_role_access_model.into();
 
-    send_update_status_from_role_id_and_org_id(
+    send_event_from_role_id_and_org_id(
+        &ChatEvent::ChannelAccessGranted {
+            channel_id: response_dto.channel_id,
+            role_id: response_dto.role_id,
+        },
         response_dto.role_id,
         user_organization_id,
         &app_state,
@@ -251,7 +256,11 @@ pub async fn delete(
 
     let response_dto: ChannelRoleAccessDTO = channel_role_access_model.into();
 
-    send_update_status_from_role_id_and_org_id(
+    send_event_from_role_id_and_org_id(
+        &ChatEvent::ChannelAccessRevoked {
+            channel_id: response_dto.channel_id,
+            role_id: response_dto.role_id,
+        },
         response_dto.role_id,
         user_organization_id,
         &app_state,
@@ -2,15 +2,22 @@ use actix_web::{get, web, Error, HttpRequest, HttpResponse, Result};
 use actix_web_actors::ws;
 use std::collections::HashMap;
 use std::sync::Arc;
+use uuid::Uuid;
 
+use crate::utils::app_state::AppState;
 use crate::utils::chat::{ChatRoom, MyWebSocket};
-use crate::utils::jwt::get_user_id_from_token;
+use crate::utils::jwt::get_claims_from_token;
+use crate::utils::organization_util::get_organization_id_from_user_id;
+use crate::utils::socket_command::get_user_is_active;
+
+const MAX_DEVICE_LABEL_LENGTH: usize = 64;
 
 #[get("/ws")]
 pub async fn chat_ws(
     req: HttpRequest,
     stream: web::Payload,
     room: web::Data<Arc<ChatRoom>>,
+    app_state: web::Data<AppState>,
     query: web::Query<HashMap<String, String>>,
 ) -> Result<HttpResponse, Error> {
     let token = match query.get("token") {
@@ -20,16 +27,52 @@ pub async fn chat_ws(
         }
     };
 
-    let user_id = match get_user_id_from_token(token.to_string()) {
-        Ok(user_id) => user_id,
+    let claims = match get_claims_from_token(token.to_string()) {
+        Ok(claims) => claims,
         Err(_) => {
             return Err(actix_web::error::ErrorBadRequest("Invalid token"));
         }
     };
 
+    let user_id = claims.id;
+
+    if !get_user_is_active(&app_state.db, user_id).await? {
+        return Err(actix_web::error::ErrorUnauthorized("User not found"));
+    }
+
+    let organization_id = get_organization_id_from_user_id(&app_state.db, user_id).await?;
+
+    let device = query.get("device").cloned();
+
+    if device
+        .as_ref()
+        .is_some_and(|device| device.chars().count() > MAX_DEVICE_LABEL_LENGTH)
+    {
+        return Err(actix_web::error::ErrorBadRequest(format!(
+            "Device must be at most {} characters",
+            MAX_DEVICE_LABEL_LENGTH
+        )));
+    }
+
+    let resume_from = match query.get("resume_from") {
+        Some(resume_from) => match resume_from.parse::<u64>() {
+            Ok(resume_from) => Some(resume_from),
+            Err(_) => {
+                return Err(actix_web::error::ErrorBadRequest("Invalid resume_from"));
+            }
+        },
+        None => None,
+    };
+
     let ws = MyWebSocket {
-        room: room.get_ref().clone(),
+        app_state,
+        room,
         user_id,
+        organization_id,
+        session_id: Uuid::new_v4(),
+        device,
+        resume_from,
+        token_expires_at: claims.exp,
     };
     ws::start(ws, &req, stream)
 }
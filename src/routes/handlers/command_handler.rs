@@ -0,0 +1,846 @@
+use crate::routes::handlers::conversation_handler::add_conversation_participant;
+use crate::routes::handlers::message_handler::{publish_message, MessageDTO, MessageSendDTO};
+use crate::routes::handlers::poll_handler::{validate_poll, PollCreateDTO};
+use crate::routes::handlers::user_channel_view_handler::set_muted_until;
+use crate::utils::api_response::ApiResponse;
+use crate::utils::app_state;
+use crate::utils::chat::ChatRoom;
+use crate::utils::event::ChatEvent;
+use crate::utils::jwt::get_user_id_from_http_request;
+use crate::utils::logging::log_info;
+use crate::utils::mention::parse_mentions;
+use crate::utils::message::send_event_from_channel_id;
+use crate::utils::organization_util::get_organization_id_from_user_id;
+use crate::utils::permissions::{check_permission, check_permission_by_user_id, Permission};
+use crate::utils::s3;
+use crate::utils::webhook::{check_webhook_url, MAX_WEBHOOK_RESPONSE_BYTES};
+use actix_web::{delete, get, post, web, HttpRequest, Result};
+use chrono::{Duration, Utc};
+use entity::{channel, slash_command, user};
+use sea_orm::ActiveValue::Set;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, EntityTrait, IntoActiveModel, ModelTrait, QueryFilter,
+    QueryOrder,
+};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use uuid::Uuid;
+
+const MAX_COMMAND_NAME_LENGTH: usize = 32;
+const MAX_MUTE_MINUTES: i64 = 60 * 24 * 365;
+
+/// Built-in commands as (name, usage, description). Integrations can't
+/// register these names.
+const BUILT_IN_COMMANDS: [(&str, &str, &str); 5] = [
+    ("help", "/help", "List the available commands"),
+    ("invite", "/invite @user", "Add a user to this conversation"),
+    (
+        "mute",
+        "/mute 1h | off",
+        "Mute this chat for a number of minutes (m), hours (h), days (d) or weeks (w)",
+    ),
+    (
+        "poll",
+        "/poll \"Question\" \"Option\" \"Option\"",
+        "Start a poll with up to ten options",
+    ),
+    ("topic", "/topic [text]", "Set or clear the channel topic"),
+];
+
+#[derive(Serialize, Deserialize)]
+struct SlashCommandDTO {
+    id: Option<Uuid>,
+    name: String,
+    usage: Option<String>,
+    description: Option<String>,
+    built_in: bool,
+}
+
+#[derive(Serialize, Deserialize)]
+struct SlashCommandCreateDTO {
+    name: String,
+    usage: Option<String>,
+    description: Option<String>,
+    webhook_url: String,
+}
+
+/// Returned once on registration so the integration can verify requests.
+#[derive(Serialize, Deserialize)]
+struct SlashCommandCreatedDTO {
+    id: Uuid,
+    name: String,
+    usage: Option<String>,
+    description: Option<String>,
+    webhook_url: String,
+    token: String,
+}
+
+#[derive(Serialize, Deserialize)]
+struct SlashCommandIdDTO {
+    id: Uuid,
+}
+
+/// Body posted to an integration's webhook.
+#[derive(Serialize, Deserialize)]
+struct SlashCommandWebhookDTO {
+    token: String,
+    command: String,
+    text: String,
+    user_id: Uuid,
+    username: String,
+    organization_id: Uuid,
+    recipient_type: String,
+    reference_id: Uuid,
+    parent_id: Option<Uuid>,
+}
+
+/// What an integration answers with. Responses are ephemeral unless
+/// `response_type` is `in_channel`.
+#[derive(Serialize, Deserialize)]
+struct SlashCommandWebhookResponseDTO {
+    #[serde(default)]
+    text: String,
+    #[serde(default)]
+    response_type: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub(crate) struct CommandResponseDTO {
+    message_type: String,
+    pub(crate) command: String,
+    text: String,
+    ephemeral: bool,
+    recipient_type: String,
+    reference_id: Uuid,
+    /// The message posted for public responses.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    message: Option<MessageDTO>,
+}
+
+#[get("/")]
+pub async fn get_commands(
+    req: HttpRequest,
+    app_state: web::Data<app_state::AppState>,
+) -> Result<ApiResponse, ApiResponse> {
+    let user_id = get_user_id_from_http_request(req)?;
+
+    let organization_id = get_organization_id_from_user_id(&app_state.db, user_id).await?;
+
+    let mut response_dtos: Vec<SlashCommandDTO> = BUILT_IN_COMMANDS
+        .iter()
+        .map(|(name, usage, description)| SlashCommandDTO {
+            id: None,
+            name: name.to_string(),
+            usage: Some(usage.to_string()),
+            description: Some(description.to_string()),
+            built_in: true,
+        })
+        .collect();
+
+    let integration_commands = slash_command::Entity::find()
+        .filter(slash_command::Column::OrganizationId.eq(organization_id))
+        .order_by_asc(slash_command::Column::Name)
+        .all(&app_state.db)
+        .await
+        .map_err(|e| ApiResponse::new(500, e.to_string()))?;
+
+    response_dtos.extend(
+        integration_commands
+            .into_iter()
+            .map(|command_model| SlashCommandDTO {
+                id: Some(command_model.id),
+                name: command_model.name,
+                usage: command_model.usage,
+                description: command_model.description,
+                built_in: false,
+            }),
+    );
+
+    Ok(ApiResponse::new(
+        200,
+        serde_json::to_string(&response_dtos).unwrap(),
+    ))
+}
+
+#[post("/")]
+pub async fn register_command(
+    req: HttpRequest,
+    app_state: web::Data<app_state::AppState>,
+    command_dto: web::Json<SlashCommandCreateDTO>,
+) -> Result<ApiResponse, ApiResponse> {
+    let user_id = get_user_id_from_http_request(req.clone())?;
+
+    let is_admin = check_permission(&app_state.db, req.clone(), Permission::Administrator).await;
+
+    if !is_admin {
+        return Err(ApiResponse::new(
+            403,
+            "You do not have permission to manage commands.".to_string(),
+        ));
+    }
+
+    let name = command_dto.name.trim().to_lowercase();
+
+    if name.is_empty()
+        || name.len() > MAX_COMMAND_NAME_LENGTH
+        || !name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        return Err(ApiResponse::new(
+            400,
+            format!(
+                "Command name must be between 1 and {} letters, digits, dashes or underscores.",
+                MAX_COMMAND_NAME_LENGTH
+            ),
+        ));
+    }
+
+    if BUILT_IN_COMMANDS
+        .iter()
+        .any(|(built_in_name, _, _)| *built_in_name == name)
+    {
+        return Err(ApiResponse::new(
+            409,
+            format!("/{} is a built-in command.", name),
+        ));
+    }
+
+    let webhook_url = command_dto.webhook_url.trim().to_string();
+
+    check_webhook_url(&webhook_url)
+        .await
+        .map_err(|e| ApiResponse::new(400, e))?;
+
+    let organization_id = get_organization_id_from_user_id(&app_state.db, user_id).await?;
+
+    let existing_command = slash_command::Entity::find()
+        .filter(slash_command::Column::OrganizationId.eq(organization_id))
+        .filter(slash_command::Column::Name.eq(name.clone()))
+        .one(&app_state.db)
+        .await
+        .map_err(|e| ApiResponse::new(500, e.to_string()))?;
+
+    if existing_command.is_some() {
+        return Err(ApiResponse::new(
+            409,
+            format!("/{} is already registered.", name),
+        ));
+    }
+
+    let command_model = slash_command::ActiveModel {
+        id: Set(Uuid::new_v4()),
+        organization_id: Set(organization_id),
+        name: Set(name),
+        description: Set(command_dto.description.clone()),
+        usage: Set(command_dto.usage.clone()),
+        webhook_url: Set(webhook_url),
+        token: Set(s3::generate_random_session_id()),
+        created_by: Set(user_id),
+        date_created: Set(Utc::now().naive_utc()),
+    }
+    .insert(&app_state.db)
+    .await
+    .map_err(|e| ApiResponse::new(500, e.to_string()))?;
+
+    log_info(req, format!("Registered command /{}", command_model.name));
+
+    let response_dto = SlashCommandCreatedDTO {
+        id: command_model.id,
+        name: command_model.name,
+        usage: command_model.usage,
+        description: command_model.description,
+        webhook_url: command_model.webhook_url,
+        token: command_model.token,
+    };
+
+    Ok(ApiResponse::new(
+        200,
+        serde_json::to_string(&response_dto).unwrap(),
+    ))
+}
+
+#[delete("/")]
+pub async fn delete_command(
+    req: HttpRequest,
+    app_state: web::Data<app_state::AppState>,
+    command_id_dto: web::Json<SlashCommandIdDTO>,
+) -> Result<ApiResponse, ApiResponse> {
+    let user_id = get_user_id_from_http_request(req.clone())?;
+
+    let is_admin = check_permission(&app_state.db, req.clone(), Permission::Administrator).await;
+
+    if !is_admin {
+        return Err(ApiResponse::new(
+            403,
+            "You do not have permission to manage commands.".to_string(),
+        ));
+    }
+
+    let organization_id = get_organization_id_from_user_id(&app_state.db, user_id).await?;
+
+    let command_model = slash_command::Entity::find()
+        .filter(slash_command::Column::Id.eq(command_id_dto.id))
+        .filter(slash_command::Column::OrganizationId.eq(organization_id))
+        .one(&app_state.db)
+        .await
+        .map_err(|e| ApiResponse::new(500, e.to_string()))?
+        .ok_or(ApiResponse::new(404, "Command not found.".to_string()))?;
+
+    command_model
+        .clone()
+        .delete(&app_state.db)
+        .await
+        .map_err(|e| ApiResponse::new(500, e.to_string()))?;
+
+    log_info(req, format!("Deleted command /{}", command_model.name));
+
+    Ok(ApiResponse::new(200, "Command deleted".to_string()))
+}
+
+/// Splits `/name args` into the lowercased name and the trimmed arguments.
+/// Content starting with `//` is an escaped message, not a command.
+pub(crate) fn parse_command(content: &str) -> Option<(String, String)> {
+    let command = content.trim_start().strip_prefix('/')?;
+
+    if command.starts_with('/') {
+        return None;
+    }
+
+    let (name, args) = command
+        .split_once(char::is_whitespace)
+        .unwrap_or((command, ""));
+
+    if name.is_empty() {
+        return None;
+    }
+
+    Some((name.to_lowercase(), args.trim().to_string()))
+}
+
+/// Runs a command sent through `send_message`. The caller has already passed
+/// the checks for sending to the target chat.
+pub(crate) async fn execute_command(
+    app_state: &web::Data<app_state::AppState>,
+    chat_room: &web::Data<Arc<ChatRoom>>,
+    user_id: Uuid,
+    message_send_dto: &MessageSendDTO,
+    name: String,
+    args: String,
+) -> Result<CommandResponseDTO, ApiResponse> {
+    let response = match name.as_str() {
+        "help" => CommandResponse::Ephemeral(get_help_text(app_state, user_id).await?),
+        "invite" => run_invite(app_state, chat_room, user_id, message_send_dto, &args).await?,
+        "mute" => run_mute(app_state, user_id, message_send_dto, &args).await?,
+        "poll" => run_poll(app_state, chat_room, user_id, message_send_dto, &args).await?,
+        "topic" => run_topic(app_state, chat_room, user_id, message_send_dto, &args).await?,
+        _ => run_webhook_command(app_state, user_id, message_send_dto, &name, &args).await?,
+    };
+
+    let (text, message) = match response {
+        CommandResponse::Ephemeral(text) => (text, None),
+        CommandResponse::Public(text) => {
+            let message_dto = publish_command_message(
+                app_state,
+                chat_room,
+                user_id,
+                message_send_dto,
+                text.clone(),
+            )
+            .await?;
+
+            (text, Some(message_dto))
+        }
+        CommandResponse::Published(message_dto) => (
+            message_dto.content.clone().unwrap_or_default(),
+            Some(*message_dto),
+        ),
+    };
+
+    let response_dto = CommandResponseDTO {
+        message_type: "COMMAND_RESPONSE".to_string(),
+        command: name,
+        ephemeral: message.is_none(),
+        text,
+        recipient_type: message_send_dto.recipient_type.clone(),
+        reference_id: message_send_dto.reference_id,
+        message,
+    };
+
+    // Public responses reach everyone as a regular message, ephemeral ones
+    // only go to the caller's sessions
+    if response_dto.ephemeral {
+        chat_room.send_message(
+            &vec![user_id],
+            &serde_json::to_string(&response_dto).unwrap(),
+        );
+    }
+
+    Ok(response_dto)
+}
+
+enum CommandResponse {
+    Ephemeral(String),
+    Public(String),
+    Published(Box<MessageDTO>),
+}
+
+async fn get_help_text(
+    app_state: &web::Data<app_state::AppState>,
+    user_id: Uuid,
+) -> Result<String, ApiResponse> {
+    let organization_id = get_organization_id_from_user_id(&app_state.db, user_id).await?;
+
+    let integration_commands = slash_command::Entity::find()
+        .filter(slash_command::Column::OrganizationId.eq(organization_id))
+        .order_by_asc(slash_command::Column::Name)
+        .all(&app_state.db)
+        .await
+        .map_err(|e| ApiResponse::new(500, e.to_string()))?;
+
+    let mut lines: Vec<String> = BUILT_IN_COMMANDS
+        .iter()
+        .map(|(_, usage, description)| format!("{} - {}", usage, description))
+        .collect();
+
+    lines.extend(integration_commands.into_iter().map(|command_model| {
+        format!(
+            "{} - {}",
+            command_model
+                .usage
+                .unwrap_or_else(|| format!("/{}", command_model.name)),
+            command_model.description.unwrap_or_default()
+        )
+    }));
+
+    Ok(lines.join("\n"))
+}
+
+async fn run_invite(
+    app_state: &web::Data<app_state::AppState>,
+    chat_room: &web::Data<Arc<ChatRoom>>,
+    user_id: Uuid,
+    message_send_dto: &MessageSendDTO,
+    args: &str,
+) -> Result<CommandResponse, ApiResponse> {
+    if message_send_dto.recipient_type != "CONVERSATION" {
+        return Err(ApiResponse::new(
+            400,
+            "/invite can only be used in group conversations.".to_string(),
+        ));
+    }
+
+    let organization_id = get_organization_id_from_user_id(&app_state.db, user_id).await?;
+
+    // Accepts both a resolved `<@user_id>` mention and a plain `@username`
+    let invited_user_condition = match parse_mentions(args).user_ids.into_iter().next() {
+        Some(invited_user_id) => user::Column::Id.eq(invited_user_id),
+        None => {
+            let username = args.trim().trim_start_matches('@');
+
+            if username.is_empty() || username.contains(char::is_whitespace) {
+                return Err(ApiResponse::new(400, "Usage: /invite @user".to_string()));
+            }
+
+            user::Column::Username.eq(username)
+        }
+    };
+
+    let invited_user = user::Entity::find()
+        .filter(invited_user_condition)
+        .filter(user::Column::OrganizationId.eq(organization_id))
+        .filter(user::Column::Deleted.eq(false))
+        .one(&app_state.db)
+        .await
+        .map_err(|e| ApiResponse::new(500, e.to_string()))?
+        .ok_or(ApiResponse::new(400, "User not found".to_string()))?;
+
+    add_conversation_participant(
+        app_state,
+        chat_room,
+        user_id,
+        message_send_dto.reference_id,
+        invited_user.id,
+    )
+    .await?;
+
+    Ok(CommandResponse::Public(format!(
+        "invited <@{}> to the conversation",
+        invited_user.id
+    )))
+}
+
+async fn run_mute(
+    app_state: &web::Data<app_state::AppState>,
+    user_id: Uuid,
+    message_send_dto: &MessageSendDTO,
+    args: &str,
+) -> Result<CommandResponse, ApiResponse> {
+    let muted_until = if args.eq_ignore_ascii_case("off") {
+        None
+    } else {
+        let duration = parse_duration(args).ok_or(ApiResponse::new(
+            400,
+            "Usage: /mute 30m, /mute 1h, /mute 2d, /mute 1w or /mute off".to_string(),
+        ))?;
+
+        Some(Utc::now().naive_utc() + duration)
+    };
+
+    set_muted_until(
+        &app_state.db,
+        user_id,
+        &message_send_dto.recipient_type,
+        message_send_dto.reference_id,
+        muted_until,
+    )
+    .await?;
+
+    Ok(CommandResponse::Ephemeral(match muted_until {
+        Some(muted_until) => format!("Muted until {}", muted_until.and_utc().to_rfc3339()),
+        None => "Unmuted".to_string(),
+    }))
+}
+
+/// Parses durations like `30m`, `1h`, `2d` and `1w`, up to a year.
+fn parse_duration(value: &str) -> Option<Duration> {
+    let value = value.trim().to_lowercase();
+    let unit = value.chars().last()?;
+    let amount = value.strip_suffix(unit)?.parse::<i64>().ok()?;
+
+    let minutes = match unit {
+        'm' => amount,
+        'h' => amount.checked_mul(60)?,
+        'd' => amount.checked_mul(60 * 24)?,
+        'w' => amount.checked_mul(60 * 24 * 7)?,
+        _ => return None,
+    };
+
+    if minutes <= 0 || minutes > MAX_MUTE_MINUTES {
+        return None;
+    }
+
+    Some(Duration::minutes(minutes))
+}
+
+async fn run_poll(
+    app_state: &web::Data<app_state::AppState>,
+    chat_room: &web::Data<Arc<ChatRoom>>,
+    user_id: Uuid,
+    message_send_dto: &MessageSendDTO,
+    args: &str,
+) -> Result<CommandResponse, ApiResponse> {
+    let mut values = split_quoted_args(args)
+        .ok_or(ApiResponse::new(
+            400,
+            "A quoted value in /poll is missing its closing quote.".to_string(),
+        ))?
+        .into_iter();
+
+    let question = values.next().ok_or(ApiResponse::new(
+        400,
+        "Usage: /poll \"Question\" \"Option\" \"Option\"".to_string(),
+    ))?;
+
+    let poll_send_dto = MessageSendDTO {
+        content: Some(question),
+        message_type: "POLL".to_string(),
+        recipient_type: message_send_dto.recipient_type.clone(),
+        reference_id: message_send_dto.reference_id,
+        media_ids: Vec::new(),
+        parent_id: message_send_dto.parent_id,
+        send_at: None,
+        ttl_seconds: message_send_dto.ttl_seconds,
+        client_message_id: message_send_dto.client_message_id.clone(),
+        poll: Some(PollCreateDTO::new(values.collect())),
+    };
+
+    validate_poll(&poll_send_dto)?;
+
+    let message_dto = publish_message(app_state, chat_room, user_id, &poll_send_dto).await?;
+
+    Ok(CommandResponse::Published(Box::new(message_dto)))
+}
+
+/// Splits on whitespace, keeping double-quoted values together. Returns `None`
+/// when a quote is left open.
+fn split_quoted_args(args: &str) -> Option<Vec<String>> {
+    let mut values = Vec::new();
+    let mut current = String::new();
+    let mut in_quotes = false;
+
+    for c in args.chars() {
+        if c == '"' {
+            in_quotes = !in_quotes;
+            push_value(&mut values, &mut current);
+        } else if c.is_whitespace() && !in_quotes {
+            push_value(&mut values, &mut current);
+        } else {
+            current.push(c);
+        }
+    }
+
+    if in_quotes {
+        return None;
+    }
+
+    push_value(&mut values, &mut current);
+
+    Some(values)
+}
+
+fn push_value(values: &mut Vec<String>, current: &mut String) {
+    let value = std::mem::take(current);
+
+    if !value.trim().is_empty() {
+        values.push(value.trim().to_string());
+    }
+}
+
+async fn run_topic(
+    app_state: &web::Data<app_state::AppState>,
+    chat_room: &web::Data<Arc<ChatRoom>>,
+    user_id: Uuid,
+    message_send_dto: &MessageSendDTO,
+    args: &str,
+) -> Result<CommandResponse, ApiResponse> {
+    if message_send_dto.recipient_type != "CHANNEL" {
+        return Err(ApiResponse::new(
+            400,
+            "/topic can only be used in channels.".to_string(),
+        ));
+    }
+
+    let has_manage_channels =
+        check_permission_by_user_id(&app_state.db, user_id, Permission::ManageChannels).await;
+
+    if !has_manage_channels {
+        return Err(ApiResponse::new(
+            403,
+            "You do not have permission to manage channels.".to_string(),
+        ));
+    }
+
+    let channel_model = channel::Entity::find()
+        .filter(channel::Column::Id.eq(message_send_dto.reference_id))
+        .filter(channel::Column::Deleted.eq(false))
+        .one(&app_state.db)
+        .await
+        .map_err(|e| ApiResponse::new(500, e.to_string()))?
+        .ok_or(ApiResponse::new(404, "Channel not found.".to_string()))?;
+
+    let topic = (!args.is_empty()).then(|| args.to_string());
+
+    let mut channel_model = channel_model.into_active_model();
+    channel_model.description = Set(topic.clone());
+
+    let channel_model = channel_model
+        .update(&app_state.db)
+        .await
+        .map_err(|e| ApiResponse::new(500, e.to_string()))?;
+
+    send_event_from_channel_id(
+        &ChatEvent::ChannelUpdated {
+            channel: channel_model.into(),
+        },
+        message_send_dto.reference_id,
+        app_state,
+        chat_room,
+    )
+    .await;
+
+    Ok(CommandResponse::Public(match topic {
+        Some(topic) => format!("changed the topic to: {}", topic),
+        None => "cleared the topic".to_string(),
+    }))
+}
+
+/// Forwards the command to the integration registered under `name` and relays
+/// its answer. Public answers are posted as the calling user.
+async fn run_webhook_command(
+    app_state: &web::Data<app_state::AppState>,
+    user_id: Uuid,
+    message_send_dto: &MessageSendDTO,
+    name: &str,
+    args: &str,
+) -> Result<CommandResponse, ApiResponse> {
+    let user_model = user::Entity::find()
+        .filter(user::Column::Id.eq(user_id))
+        .one(&app_state.db)
+        .await
+        .map_err(|e| ApiResponse::new(500, e.to_string()))?
+        .ok_or(ApiResponse::new(404, "User not found".to_string()))?;
+
+    let command_model = slash_command::Entity::find()
+        .filter(slash_command::Column::OrganizationId.eq(user_model.organization_id))
+        .filter(slash_command::Column::Name.eq(name))
+        .one(&app_state.db)
+        .await
+        .map_err(|e| ApiResponse::new(500, e.to_string()))?
+        .ok_or(ApiResponse::new(
+            404,
+            format!(
+                "Unknown command /{}. Start the message with // to send it as text.",
+                name
+            ),
+        ))?;
+
+    let webhook_dto = SlashCommandWebhookDTO {
+        token: command_model.token,
+        command: format!("/{}", command_model.name),
+        text: args.to_string(),
+        user_id,
+        username: user_model.username,
+        organization_id: user_model.organization_id,
+        recipient_type: message_send_dto.recipient_type.clone(),
+        reference_id: message_send_dto.reference_id,
+        parent_id: message_send_dto.parent_id,
+    };
+
+    let unavailable = || ApiResponse::new(502, format!("/{} did not respond.", command_model.name));
+
+    // Checked again in case the URL was stored before the rules applied,
+    // the client's resolver covers DNS changes since
+    let webhook_url = check_webhook_url(&command_model.webhook_url)
+        .await
+        .map_err(|_| unavailable())?;
+
+    let mut response = app_state
+        .webhook_client
+        .post(webhook_url)
+        .header(reqwest::header::CONTENT_TYPE, "application/json")
+        .body(serde_json::to_string(&webhook_dto).unwrap())
+        .send()
+        .await
+        .map_err(|_| unavailable())?;
+
+    if !response.status().is_success() {
+        return Err(unavailable());
+    }
+
+    if response
+        .content_length()
+        .is_some_and(|length| length > MAX_WEBHOOK_RESPONSE_BYTES as u64)
+    {
+        return Err(unavailable());
+    }
+
+    let mut response_body: Vec<u8> = Vec::new();
+
+    while let Some(chunk) = response.chunk().await.map_err(|_| unavailable())? {
+        if response_body.len() + chunk.len() > MAX_WEBHOOK_RESPONSE_BYTES {
+            return Err(unavailable());
+        }
+
+        response_body.extend_from_slice(&chunk);
+    }
+
+    let response_dto: SlashCommandWebhookResponseDTO =
+        serde_json::from_slice(&response_body).map_err(|_| unavailable())?;
+
+    if response_dto.response_type.as_deref() == Some("in_channel")
+        && !response_dto.text.trim().is_empty()
+    {
+        return Ok(CommandResponse::Public(response_dto.text));
+    }
+
+    Ok(CommandResponse::Ephemeral(response_dto.text))
+}
+
+async fn publish_command_message(
+    app_state: &web::Data<app_state::AppState>,
+    chat_room: &web::Data<Arc<ChatRoom>>,
+    user_id: Uuid,
+    message_send_dto: &MessageSendDTO,
+    content: String,
+) -> Result<MessageDTO, ApiResponse> {
+    let command_message_dto = MessageSendDTO {
+        content: Some(content),
+        message_type: "MESSAGE".to_string(),
+        recipient_type: message_send_dto.recipient_type.clone(),
+        reference_id: message_send_dto.reference_id,
+        media_ids: Vec::new(),
+        parent_id: message_send_dto.parent_id,
+        send_at: None,
+        ttl_seconds: message_send_dto.ttl_seconds,
+        client_message_id: message_send_dto.client_message_id.clone(),
+        poll: None,
+    };
+
+    publish_message(app_state, chat_room, user_id, &command_message_dto).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn command(name: &str, args: &str) -> Option<(String, String)> {
+        Some((name.to_string(), args.to_string()))
+    }
+
+    #[test]
+    fn parses_commands() {
+        assert_eq!(parse_command("/shrug"), command("shrug", ""));
+        assert_eq!(parse_command("  /Mute  1h  "), command("mute", "1h"));
+        assert_eq!(
+            parse_command("/poll \"Lunch?\" Pizza"),
+            command("poll", "\"Lunch?\" Pizza")
+        );
+        assert_eq!(parse_command("/café au lait"), command("café", "au lait"));
+    }
+
+    #[test]
+    fn ignores_content_that_is_not_a_command() {
+        assert_eq!(parse_command(""), None);
+        assert_eq!(parse_command("/"), None);
+        assert_eq!(parse_command("/ mute"), None);
+        assert_eq!(parse_command("hello /shrug"), None);
+        assert_eq!(parse_command("//shrug"), None);
+        assert_eq!(parse_command("  //not a command"), None);
+    }
+
+    #[test]
+    fn parses_durations() {
+        assert_eq!(parse_duration("30m"), Some(Duration::minutes(30)));
+        assert_eq!(parse_duration(" 1H "), Some(Duration::hours(1)));
+        assert_eq!(parse_duration("2d"), Some(Duration::days(2)));
+        assert_eq!(parse_duration("52w"), Some(Duration::weeks(52)));
+    }
+
+    #[test]
+    fn rejects_invalid_durations() {
+        for value in ["", "m", "10", "1.5h", "5s", "5é", "５m", "h1"] {
+            assert_eq!(parse_duration(value), None, "{}", value);
+        }
+    }
+
+    #[test]
+    fn rejects_out_of_range_durations() {
+        for value in ["0m", "-5m", "366d", "53w", "9223372036854775807w"] {
+            assert_eq!(parse_duration(value), None, "{}", value);
+        }
+    }
+
+    #[test]
+    fn splits_quoted_args() {
+        assert_eq!(split_quoted_args(""), Some(vec![]));
+        assert_eq!(
+            split_quoted_args("\"Lunch today?\" Pizza  \"Sushi rolls\""),
+            Some(vec![
+                "Lunch today?".to_string(),
+                "Pizza".to_string(),
+                "Sushi rolls".to_string(),
+            ])
+        );
+        assert_eq!(
+            split_quoted_args("\"Café ☕\" \"\" \"  \" Tea"),
+            Some(vec!["Café ☕".to_string(), "Tea".to_string()])
+        );
+    }
+
+    #[test]
+    fn rejects_unbalanced_quotes() {
+        assert_eq!(split_quoted_args("\"Lunch?"), None);
+        assert_eq!(split_quoted_args("\"Lunch?\" \"Pizza"), None);
+    }
+}
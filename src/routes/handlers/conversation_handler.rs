@@ -0,0 +1,454 @@
+use crate::utils::api_response::ApiResponse;
+use crate::utils::app_state;
+use crate::utils::chat::ChatRoom;
+use crate::utils::jwt::get_user_id_from_http_request;
+use crate::utils::logging::log_info;
+use crate::utils::organization_util::get_organization_id_from_user_id;
+use actix_web::{get, post, web, HttpRequest, Result};
+use chrono::Utc;
+use entity::{conversation, conversation_participant, user};
+use sea_orm::ActiveValue::Set;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, IntoActiveModel,
+    PaginatorTrait, QueryFilter, QueryOrder,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use uuid::Uuid;
+
+const MAX_CONVERSATION_PARTICIPANTS: usize = 50;
+
+#[derive(Serialize, Deserialize)]
+struct ConversationCreateDTO {
+    name: Option<String>,
+    participant_ids: Vec<Uuid>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct ConversationParticipantDTO {
+    conversation_id: Uuid,
+    user_id: Uuid,
+}
+
+#[derive(Serialize, Deserialize)]
+struct ConversationIdDTO {
+    conversation_id: Uuid,
+}
+
+#[derive(Serialize, Deserialize)]
+struct ConversationDTO {
+    id: Uuid,
+    name: Option<String>,
+    created_by: Uuid,
+    date_created: String,
+    participant_ids: Vec<Uuid>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct ConversationEventDTO {
+    message_type: String,
+    conversation_id: Uuid,
+    user_id: Uuid,
+}
+
+#[post("/")]
+pub async fn create_conversation(
+    req: HttpRequest,
+    app_state: web::Data<app_state::AppState>,
+    conversation_create_dto: web::Json<ConversationCreateDTO>,
+    chat_room: web::Data<Arc<ChatRoom>>,
+) -> Result<ApiResponse, ApiResponse> {
+    let user_id = get_user_id_from_http_request(req.clone())?;
+
+    let organization_id = get_organization_id_from_user_id(&app_state.db, user_id).await?;
+
+    let mut participant_ids: Vec<Uuid> = vec![user_id];
+
+    for participant_id in conversation_create_dto.participant_ids.iter() {
+        if !participant_ids.contains(participant_id) {
+            participant_ids.push(*participant_id);
+        }
+    }
+
+    if participant_ids.len() < 2 {
+        return Err(ApiResponse::new(
+            400,
+            "A conversation needs at least one other participant".to_string(),
+        ));
+    }
+
+    if participant_ids.len() > MAX_CONVERSATION_PARTICIPANTS {
+        return Err(ApiResponse::new(
+            400,
+            format!(
+                "A conversation can have at most {} participants",
+                MAX_CONVERSATION_PARTICIPANTS
+            ),
+        ));
+    }
+
+    let organization_user_count = user::Entity::find()
+        .filter(user::Column::Id.is_in(participant_ids.clone()))
+        .filter(user::Column::OrganizationId.eq(organization_id))
+        .filter(user::Column::Deleted.eq(false))
+        .count(&app_state.db)
+        .await
+        .map_err(|e| ApiResponse::new(500, e.to_string()))?;
+
+    if organization_user_count != participant_ids.len() as u64 {
+        return Err(ApiResponse::new(400, "User not found".to_string()));
+    }
+
+    let name = conversation_create_dto
+        .name
+        .as_ref()
+        .map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty());
+
+    let conversation_model = conversation::ActiveModel {
+        id: Set(Uuid::new_v4()),
+        name: Set(name),
+        organization_id: Set(organization_id),
+        created_by: Set(user_id),
+        date_created: Set(Utc::now().naive_utc()),
+    }
+    .insert(&app_state.db)
+    .await
+    .map_err(|e| ApiResponse::new(500, e.to_string()))?;
+
+    let participant_models =
+        participant_ids
+            .iter()
+            .map(|participant_id| conversation_participant::ActiveModel {
+                id: Set(Uuid::new_v4()),
+                conversation_id: Set(conversation_model.id),
+                user_id: Set(*participant_id),
+                date_joined: Set(Utc::now().naive_utc()),
+                deleted: Set(false),
+            });
+
+    conversation_participant::Entity::insert_many(participant_models)
+        .exec(&app_state.db)
+        .await
+        .map_err(|e| ApiResponse::new(500, e.to_string()))?;
+
+    log_info(
+        req,
+        format!("Created conversation {}", conversation_model.id),
+    );
+
+    send_conversation_event(
+        &chat_room,
+        &participant_ids,
+        conversation_model.id,
+        user_id,
+        "CONVERSATION_CREATED",
+    );
+
+    let response_dto = ConversationDTO {
+        id: conversation_model.id,
+        name: conversation_model.name,
+        created_by: conversation_model.created_by,
+        date_created: conversation_model.date_created.to_string(),
+        participant_ids,
+    };
+
+    Ok(ApiResponse::new(
+        200,
+        serde_json::to_string(&response_dto).unwrap(),
+    ))
+}
+
+#[get("/")]
+pub async fn get_conversations(
+    req: HttpRequest,
+    app_state: web::Data<app_state::AppState>,
+) -> Result<ApiResponse, ApiResponse> {
+    let user_id = get_user_id_from_http_request(req)?;
+
+    let conversation_ids = get_conversation_ids_by_user_id(&app_state.db, user_id).await?;
+
+    let conversations = conversation::Entity::find()
+        .filter(conversation::Column::Id.is_in(conversation_ids.clone()))
+        .order_by_desc(conversation::Column::DateCreated)
+        .all(&app_state.db)
+        .await
+        .map_err(|e| ApiResponse::new(500, e.to_string()))?;
+
+    let mut participant_ids_by_conversation_id: HashMap<Uuid, Vec<Uuid>> = HashMap::new();
+
+    let participants = conversation_participant::Entity::find()
+        .filter(conversation_participant::Column::ConversationId.is_in(conversation_ids))
+        .filter(conversation_participant::Column::Deleted.eq(false))
+        .all(&app_state.db)
+        .await
+        .map_err(|e| ApiResponse::new(500, e.to_string()))?;
+
+    for participant in participants {
+        participant_ids_by_conversation_id
+            .entry(participant.conversation_id)
+            .or_default()
+            .push(participant.user_id);
+    }
+
+    let response_dtos: Vec<ConversationDTO> = conversations
+        .into_iter()
+        .map(|conversation_model| ConversationDTO {
+            participant_ids: participant_ids_by_conversation_id
+                .remove(&conversation_model.id)
+                .unwrap_or_default(),
+            id: conversation_model.id,
+            name: conversation_model.name,
+            created_by: conversation_model.created_by,
+            date_created: conversation_model.date_created.to_string(),
+        })
+        .collect();
+
+    Ok(ApiResponse::new(
+        200,
+        serde_json::to_string(&response_dtos).unwrap(),
+    ))
+}
+
+#[post("/participant")]
+pub async fn add_participant(
+    req: HttpRequest,
+    app_state: web::Data<app_state::AppState>,
+    participant_dto: web::Json<ConversationParticipantDTO>,
+    chat_room: web::Data<Arc<ChatRoom>>,
+) -> Result<ApiResponse, ApiResponse> {
+    let user_id = get_user_id_from_http_request(req.clone())?;
+
+    let participant_ids = add_conversation_participant(
+        &app_state,
+        &chat_room,
+        user_id,
+        participant_dto.conversation_id,
+        participant_dto.user_id,
+    )
+    .await?;
+
+    log_info(
+        req,
+        format!(
+            "Added user {} to conversation {}",
+            participant_dto.user_id, participant_dto.conversation_id
+        ),
+    );
+
+    Ok(ApiResponse::new(
+        200,
+        serde_json::to_string(&participant_ids).unwrap(),
+    ))
+}
+
+/// Adds `participant_id` to a conversation `user_id` takes part in and notifies
+/// every participant. Returns the updated participant ids.
+pub(crate) async fn add_conversation_participant(
+    app_state: &web::Data<app_state::AppState>,
+    chat_room: &web::Data<Arc<ChatRoom>>,
+    user_id: Uuid,
+    conversation_id: Uuid,
+    participant_id: Uuid,
+) -> Result<Vec<Uuid>, ApiResponse> {
+    check_conversation_participant(&app_state.db, user_id, conversation_id).await?;
+
+    let participant_ids = get_conversation_participant_ids(&app_state.db, conversation_id).await?;
+
+    if participant_ids.contains(&participant_id) {
+        return Err(ApiResponse::new(
+            409,
+            "User is already a participant".to_string(),
+        ));
+    }
+
+    if participant_ids.len() >= MAX_CONVERSATION_PARTICIPANTS {
+        return Err(ApiResponse::new(
+            400,
+            format!(
+                "A conversation can have at most {} participants",
+                MAX_CONVERSATION_PARTICIPANTS
+            ),
+        ));
+    }
+
+    let organization_id = get_organization_id_from_user_id(&app_state.db, user_id).await?;
+
+    user::Entity::find()
+        .filter(user::Column::Id.eq(participant_id))
+        .filter(user::Column::OrganizationId.eq(organization_id))
+        .filter(user::Column::Deleted.eq(false))
+        .one(&app_state.db)
+        .await
+        .map_err(|e| ApiResponse::new(500, e.to_string()))?
+        .ok_or(ApiResponse::new(400, "User not found".to_string()))?;
+
+    // Users who left earlier rejoin on their original row
+    let previous_participant = conversation_participant::Entity::find()
+        .filter(conversation_participant::Column::ConversationId.eq(conversation_id))
+        .filter(conversation_participant::Column::UserId.eq(participant_id))
+        .one(&app_state.db)
+        .await
+        .map_err(|e| ApiResponse::new(500, e.to_string()))?;
+
+    if let Some(previous_participant) = previous_participant {
+        let mut previous_participant = previous_participant.into_active_model();
+        previous_participant.deleted = Set(false);
+        previous_participant.date_joined = Set(Utc::now().naive_utc());
+
+        previous_participant
+            .update(&app_state.db)
+            .await
+            .map_err(|e| ApiResponse::new(500, e.to_string()))?;
+    } else {
+        conversation_participant::ActiveModel {
+            id: Set(Uuid::new_v4()),
+            conversation_id: Set(conversation_id),
+            user_id: Set(participant_id),
+            date_joined: Set(Utc::now().naive_utc()),
+            deleted: Set(false),
+        }
+        .insert(&app_state.db)
+        .await
+        .map_err(|e| ApiResponse::new(500, e.to_string()))?;
+    }
+
+    let participant_ids = [participant_ids, vec![participant_id]].concat();
+
+    send_conversation_event(
+        chat_room,
+        &participant_ids,
+        conversation_id,
+        participant_id,
+        "PARTICIPANT_ADDED",
+    );
+
+    Ok(participant_ids)
+}
+
+#[post("/leave")]
+pub async fn leave_conversation(
+    req: HttpRequest,
+    app_state: web::Data<app_state::AppState>,
+    conversation_id_dto: web::Json<ConversationIdDTO>,
+    chat_room: web::Data<Arc<ChatRoom>>,
+) -> Result<ApiResponse, ApiResponse> {
+    let user_id = get_user_id_from_http_request(req.clone())?;
+    let conversation_id = conversation_id_dto.conversation_id;
+
+    let participant = conversation_participant::Entity::find()
+        .filter(conversation_participant::Column::ConversationId.eq(conversation_id))
+        .filter(conversation_participant::Column::UserId.eq(user_id))
+        .filter(conversation_participant::Column::Deleted.eq(false))
+        .one(&app_state.db)
+        .await
+        .map_err(|e| ApiResponse::new(500, e.to_string()))?
+        .ok_or(ApiResponse::new(
+            404,
+            "You are not a participant of this conversation".to_string(),
+        ))?;
+
+    let participant_ids = get_conversation_participant_ids(&app_state.db, conversation_id).await?;
+
+    let mut participant = participant.into_active_model();
+    participant.deleted = Set(true);
+
+    participant
+        .update(&app_state.db)
+        .await
+        .map_err(|e| ApiResponse::new(500, e.to_string()))?;
+
+    log_info(req, format!("Left conversation {}", conversation_id));
+
+    send_conversation_event(
+        &chat_room,
+        &participant_ids,
+        conversation_id,
+        user_id,
+        "PARTICIPANT_LEFT",
+    );
+
+    Ok(ApiResponse::new(200, "Left conversation".to_string()))
+}
+
+pub async fn get_conversation_participant_ids(
+    db: &DatabaseConnection,
+    conversation_id: Uuid,
+) -> Result<Vec<Uuid>, ApiResponse> {
+    let participant_ids = conversation_participant::Entity::find()
+        .filter(conversation_participant::Column::ConversationId.eq(conversation_id))
+        .filter(conversation_participant::Column::Deleted.eq(false))
+        .all(db)
+        .await
+        .map_err(|e| ApiResponse::new(500, e.to_string()))?
+        .into_iter()
+        .map(|participant| participant.user_id)
+        .collect();
+
+    Ok(participant_ids)
+}
+
+pub async fn get_conversation_ids_by_user_id(
+    db: &DatabaseConnection,
+    user_id: Uuid,
+) -> Result<Vec<Uuid>, ApiResponse> {
+    let conversation_ids = conversation_participant::Entity::find()
+        .filter(conversation_participant::Column::UserId.eq(user_id))
+        .filter(conversation_participant::Column::Deleted.eq(false))
+        .all(db)
+        .await
+        .map_err(|e| ApiResponse::new(500, e.to_string()))?
+        .into_iter()
+        .map(|participant| participant.conversation_id)
+        .collect();
+
+    Ok(conversation_ids)
+}
+
+/// Conversation access is based on the participant list alone, roles play no part.
+pub async fn check_conversation_participant(
+    db: &DatabaseConnection,
+    user_id: Uuid,
+    conversation_id: Uuid,
+) -> Result<(), ApiResponse> {
+    let participant = conversation_participant::Entity::find()
+        .filter(conversation_participant::Column::ConversationId.eq(conversation_id))
+        .filter(conversation_participant::Column::UserId.eq(user_id))
+        .filter(conversation_participant::Column::Deleted.eq(false))
+        .one(db)
+        .await
+        .map_err(|e| ApiResponse::new(500, e.to_string()))?;
+
+    if participant.is_none() {
+        return Err(ApiResponse::new(
+            403,
+            "You are not a participant of this conversation".to_string(),
+        ));
+    }
+
+    Ok(())
+}
+
+fn send_conversation_event(
+    chat_room: &web::Data<Arc<ChatRoom>>,
+    participant_ids: &[Uuid],
+    conversation_id: Uuid,
+    user_id: Uuid,
+    message_type: &str,
+) {
+    let event_dto = ConversationEventDTO {
+        message_type: message_type.to_string(),
+        conversation_id,
+        user_id,
+    };
+
+    let user_ids: Vec<Uuid> = participant_ids
+        .iter()
+        .copied()
+        .collect::<HashSet<Uuid>>()
+        .into_iter()
+        .collect();
+
+    chat_room.send_message(&user_ids, &serde_json::to_string(&event_dto).unwrap());
+}
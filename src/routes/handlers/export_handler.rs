@@ -0,0 +1,454 @@
+use crate::routes::handlers::message_handler::{
+    get_readable_conversation_condition, MessageCursor, CONTENT_MESSAGE_TYPES,
+};
+use crate::utils::api_response::ApiResponse;
+use crate::utils::app_state;
+use crate::utils::jwt::get_user_id_from_http_request;
+use crate::utils::logging::log_info;
+use actix_web::http::header;
+use actix_web::{get, web, HttpRequest, HttpResponse, Result};
+use chrono::Utc;
+use entity::{media, message, message_revision, user};
+use futures::stream;
+use sea_orm::{ColumnTrait, Condition, EntityTrait, QueryFilter, QueryOrder, QuerySelect};
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use uuid::Uuid;
+
+const EXPORT_BATCH_SIZE: u64 = 500;
+
+#[derive(Clone, Copy, PartialEq)]
+enum ExportFormat {
+    Json,
+    Csv,
+    Html,
+}
+
+impl ExportFormat {
+    fn parse(value: &str) -> Option<Self> {
+        match value {
+            "json" => Some(ExportFormat::Json),
+            "csv" => Some(ExportFormat::Csv),
+            "html" => Some(ExportFormat::Html),
+            _ => None,
+        }
+    }
+
+    fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Json => "json",
+            ExportFormat::Csv => "csv",
+            ExportFormat::Html => "html",
+        }
+    }
+
+    fn content_type(&self) -> &'static str {
+        match self {
+            ExportFormat::Json => "application/json",
+            ExportFormat::Csv => "text/csv; charset=utf-8",
+            ExportFormat::Html => "text/html; charset=utf-8",
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct ExportMessageDTO {
+    id: Uuid,
+    parent_id: Option<Uuid>,
+    author_id: Uuid,
+    author: String,
+    message_type: String,
+    content: Option<String>,
+    date_created: String,
+    date_updated: String,
+    edited: bool,
+    deleted: bool,
+    media: Vec<ExportMediaDTO>,
+}
+
+#[derive(Serialize)]
+struct ExportMediaDTO {
+    file_name: String,
+    url: String,
+}
+
+enum ExportStage {
+    Header,
+    Messages,
+    Footer,
+    Done,
+}
+
+struct ExportState {
+    app_state: web::Data<app_state::AppState>,
+    format: ExportFormat,
+    recipient_type: String,
+    reference_id: Uuid,
+    conversation_condition: Condition,
+    cursor: Option<MessageCursor>,
+    stage: ExportStage,
+    exported_count: u64,
+    author_names: HashMap<Uuid, String>,
+}
+
+/// Streams the full history of a channel, DM or conversation as JSON, CSV or a
+/// self-contained HTML page. Messages are read in batches, oldest first, so
+/// large histories are never held in memory.
+#[get("/")]
+async fn export_transcript(
+    req: HttpRequest,
+    app_state: web::Data<app_state::AppState>,
+    query: web::Query<HashMap<String, String>>,
+) -> Result<HttpResponse, ApiResponse> {
+    let user_id = get_user_id_from_http_request(req.clone())?;
+
+    let recipient_type = query
+        .get("recipient_type")
+        .unwrap_or(&"CHANNEL".to_string())
+        .to_string();
+
+    if !["CHANNEL", "USER", "CONVERSATION"].contains(&recipient_type.as_str()) {
+        return Err(ApiResponse::new(
+            400,
+            "Recipient type must be either CHANNEL, USER or CONVERSATION.".to_string(),
+        ));
+    }
+
+    let reference_id = query
+        .get("reference_id")
+        .ok_or(ApiResponse::new(
+            400,
+            "Reference ID is required.".to_string(),
+        ))?
+        .parse::<Uuid>()
+        .map_err(|e| ApiResponse::new(400, e.to_string()))?;
+
+    let format =
+        ExportFormat::parse(query.get("format").map(String::as_str).unwrap_or("json")).ok_or(
+            ApiResponse::new(400, "Format must be either json, csv or html.".to_string()),
+        )?;
+
+    let conversation_condition =
+        get_readable_conversation_condition(&app_state, user_id, &recipient_type, reference_id)
+            .await?;
+
+    log_info(
+        req,
+        format!(
+            "Exported {} {} as {}",
+            recipient_type,
+            reference_id,
+            format.extension()
+        ),
+    );
+
+    let export_state = ExportState {
+        app_state,
+        format,
+        recipient_type,
+        reference_id,
+        conversation_condition,
+        cursor: None,
+        stage: ExportStage::Header,
+        exported_count: 0,
+        author_names: HashMap::new(),
+    };
+
+    let body = stream::unfold(export_state, |mut export_state| async move {
+        let chunk = match export_state.stage {
+            ExportStage::Header => {
+                export_state.stage = ExportStage::Messages;
+                Ok(render_header(&export_state))
+            }
+            ExportStage::Messages => match get_next_batch(&mut export_state).await {
+                Ok(Some(chunk)) => Ok(chunk),
+                Ok(None) => {
+                    export_state.stage = ExportStage::Footer;
+                    Ok(String::new())
+                }
+                Err(e) => {
+                    export_state.stage = ExportStage::Done;
+                    Err(actix_web::error::ErrorInternalServerError(e.body))
+                }
+            },
+            ExportStage::Footer => {
+                export_state.stage = ExportStage::Done;
+                Ok(render_footer(export_state.format))
+            }
+            ExportStage::Done => return None,
+        };
+
+        Some((chunk.map(web::Bytes::from), export_state))
+    });
+
+    Ok(HttpResponse::Ok()
+        .content_type(format.content_type())
+        .insert_header((
+            header::CONTENT_DISPOSITION,
+            format!(
+                "attachment; filename=\"transcript-{}.{}\"",
+                reference_id,
+                format.extension()
+            ),
+        ))
+        .streaming(body))
+}
+
+/// Loads and renders the next batch of messages, or `None` once all are out.
+async fn get_next_batch(export_state: &mut ExportState) -> Result<Option<String>, ApiResponse> {
+    let db = &export_state.app_state.db;
+
+    let mut query_messages = message::Entity::find()
+        .filter(message::Column::MessageType.is_in(CONTENT_MESSAGE_TYPES))
+        .filter(message::Column::RecipientType.eq(export_state.recipient_type.clone()))
+        .filter(export_state.conversation_condition.clone())
+        .order_by_asc(message::Column::DateCreated)
+        .order_by_asc(message::Column::Id)
+        .limit(EXPORT_BATCH_SIZE);
+
+    if let Some(cursor) = export_state.cursor.as_ref() {
+        query_messages = query_messages.filter(cursor.after_condition());
+    }
+
+    let messages = query_messages
+        .all(db)
+        .await
+        .map_err(|e| ApiResponse::new(500, e.to_string()))?;
+
+    let Some(last_message) = messages.last() else {
+        return Ok(None);
+    };
+
+    export_state.cursor = Some(MessageCursor::Message(
+        last_message.date_created,
+        last_message.id,
+    ));
+
+    let message_ids: Vec<Uuid> = messages.iter().map(|message| message.id).collect();
+
+    let missing_author_ids: HashSet<Uuid> = messages
+        .iter()
+        .map(|message| message.user_id)
+        .filter(|user_id| !export_state.author_names.contains_key(user_id))
+        .collect();
+
+    if !missing_author_ids.is_empty() {
+        for user_model in user::Entity::find()
+            .filter(user::Column::Id.is_in(missing_author_ids))
+            .all(db)
+            .await
+            .map_err(|e| ApiResponse::new(500, e.to_string()))?
+        {
+            export_state
+                .author_names
+                .insert(user_model.id, user_model.display_name);
+        }
+    }
+
+    let mut media_by_message_id: HashMap<Uuid, Vec<ExportMediaDTO>> = HashMap::new();
+
+    for media_model in media::Entity::find()
+        .filter(media::Column::MessageId.is_in(message_ids.clone()))
+        .filter(media::Column::Deleted.eq(false))
+        .order_by_asc(media::Column::CreatedAt)
+        .all(db)
+        .await
+        .map_err(|e| ApiResponse::new(500, e.to_string()))?
+    {
+        if let Some(message_id) = media_model.message_id {
+            media_by_message_id
+                .entry(message_id)
+                .or_default()
+                .push(ExportMediaDTO {
+                    file_name: media_model.file_name,
+                    url: media_model.url,
+                });
+        }
+    }
+
+    let edited_message_ids: HashSet<Uuid> = message_revision::Entity::find()
+        .select_only()
+        .column(message_revision::Column::MessageId)
+        .filter(message_revision::Column::MessageId.is_in(message_ids))
+        .distinct()
+        .into_tuple::<Uuid>()
+        .all(db)
+        .await
+        .map_err(|e| ApiResponse::new(500, e.to_string()))?
+        .into_iter()
+        .collect();
+
+    let mut chunk = String::new();
+
+    for message_model in messages {
+        let export_message_dto = ExportMessageDTO {
+            id: message_model.id,
+            parent_id: message_model.parent_id,
+            author_id: message_model.user_id,
+            author: export_state
+                .author_names
+                .get(&message_model.user_id)
+                .cloned()
+                .unwrap_or_default(),
+            message_type: message_model.message_type,
+            content: if message_model.deleted {
+                None
+            } else {
+                message_model.content
+            },
+            date_created: message_model.date_created.to_string(),
+            date_updated: message_model.date_updated.to_string(),
+            edited: edited_message_ids.contains(&message_model.id),
+            deleted: message_model.deleted,
+            media: media_by_message_id
+                .remove(&message_model.id)
+                .unwrap_or_default(),
+        };
+
+        chunk.push_str(&render_message(
+            export_state.format,
+            &export_message_dto,
+            export_state.exported_count == 0,
+        ));
+
+        export_state.exported_count += 1;
+    }
+
+    Ok(Some(chunk))
+}
+
+fn render_header(export_state: &ExportState) -> String {
+    match export_state.format {
+        ExportFormat::Json => format!(
+            "{{\"recipient_type\":{},\"reference_id\":\"{}\",\"exported_at\":\"{}\",\"messages\":[",
+            serde_json::to_string(&export_state.recipient_type).unwrap(),
+            export_state.reference_id,
+            Utc::now().naive_utc()
+        ),
+        ExportFormat::Csv => {
+            "id,parent_id,author_id,author,message_type,content,date_created,date_updated,edited,deleted,media\n"
+                .to_string()
+        }
+        ExportFormat::Html => format!(
+            "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>Transcript {reference_id}</title>\n<style>\
+             body{{font-family:sans-serif;max-width:800px;margin:2em auto;color:#222}}\
+             article{{border-bottom:1px solid #ddd;padding:.5em 0}}\
+             article.reply{{margin-left:2em}}\
+             .author{{font-weight:bold}}\
+             .meta{{color:#777;font-size:.85em}}\
+             .content{{white-space:pre-wrap}}\
+             .deleted{{color:#999;font-style:italic}}\
+             </style>\n</head>\n<body>\n<h1>Transcript</h1>\n<p class=\"meta\">{recipient_type} {reference_id}, exported {exported_at}</p>\n",
+            recipient_type = escape_html(&export_state.recipient_type),
+            reference_id = export_state.reference_id,
+            exported_at = Utc::now().naive_utc()
+        ),
+    }
+}
+
+fn render_message(format: ExportFormat, message: &ExportMessageDTO, first: bool) -> String {
+    match format {
+        ExportFormat::Json => format!(
+            "{}{}",
+            if first { "" } else { "," },
+            serde_json::to_string(message).unwrap()
+        ),
+        ExportFormat::Csv => {
+            let fields = [
+                message.id.to_string(),
+                message
+                    .parent_id
+                    .map(|parent_id| parent_id.to_string())
+                    .unwrap_or_default(),
+                message.author_id.to_string(),
+                message.author.clone(),
+                message.message_type.clone(),
+                message.content.clone().unwrap_or_default(),
+                message.date_created.clone(),
+                message.date_updated.clone(),
+                message.edited.to_string(),
+                message.deleted.to_string(),
+                message
+                    .media
+                    .iter()
+                    .map(|media| media.url.as_str())
+                    .collect::<Vec<&str>>()
+                    .join(" "),
+            ];
+
+            let mut row = fields
+                .iter()
+                .map(|field| escape_csv(field))
+                .collect::<Vec<String>>()
+                .join(",");
+            row.push('\n');
+            row
+        }
+        ExportFormat::Html => {
+            let content = if message.deleted {
+                "<p class=\"content deleted\">This message was deleted.</p>".to_string()
+            } else {
+                format!(
+                    "<p class=\"content\">{}</p>",
+                    escape_html(message.content.as_deref().unwrap_or_default())
+                )
+            };
+
+            let media = message
+                .media
+                .iter()
+                .map(|media| {
+                    format!(
+                        "<li><a href=\"{}\">{}</a></li>",
+                        escape_html(&media.url),
+                        escape_html(&media.file_name)
+                    )
+                })
+                .collect::<String>();
+
+            format!(
+                "<article id=\"{id}\"{class}>\n<div><span class=\"author\">{author}</span> <span class=\"meta\">{date_created}{edited}</span></div>\n{content}\n{media}</article>\n",
+                id = message.id,
+                class = if message.parent_id.is_some() {
+                    " class=\"reply\""
+                } else {
+                    ""
+                },
+                author = escape_html(&message.author),
+                date_created = message.date_created,
+                edited = if message.edited { " (edited)" } else { "" },
+                content = content,
+                media = if media.is_empty() {
+                    String::new()
+                } else {
+                    format!("<ul>{}</ul>\n", media)
+                },
+            )
+        }
+    }
+}
+
+fn render_footer(format: ExportFormat) -> String {
+    match format {
+        ExportFormat::Json => "]}".to_string(),
+        ExportFormat::Csv => String::new(),
+        ExportFormat::Html => "</body>\n</html>\n".to_string(),
+    }
+}
+
+fn escape_csv(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+fn escape_html(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
@@ -63,7 +63,6 @@ pub async fn update_flag(
         let new_flag = flag::ActiveModel {
             name: Set(flag_name),
             enabled: Set(flag_enabled),
-            ..Default::default()
         };
 
         let created_flag = new_flag
@@ -1,3 +1,4 @@
+use crate::routes::handlers::conversation_handler::check_conversation_participant;
 use crate::routes::handlers::message_handler::get_user_has_access_to_channel;
 use crate::utils::api_response::ApiResponse;
 use crate::utils::app_state;
@@ -5,11 +6,11 @@ use crate::utils::jwt::get_user_id_from_http_request;
 use crate::utils::logging::log_info;
 use crate::utils::permissions::{check_permission, Permission};
 use crate::utils::s3;
-use actix_web::{delete, get, patch, post, web, HttpRequest};
+use actix_web::{delete, get, post, web, HttpRequest};
 use entity::media;
 use sea_orm::prelude::DateTime;
 use sea_orm::ActiveValue::Set;
-use sea_orm::{ActiveModelTrait, ColumnTrait, EntityTrait, IntoActiveModel, QueryFilter};
+use sea_orm::{ActiveModelTrait, ColumnTrait, EntityTrait, QueryFilter};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use uuid::Uuid;
@@ -20,12 +21,6 @@ pub struct UploadMedia {
     file: Option<actix_multipart::form::tempfile::TempFile>,
 }
 
-#[derive(Serialize, Deserialize)]
-pub struct MediaAttachMessageDTO {
-    media_id: Uuid,
-    message_id: Uuid,
-}
-
 #[derive(Serialize, Deserialize)]
 pub struct MediaUploadDTO {
     id: Uuid,
@@ -38,6 +33,21 @@ pub struct MediaUploadDTO {
     deleted: Option<bool>,
 }
 
+impl From<media::Model> for MediaUploadDTO {
+    fn from(model: media::Model) -> Self {
+        Self {
+            id: model.id,
+            file_name: model.file_name,
+            key: model.key,
+            url: model.url,
+            created_at: model.created_at,
+            user_id: model.user_id,
+            message_id: model.message_id,
+            deleted: Some(model.deleted),
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct MediaIdDTO {
     id: Uuid,
@@ -98,64 +108,6 @@ pub async fn upload_media(
     ))
 }
 
-#[patch("/")]
-async fn attach_media_to_message(
-    req: HttpRequest,
-    app_state: web::Data<app_state::AppState>,
-    attach_message_dto: web::Json<MediaAttachMessageDTO>,
-) -> actix_web::Result<ApiResponse, ApiResponse> {
-    let user_id = get_user_id_from_http_request(req.clone())?;
-
-    let existing_media = media::Entity::find()
-        .filter(media::Column::Id.eq(attach_message_dto.media_id))
-        .one(&app_state.db)
-        .await
-        .map_err(|e| ApiResponse::new(500, e.to_string()))?;
-
-    if existing_media.is_none() {
-        return Err(ApiResponse::new(404, "Media not found.".to_string()));
-    }
-
-    if existing_media.as_ref().unwrap().user_id != user_id {
-        return Err(ApiResponse::new(
-            403,
-            "You do not have permission to attach this media.".to_string(),
-        ));
-    }
-
-    let mut media_model = existing_media.unwrap().into_active_model();
-    media_model.message_id = Set(Some(attach_message_dto.message_id));
-
-    let updated_media = media_model
-        .update(&app_state.db)
-        .await
-        .map_err(|e| ApiResponse::new(500, e.to_string()))?;
-
-    log_info(
-        req,
-        format!(
-            "Attached media {} to message {}",
-            updated_media.id, attach_message_dto.message_id
-        ),
-    );
-
-    let media_dto = MediaUploadDTO {
-        id: updated_media.id,
-        file_name: updated_media.file_name.clone(),
-        key: updated_media.key.clone(),
-        url: updated_media.url.clone(),
-        created_at: updated_media.created_at,
-        user_id: updated_media.user_id,
-        message_id: updated_media.message_id,
-        deleted: Some(updated_media.deleted),
-    };
-
-    Ok(ApiResponse::new(
-        200,
-        serde_json::to_string(&media_dto).unwrap(),
-    ))
-}
-
 #[get("/")]
 async fn get_all_media_by_message_id(
     req: HttpRequest,
@@ -199,6 +151,9 @@ async fn get_all_media_by_message_id(
                 ));
             }
         }
+        "CONVERSATION" => {
+            check_conversation_participant(&app_state.db, user_id, message.reference_id).await?;
+        }
         _ => {
             return Err(ApiResponse::new(400, "Invalid recipient type".to_string()));
         }
@@ -337,6 +292,10 @@ pub async fn get_media_by_list_of_message_ids(
                     ));
                 }
             }
+            "CONVERSATION" => {
+                check_conversation_participant(&app_state.db, user_id, message.reference_id)
+                    .await?;
+            }
             _ => {
                 return Err(ApiResponse::new(400, "Invalid recipient type".to_string()));
             }
@@ -1,19 +1,44 @@
+use crate::routes::handlers::bookmark_handler::mark_bookmarks_unavailable;
+use crate::routes::handlers::command_handler::{
+    execute_command, parse_command, CommandResponseDTO,
+};
+use crate::routes::handlers::conversation_handler::{
+    check_conversation_participant, get_conversation_ids_by_user_id,
+    get_conversation_participant_ids,
+};
+use crate::routes::handlers::poll_handler::{
+    create_poll, get_polls_by_message_ids, validate_poll, PollCreateDTO, PollDTO,
+};
+use crate::routes::handlers::reaction_handler::{
+    get_reaction_counts_by_message_ids, ReactionCountDTO,
+};
+use crate::routes::handlers::scheduled_message_handler::{schedule_message, ScheduledMessageDTO};
+use crate::routes::handlers::user_channel_view_handler::get_user_channel_view_conflict;
 use crate::utils::api_response::ApiResponse;
 use crate::utils::app_state;
 use crate::utils::chat::ChatRoom;
 use crate::utils::jwt::get_user_id_from_http_request;
 use crate::utils::logging::log_info;
+use crate::utils::mention::{get_mentioned_users, save_mentions};
+use crate::utils::organization_util::get_organization_id_from_user_id;
 use crate::utils::permissions::{
-    check_chat_permission, check_permission, ChatPermission, Permission,
+    check_chat_permission, check_chat_permission_by_user_id, check_permission,
+    get_chat_permission_channel_ids, ChatPermission, Permission,
+};
+use crate::utils::s3;
+use crate::utils::search_query::{
+    get_has_condition, get_search_condition, get_search_text, parse_search_query, SearchHas,
 };
 use actix_web::{delete, get, patch, post, web, HttpRequest, Result};
 use chrono::Utc;
 use entity::{
-    channel, channel_role_access, media, message, role, user, user_channel_view, user_role_access,
+    channel, channel_role_access, media, message, message_revision, organization, role, user,
+    user_channel_view, user_role_access,
 };
+use sea_orm::sea_query::Expr;
 use sea_orm::ActiveValue::Set;
 use sea_orm::{
-    ActiveModelTrait, ColumnTrait, Condition, EntityTrait, IntoActiveModel, PaginatorTrait,
+    ActiveModelTrait, ColumnTrait, Condition, EntityTrait, FromQueryResult, IntoActiveModel,
     QueryFilter, QueryOrder, QuerySelect,
 };
 use serde::{Deserialize, Serialize};
@@ -22,13 +47,39 @@ use std::collections::HashSet;
 use std::sync::Arc;
 use uuid::Uuid;
 
+const MAX_CLIENT_MESSAGE_ID_LENGTH: usize = 128;
+
+/// Longest TTL a message or channel can set, one year.
+pub(crate) const MAX_MESSAGE_TTL_SECONDS: i64 = 60 * 60 * 24 * 365;
+
+/// Longest edit window an organization can set, one year.
+pub(crate) const MAX_MESSAGE_EDIT_WINDOW_SECONDS: i64 = 60 * 60 * 24 * 365;
+
+/// Largest page a paginated listing returns.
+pub(crate) const MAX_PER_PAGE: u64 = 100;
+
+/// Message content with HTML escaped, so snippets are safe to render as markup.
+const ESCAPED_CONTENT: &str = "replace(replace(replace(replace(coalesce(message.content, ''), '&', '&amp;'), '<', '&lt;'), '>', '&gt;'), '\"', '&quot;')";
+
+/// Message types shown in conversation history and search results.
+pub(crate) const CONTENT_MESSAGE_TYPES: [&str; 2] = ["MESSAGE", "POLL"];
+
 #[derive(Serialize, Deserialize)]
-struct MessageSendDTO {
-    content: Option<String>,
-    message_type: String,
-    recipient_type: String,
-    reference_id: Uuid,
-    media_ids: Vec<Uuid>,
+pub(crate) struct MessageSendDTO {
+    pub(crate) content: Option<String>,
+    pub(crate) message_type: String,
+    pub(crate) recipient_type: String,
+    pub(crate) reference_id: Uuid,
+    pub(crate) media_ids: Vec<Uuid>,
+    pub(crate) parent_id: Option<Uuid>,
+    #[serde(default)]
+    pub(crate) send_at: Option<String>,
+    #[serde(default)]
+    pub(crate) ttl_seconds: Option<i64>,
+    #[serde(default)]
+    pub(crate) client_message_id: Option<String>,
+    #[serde(default)]
+    pub(crate) poll: Option<PollCreateDTO>,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -38,16 +89,130 @@ struct MessageEditDTO {
 }
 
 #[derive(Serialize, Deserialize)]
-struct MessageDTO {
-    id: Uuid,
+pub(crate) struct MessageDTO {
+    pub(crate) id: Uuid,
     user_id: Uuid,
-    content: Option<String>,
+    pub(crate) content: Option<String>,
     date_created: String,
     date_updated: String,
     message_type: String,
     recipient_type: String,
     reference_id: Uuid,
     deleted: bool,
+    parent_id: Option<Uuid>,
+    expires_at: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    client_message_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    poll: Option<PollDTO>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct MessageWithReactionsDTO {
+    #[serde(flatten)]
+    message: MessageDTO,
+    reactions: Vec<ReactionCountDTO>,
+}
+
+#[derive(FromQueryResult)]
+struct MessageSearchResult {
+    id: Uuid,
+    user_id: Uuid,
+    deleted: bool,
+    content: Option<String>,
+    date_created: chrono::NaiveDateTime,
+    date_updated: chrono::NaiveDateTime,
+    message_type: String,
+    recipient_type: String,
+    reference_id: Uuid,
+    parent_id: Option<Uuid>,
+    expires_at: Option<chrono::NaiveDateTime>,
+    client_message_id: Option<String>,
+    rank: f32,
+    snippet: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct MessageSearchResultDTO {
+    #[serde(flatten)]
+    message: MessageDTO,
+    rank: f32,
+    snippet: Option<String>,
+}
+
+impl From<MessageSearchResult> for MessageSearchResultDTO {
+    fn from(result: MessageSearchResult) -> Self {
+        Self {
+            message: message::Model {
+                id: result.id,
+                user_id: result.user_id,
+                deleted: result.deleted,
+                content: result.content,
+                date_created: result.date_created,
+                date_updated: result.date_updated,
+                message_type: result.message_type,
+                recipient_type: result.recipient_type,
+                reference_id: result.reference_id,
+                parent_id: result.parent_id,
+                expires_at: result.expires_at,
+                client_message_id: result.client_message_id,
+            }
+            .into(),
+            rank: result.rank,
+            snippet: result.snippet,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct MentionEventDTO {
+    message_type: String,
+    mention_type: String,
+    message: MessageDTO,
+}
+
+#[derive(Serialize, Deserialize)]
+struct MessageIdsDTO {
+    ids: Vec<Uuid>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct MessageRevisionDTO {
+    id: Uuid,
+    message_id: Uuid,
+    content: Option<String>,
+    edited_by: Uuid,
+    date_edited: String,
+}
+
+impl From<message_revision::Model> for MessageRevisionDTO {
+    fn from(model: message_revision::Model) -> Self {
+        Self {
+            id: model.id,
+            message_id: model.message_id,
+            content: model.content,
+            edited_by: model.edited_by,
+            date_edited: model.date_edited.to_string(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct ThreadDTO {
+    root: MessageDTO,
+    replies: Vec<MessageDTO>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct ThreadReplyCountDTO {
+    message_id: Uuid,
+    reply_count: i64,
+}
+
+#[derive(Serialize, Deserialize)]
+struct ThreadLatestReplyDTO {
+    message_id: Uuid,
+    latest_reply: Option<MessageDTO>,
 }
 
 impl From<message::Model> for MessageDTO {
@@ -62,10 +227,24 @@ impl From<message::Model> for MessageDTO {
             recipient_type: model.recipient_type.to_string(),
             reference_id: model.reference_id,
             deleted: model.deleted,
+            parent_id: model.parent_id,
+            expires_at: model.expires_at.map(|expires_at| expires_at.to_string()),
+            client_message_id: model.client_message_id,
+            poll: None,
         }
     }
 }
 
+/// What a send turned into. Commands and scheduled sends don't publish a
+/// message straight away.
+#[derive(Serialize)]
+#[serde(untagged)]
+pub(crate) enum SentMessageDTO {
+    Command(CommandResponseDTO),
+    Scheduled(ScheduledMessageDTO),
+    Published(MessageDTO),
+}
+
 #[post("/")]
 pub async fn send_message(
     req: HttpRequest,
@@ -75,6 +254,97 @@ pub async fn send_message(
 ) -> Result<ApiResponse, ApiResponse> {
     let user_id = get_user_id_from_http_request(req.clone())?;
 
+    let sent_message_dto = send_message_as_user(
+        &app_state,
+        &chat_room,
+        Some(req.clone()),
+        user_id,
+        message_send_dto.into_inner(),
+    )
+    .await?;
+
+    match &sent_message_dto {
+        SentMessageDTO::Command(response_dto) => {
+            log_info(req, format!("Ran command /{}", response_dto.command));
+        }
+        SentMessageDTO::Scheduled(response_dto) => {
+            log_info(req, format!("Scheduled message {}", response_dto.id));
+        }
+        SentMessageDTO::Published(_) => {}
+    }
+
+    Ok(ApiResponse::new(
+        200,
+        serde_json::to_string(&sent_message_dto).unwrap(),
+    ))
+}
+
+/// Runs a command, schedules or publishes a message for the REST endpoint and
+/// the WebSocket, which has no request to check permissions against.
+pub(crate) async fn send_message_as_user(
+    app_state: &web::Data<app_state::AppState>,
+    chat_room: &web::Data<Arc<ChatRoom>>,
+    req: Option<HttpRequest>,
+    user_id: Uuid,
+    mut message_send_dto: MessageSendDTO,
+) -> Result<SentMessageDTO, ApiResponse> {
+    check_user_can_send_message(app_state, req, user_id, &message_send_dto).await?;
+
+    let command = message_send_dto
+        .content
+        .as_deref()
+        .filter(|_| message_send_dto.message_type == "MESSAGE")
+        .and_then(parse_command);
+
+    if let Some((name, args)) = command {
+        if message_send_dto.send_at.is_some() {
+            return Err(ApiResponse::new(
+                400,
+                "Commands can't be scheduled.".to_string(),
+            ));
+        }
+
+        let response_dto =
+            execute_command(app_state, chat_room, user_id, &message_send_dto, name, args).await?;
+
+        return Ok(SentMessageDTO::Command(response_dto));
+    }
+
+    if let Some(content) = message_send_dto
+        .content
+        .as_deref()
+        .and_then(unescape_command_content)
+    {
+        message_send_dto.content = Some(content);
+    }
+
+    if message_send_dto.send_at.is_some() {
+        let response_dto = schedule_message(app_state, user_id, &message_send_dto).await?;
+
+        return Ok(SentMessageDTO::Scheduled(response_dto));
+    }
+
+    let response_dto = publish_message(app_state, chat_room, user_id, &message_send_dto).await?;
+
+    Ok(SentMessageDTO::Published(response_dto))
+}
+
+/// `//text` sends `/text` as a regular message.
+pub(crate) fn unescape_command_content(content: &str) -> Option<String> {
+    content
+        .trim_start()
+        .strip_prefix("//")
+        .map(|content| format!("/{}", content))
+}
+
+/// Checks shared by immediate and scheduled sends. Scheduled messages are checked
+/// again without a request when they are published.
+pub(crate) async fn check_user_can_send_message(
+    app_state: &web::Data<app_state::AppState>,
+    req: Option<HttpRequest>,
+    user_id: Uuid,
+    message_send_dto: &MessageSendDTO,
+) -> Result<(), ApiResponse> {
     match message_send_dto.recipient_type.as_str() {
         "CHANNEL" => {
             let has_user_access_to_channel = get_user_has_access_to_channel(
@@ -91,13 +361,26 @@ pub async fn send_message(
                 ));
             }
 
-            let user_can_write = check_chat_permission(
-                &app_state.db,
-                req,
-                ChatPermission::CanWrite,
-                message_send_dto.reference_id,
-            )
-            .await;
+            let user_can_write = match req {
+                Some(req) => {
+                    check_chat_permission(
+                        &app_state.db,
+                        req,
+                        ChatPermission::CanWrite,
+                        message_send_dto.reference_id,
+                    )
+                    .await
+                }
+                None => {
+                    check_chat_permission_by_user_id(
+                        &app_state.db,
+                        user_id,
+                        ChatPermission::CanWrite,
+                        message_send_dto.reference_id,
+                    )
+                    .await
+                }
+            };
 
             if !user_can_write {
                 return Err(ApiResponse::new(
@@ -109,15 +392,59 @@ pub async fn send_message(
         "USER" => {
             //implement checks to see if a user requires permission to write to another user... for now not necessary
         }
+        "CONVERSATION" => {
+            check_conversation_participant(&app_state.db, user_id, message_send_dto.reference_id)
+                .await?;
+        }
         _ => {
             return Err(ApiResponse::new(
                 400,
-                "Recipient type must be either CHANNEL or USER.".to_string(),
+                "Recipient type must be either CHANNEL, USER or CONVERSATION.".to_string(),
             ));
         }
     }
 
-    // Now that permissions are verified, proceed with message creation
+    if let Some(parent_id) = message_send_dto.parent_id {
+        validate_thread_parent(
+            app_state,
+            user_id,
+            parent_id,
+            &message_send_dto.recipient_type,
+            message_send_dto.reference_id,
+        )
+        .await?;
+    }
+
+    validate_poll(message_send_dto)?;
+
+    if message_send_dto
+        .client_message_id
+        .as_ref()
+        .is_some_and(|client_message_id| {
+            client_message_id.is_empty() || client_message_id.len() > MAX_CLIENT_MESSAGE_ID_LENGTH
+        })
+    {
+        return Err(ApiResponse::new(
+            400,
+            format!(
+                "Client message ID must be between 1 and {} characters.",
+                MAX_CLIENT_MESSAGE_ID_LENGTH
+            ),
+        ));
+    }
+
+    if message_send_dto
+        .ttl_seconds
+        .is_some_and(|ttl_seconds| !(1..=MAX_MESSAGE_TTL_SECONDS).contains(&ttl_seconds))
+    {
+        return Err(ApiResponse::new(
+            400,
+            format!(
+                "Message TTL must be between 1 and {} seconds.",
+                MAX_MESSAGE_TTL_SECONDS
+            ),
+        ));
+    }
 
     for media_id in message_send_dto.media_ids.iter() {
         let media_model = media::Entity::find()
@@ -132,6 +459,40 @@ pub async fn send_message(
         }
     }
 
+    Ok(())
+}
+
+pub(crate) async fn publish_message(
+    app_state: &web::Data<app_state::AppState>,
+    chat_room: &web::Data<Arc<ChatRoom>>,
+    user_id: Uuid,
+    message_send_dto: &MessageSendDTO,
+) -> Result<MessageDTO, ApiResponse> {
+    // A retried send returns the original message without publishing it again
+    if let Some(message_model) = get_message_by_client_message_id(
+        app_state,
+        user_id,
+        message_send_dto.client_message_id.as_deref(),
+    )
+    .await?
+    {
+        return Ok(message_model.into());
+    }
+
+    let date_created = Utc::now().naive_utc();
+
+    let expires_at = match get_message_ttl_seconds(app_state, message_send_dto).await? {
+        Some(ttl_seconds) => Some(
+            chrono::TimeDelta::try_seconds(ttl_seconds)
+                .and_then(|ttl| date_created.checked_add_signed(ttl))
+                .ok_or(ApiResponse::new(
+                    400,
+                    "Message TTL is too long.".to_string(),
+                ))?,
+        ),
+        None => None,
+    };
+
     let message_model = message::ActiveModel {
         id: Set(Uuid::new_v4()),
         content: Set(message_send_dto.content.clone()),
@@ -139,14 +500,32 @@ pub async fn send_message(
         message_type: Set(message_send_dto.message_type.clone()),
         recipient_type: Set(message_send_dto.recipient_type.clone()),
         reference_id: Set(message_send_dto.reference_id),
-        date_updated: Set(Utc::now().naive_utc()),
-        date_created: Set(Utc::now().naive_utc()),
+        parent_id: Set(message_send_dto.parent_id),
+        date_updated: Set(date_created),
+        date_created: Set(date_created),
         deleted: Set(false),
-        ..Default::default()
+        expires_at: Set(expires_at),
+        client_message_id: Set(message_send_dto.client_message_id.clone()),
     }
     .insert(&app_state.db)
-    .await
-    .map_err(|e| ApiResponse::new(500, e.to_string()))?;
+    .await;
+
+    let message_model = match message_model {
+        Ok(message_model) => message_model,
+        Err(e) => {
+            // Concurrent retries race on the unique index, the loser returns the winner's message
+            return match get_message_by_client_message_id(
+                app_state,
+                user_id,
+                message_send_dto.client_message_id.as_deref(),
+            )
+            .await?
+            {
+                Some(message_model) => Ok(message_model.into()),
+                None => Err(ApiResponse::new(500, e.to_string())),
+            };
+        }
+    };
 
     for media_id in message_send_dto.media_ids.iter() {
         let media_model = media::Entity::find()
@@ -164,53 +543,69 @@ pub async fn send_message(
             .map_err(|e| ApiResponse::new(500, e.to_string()))?;
     }
 
-    let user_channel_view = user_channel_view::Entity::find()
-        .filter(user_channel_view::Column::UserId.eq(user_id))
-        .filter(
-            user_channel_view::Column::RecipientType.eq(message_send_dto.recipient_type.clone()),
+    let new_user_channel_view = user_channel_view::ActiveModel {
+        id: Set(Uuid::new_v4()),
+        user_id: Set(user_id),
+        recipient_type: Set(message_send_dto.recipient_type.clone()),
+        reference_id: Set(message_send_dto.reference_id),
+        last_viewed: Set(Utc::now().naive_utc()),
+        muted_until: Set(None),
+        last_read_message_id: Set(Some(message_model.id)),
+    };
+
+    user_channel_view::Entity::insert(new_user_channel_view)
+        .on_conflict(
+            get_user_channel_view_conflict()
+                .update_columns([
+                    user_channel_view::Column::LastViewed,
+                    user_channel_view::Column::LastReadMessageId,
+                ])
+                .to_owned(),
         )
-        .filter(user_channel_view::Column::ReferenceId.eq(message_send_dto.reference_id))
-        .one(&app_state.db)
+        .exec_without_returning(&app_state.db)
         .await
         .map_err(|e| ApiResponse::new(500, e.to_string()))?;
 
-    if user_channel_view.is_none() {
-        let new_user_channel_view = user_channel_view::ActiveModel {
-            id: Set(Uuid::new_v4()),
-            user_id: Set(user_id),
-            recipient_type: Set(message_send_dto.recipient_type.clone()),
-            reference_id: Set(message_send_dto.reference_id),
-            last_viewed: Set(Utc::now().naive_utc()),
-            ..Default::default()
-        };
+    let reader_ids = match message_send_dto.recipient_type.as_str() {
+        "CHANNEL" => {
+            get_array_of_readers_by_channel_id(app_state, message_send_dto.reference_id).await?
+        }
+        "CONVERSATION" => {
+            get_conversation_participant_ids(&app_state.db, message_send_dto.reference_id).await?
+        }
+        _ => vec![message_send_dto.reference_id],
+    };
 
-        new_user_channel_view
-            .insert(&app_state.db)
-            .await
-            .map_err(|e| ApiResponse::new(500, e.to_string()))?;
-    } else {
-        let user_channel_view = user_channel_view.unwrap();
+    let organization_id = get_organization_id_from_user_id(&app_state.db, user_id).await?;
 
-        let mut user_channel_view = user_channel_view.into_active_model();
-        user_channel_view.last_viewed = Set(Utc::now().naive_utc());
-        user_channel_view.user_id = Set(user_id);
-        user_channel_view.recipient_type = Set(message_send_dto.recipient_type.clone());
-        user_channel_view.reference_id = Set(message_send_dto.reference_id);
+    let mentioned_users = get_mentioned_users(
+        &app_state.db,
+        user_id,
+        &message_model,
+        organization_id,
+        &reader_ids,
+    )
+    .await?;
 
-        user_channel_view
-            .update(&app_state.db)
-            .await
-            .map_err(|e| ApiResponse::new(500, e.to_string()))?;
-    }
+    save_mentions(&app_state.db, message_model.id, &mentioned_users).await?;
 
-    let response_dto: MessageDTO = message_model.clone().into();
+    let mut response_dto: MessageDTO = message_model.clone().into();
+
+    if let Some(poll_create_dto) = message_send_dto.poll.as_ref() {
+        response_dto.poll =
+            Some(create_poll(&app_state.db, message_model.id, poll_create_dto).await?);
+    }
 
     match message_send_dto.recipient_type.as_str() {
         "CHANNEL" => {
-            let user_ids =
-                get_array_of_users_by_channel_id(app_state, message_send_dto.reference_id)
+            // Thread replies only go to users who can read the parent message
+            let user_ids = if message_model.parent_id.is_some() {
+                reader_ids
+            } else {
+                get_array_of_users_by_channel_id(app_state.clone(), message_send_dto.reference_id)
                     .await
-                    .map_err(|e| ApiResponse::new(500, e.to_string()))?;
+                    .map_err(|e| ApiResponse::new(500, e.to_string()))?
+            };
 
             chat_room.send_message(&user_ids, &serde_json::to_string(&response_dto).unwrap());
         }
@@ -218,13 +613,27 @@ pub async fn send_message(
             let user_ids = vec![message_send_dto.reference_id, user_id];
             chat_room.send_message(&user_ids, &serde_json::to_string(&response_dto).unwrap());
         }
+        "CONVERSATION" => {
+            chat_room.send_message(&reader_ids, &serde_json::to_string(&response_dto).unwrap());
+        }
         _ => {}
     }
 
-    Ok(ApiResponse::new(
-        200,
-        serde_json::to_string(&response_dto).unwrap(),
-    ))
+    // Mentions are delivered separately so they still reach users who have muted the channel
+    for (mentioned_user_id, mention_type) in mentioned_users {
+        let mention_event_dto = MentionEventDTO {
+            message_type: "MENTION".to_string(),
+            mention_type,
+            message: message_model.clone().into(),
+        };
+
+        chat_room.send_message(
+            &vec![mentioned_user_id],
+            &serde_json::to_string(&mention_event_dto).unwrap(),
+        );
+    }
+
+    Ok(response_dto)
 }
 
 #[get("/")]
@@ -249,58 +658,126 @@ async fn get_by_channel_and_per_page(
         .parse::<Uuid>()
         .map_err(|e| ApiResponse::new(400, e.to_string()))?;
 
-    let page = query
-        .get("page")
-        .unwrap_or(&"1".to_string())
-        .parse::<u64>()
-        .unwrap_or(1);
-
     let per_page = query
         .get("per_page")
         .unwrap_or(&"30".to_string())
         .parse::<u64>()
         .unwrap_or(30);
 
-    let mut query = message::Entity::find()
-        .filter(message::Column::MessageType.eq("MESSAGE"))
+    let conversation_condition =
+        get_readable_conversation_condition(&app_state, user_id, &recipient_type, reference_id)
+            .await?;
+
+    let query_messages = message::Entity::find()
+        .filter(message::Column::MessageType.is_in(CONTENT_MESSAGE_TYPES))
         .filter(message::Column::RecipientType.eq(recipient_type.clone()))
         .filter(message::Column::Deleted.eq(false))
-        .order_by_desc(message::Column::DateCreated);
-
-    if recipient_type == "USER" {
-        query = query.filter(
-            message::Column::UserId
-                .eq(user_id)
-                .and(message::Column::ReferenceId.eq(reference_id))
-                .or(message::Column::UserId
-                    .eq(reference_id)
-                    .and(message::Column::ReferenceId.eq(user_id))),
-        );
-    }
+        .filter(message::Column::ParentId.is_null())
+        .filter(conversation_condition.clone());
+
+    // Messages are always returned newest first, keyed on (date_created, id) so
+    // that messages arriving while the user scrolls don't shift the window.
+    let messages = if let Some(around) = query.get("around") {
+        let around_id = around
+            .parse::<Uuid>()
+            .map_err(|e| ApiResponse::new(400, e.to_string()))?;
+
+        let anchor = query_messages
+            .clone()
+            .filter(message::Column::Id.eq(around_id))
+            .one(&app_state.db)
+            .await
+            .map_err(|e| ApiResponse::new(500, e.to_string()))?
+            .ok_or(ApiResponse::new(404, "Message not found".to_string()))?;
+
+        let cursor = MessageCursor::Message(anchor.date_created, anchor.id);
+
+        let mut newer = query_messages
+            .clone()
+            .filter(cursor.after_condition())
+            .order_by_asc(message::Column::DateCreated)
+            .order_by_asc(message::Column::Id)
+            .limit(per_page)
+            .all(&app_state.db)
+            .await
+            .map_err(|e| ApiResponse::new(500, e.to_string()))?;
+        newer.reverse();
+
+        let older = query_messages
+            .filter(cursor.before_condition())
+            .order_by_desc(message::Column::DateCreated)
+            .order_by_desc(message::Column::Id)
+            .limit(per_page)
+            .all(&app_state.db)
+            .await
+            .map_err(|e| ApiResponse::new(500, e.to_string()))?;
 
-    if recipient_type == "CHANNEL" {
-        query = query.filter(message::Column::ReferenceId.eq(reference_id));
+        newer.push(anchor);
+        newer.extend(older);
+        newer
+    } else if let Some(after) = query.get("after") {
+        let cursor =
+            parse_message_cursor(&app_state, after, &recipient_type, conversation_condition)
+                .await?;
+
+        let mut messages = query_messages
+            .filter(cursor.after_condition())
+            .order_by_asc(message::Column::DateCreated)
+            .order_by_asc(message::Column::Id)
+            .limit(per_page)
+            .all(&app_state.db)
+            .await
+            .map_err(|e| ApiResponse::new(500, e.to_string()))?;
+        messages.reverse();
+        messages
+    } else {
+        let mut query_messages = query_messages
+            .order_by_desc(message::Column::DateCreated)
+            .order_by_desc(message::Column::Id)
+            .limit(per_page);
 
-        let has_user_access_to_channel =
-            get_user_has_access_to_channel(app_state.clone(), user_id, reference_id).await?;
+        if let Some(before) = query.get("before") {
+            let cursor =
+                parse_message_cursor(&app_state, before, &recipient_type, conversation_condition)
+                    .await?;
 
-        if !has_user_access_to_channel {
-            return Err(ApiResponse::new(
-                400,
-                "User does not have access to channel".to_string(),
-            ));
+            query_messages = query_messages.filter(cursor.before_condition());
         }
-    }
-
-    // Paginate the combined query
-    let paginator = query.paginate(&app_state.db, per_page);
 
-    let messages = paginator
-        .fetch_page(page - 1)
-        .await
-        .map_err(|e| ApiResponse::new(500, e.to_string()))?;
+        query_messages
+            .all(&app_state.db)
+            .await
+            .map_err(|e| ApiResponse::new(500, e.to_string()))?
+    };
 
-    let response_dtos: Vec<MessageDTO> = messages.into_iter().map(MessageDTO::from).collect();
+    let mut reactions_by_message_id = get_reaction_counts_by_message_ids(
+        &app_state.db,
+        user_id,
+        messages.iter().map(|message| message.id).collect(),
+    )
+    .await?;
+
+    let mut polls_by_message_id = get_polls_by_message_ids(
+        &app_state.db,
+        Some(user_id),
+        messages.iter().map(|message| message.id).collect(),
+    )
+    .await?;
+
+    let response_dtos: Vec<MessageWithReactionsDTO> = messages
+        .into_iter()
+        .map(|message| {
+            let mut message_dto: MessageDTO = message.into();
+            message_dto.poll = polls_by_message_id.remove(&message_dto.id);
+
+            MessageWithReactionsDTO {
+                reactions: reactions_by_message_id
+                    .remove(&message_dto.id)
+                    .unwrap_or_default(),
+                message: message_dto,
+            }
+        })
+        .collect();
 
     Ok(ApiResponse::new(
         200,
@@ -352,6 +829,28 @@ async fn edit_message(
         ));
     }
 
+    let organization_id = get_organization_id_from_user_id(&app_state.db, user_id).await?;
+
+    let organization = organization::Entity::find()
+        .filter(organization::Column::Id.eq(organization_id))
+        .one(&app_state.db)
+        .await
+        .map_err(|e| ApiResponse::new(500, e.to_string()))?
+        .ok_or(ApiResponse::new(404, "Organization not found".to_string()))?;
+
+    if let Some(message_edit_window_seconds) = organization.message_edit_window_seconds {
+        // A window too long to represent never expires
+        let edit_deadline = chrono::TimeDelta::try_seconds(message_edit_window_seconds)
+            .and_then(|window| message_model.date_created.checked_add_signed(window));
+
+        if edit_deadline.is_some_and(|edit_deadline| Utc::now().naive_utc() > edit_deadline) {
+            return Err(ApiResponse::new(
+                403,
+                "The edit window for this message has expired.".to_string(),
+            ));
+        }
+    }
+
     let response_dto = MessageDTO {
         id: message_model.id,
         user_id: message_model.user_id,
@@ -362,6 +861,12 @@ async fn edit_message(
         recipient_type: message_model.recipient_type.to_string(),
         reference_id: message_model.reference_id,
         deleted: message_model.deleted,
+        parent_id: message_model.parent_id,
+        expires_at: message_model
+            .expires_at
+            .map(|expires_at| expires_at.to_string()),
+        client_message_id: message_model.client_message_id.clone(),
+        poll: None,
     };
 
     match message_model.recipient_type.as_str() {
@@ -395,10 +900,13 @@ async fn edit_message(
                 ));
             }
 
-            let user_ids =
+            let user_ids = if message_model.parent_id.is_some() {
+                get_array_of_readers_by_channel_id(&app_state, message_model.reference_id).await?
+            } else {
                 get_array_of_users_by_channel_id(app_state.clone(), message_model.reference_id)
                     .await
-                    .map_err(|e| ApiResponse::new(500, e.to_string()))?;
+                    .map_err(|e| ApiResponse::new(500, e.to_string()))?
+            };
 
             chat_room.send_message(&user_ids, &serde_json::to_string(&response_dto).unwrap());
         }
@@ -406,14 +914,31 @@ async fn edit_message(
             let user_ids = vec![message_model.reference_id, user_id];
             chat_room.send_message(&user_ids, &serde_json::to_string(&response_dto).unwrap());
         }
+        "CONVERSATION" => {
+            let user_ids =
+                get_conversation_participant_ids(&app_state.db, message_model.reference_id).await?;
+            chat_room.send_message(&user_ids, &serde_json::to_string(&response_dto).unwrap());
+        }
         _ => {
             return Err(ApiResponse::new(
                 400,
-                "Recipient type must be either CHANNEL or USER.".to_string(),
+                "Recipient type must be either CHANNEL, USER or CONVERSATION.".to_string(),
             ));
         }
     }
 
+    // Keep the version being replaced so the full edit history can be reviewed
+    message_revision::ActiveModel {
+        id: Set(Uuid::new_v4()),
+        message_id: Set(message_id),
+        content: Set(message_model.content.clone()),
+        edited_by: Set(user_id),
+        date_edited: Set(Utc::now().naive_utc()),
+    }
+    .insert(&app_state.db)
+    .await
+    .map_err(|e| ApiResponse::new(500, e.to_string()))?;
+
     let active_model = message::ActiveModel {
         id: Set(message_id),
         content: Set(message_edit_dto.content.clone()),
@@ -467,12 +992,18 @@ async fn delete_message(
         recipient_type: message_model.recipient_type.to_string(),
         reference_id: message_model.reference_id,
         deleted: true,
+        parent_id: message_model.parent_id,
+        expires_at: message_model
+            .expires_at
+            .map(|expires_at| expires_at.to_string()),
+        client_message_id: message_model.client_message_id.clone(),
+        poll: None,
     };
 
-    if message_model.message_type != "MESSAGE" {
+    if !CONTENT_MESSAGE_TYPES.contains(&message_model.message_type.as_str()) {
         return Err(ApiResponse::new(
             400,
-            "Message type is not MESSAGE".to_string(),
+            "Message type is not MESSAGE or POLL".to_string(),
         ));
     }
 
@@ -502,10 +1033,13 @@ async fn delete_message(
                 ));
             }
 
-            let user_ids =
+            let user_ids = if message_model.parent_id.is_some() {
+                get_array_of_readers_by_channel_id(&app_state, message_model.reference_id).await?
+            } else {
                 get_array_of_users_by_channel_id(app_state.clone(), message_model.reference_id)
                     .await
-                    .map_err(|e| ApiResponse::new(500, e.to_string()))?;
+                    .map_err(|e| ApiResponse::new(500, e.to_string()))?
+            };
 
             chat_room.send_message(&user_ids, &serde_json::to_string(&response_dto).unwrap());
         }
@@ -513,10 +1047,15 @@ async fn delete_message(
             let user_ids = vec![message_model.reference_id, user_id];
             chat_room.send_message(&user_ids, &serde_json::to_string(&response_dto).unwrap());
         }
+        "CONVERSATION" => {
+            let user_ids =
+                get_conversation_participant_ids(&app_state.db, message_model.reference_id).await?;
+            chat_room.send_message(&user_ids, &serde_json::to_string(&response_dto).unwrap());
+        }
         _ => {
             return Err(ApiResponse::new(
                 400,
-                "Recipient type must be either CHANNEL or USER.".to_string(),
+                "Recipient type must be either CHANNEL, USER or CONVERSATION.".to_string(),
             ));
         }
     }
@@ -534,6 +1073,8 @@ async fn delete_message(
         .await
         .map_err(|e| ApiResponse::new(500, e.to_string()))?;
 
+    mark_bookmarks_unavailable(&app_state.db, vec![message_id]).await?;
+
     log_info(req, format!("Deleted message {}", message_id));
 
     Ok(ApiResponse::new(
@@ -542,26 +1083,181 @@ async fn delete_message(
     ))
 }
 
-async fn get_array_of_users_by_channel_id(
-    app_state: web::Data<app_state::AppState>,
-    channel_id: Uuid,
-) -> Result<Vec<Uuid>, ApiResponse> {
-    let role_ids: Vec<Uuid> = channel_role_access::Entity::find()
-        .filter(channel_role_access::Column::ChannelId.eq(channel_id))
-        .filter(channel_role_access::Column::Deleted.eq(false))
-        .all(&app_state.db)
-        .await
-        .map_err(|e| ApiResponse::new(500, e.to_string()))?
-        .into_iter()
-        .map(|role_access| role_access.role_id)
-        .collect();
+async fn get_message_by_client_message_id(
+    app_state: &web::Data<app_state::AppState>,
+    user_id: Uuid,
+    client_message_id: Option<&str>,
+) -> Result<Option<message::Model>, ApiResponse> {
+    let Some(client_message_id) = client_message_id else {
+        return Ok(None);
+    };
 
-    let mut user_ids: HashSet<Uuid> = user_role_access::Entity::find()
-        .filter(user_role_access::Column::RoleId.is_in(role_ids.clone()))
-        .find_also_related(user::Entity)
-        .all(&app_state.db)
+    message::Entity::find()
+        .filter(message::Column::UserId.eq(user_id))
+        .filter(message::Column::ClientMessageId.eq(client_message_id))
+        .one(&app_state.db)
         .await
-        .map_err(|e| ApiResponse::new(500, e.to_string()))?
+        .map_err(|e| ApiResponse::new(500, e.to_string()))
+}
+
+/// The shorter of the message's own TTL and its channel's TTL, if either is set.
+async fn get_message_ttl_seconds(
+    app_state: &web::Data<app_state::AppState>,
+    message_send_dto: &MessageSendDTO,
+) -> Result<Option<i64>, ApiResponse> {
+    let channel_ttl_seconds = if message_send_dto.recipient_type == "CHANNEL" {
+        channel::Entity::find_by_id(message_send_dto.reference_id)
+            .one(&app_state.db)
+            .await
+            .map_err(|e| ApiResponse::new(500, e.to_string()))?
+            .and_then(|channel| channel.message_ttl_seconds)
+    } else {
+        None
+    };
+
+    Ok(match (message_send_dto.ttl_seconds, channel_ttl_seconds) {
+        (Some(ttl_seconds), Some(channel_ttl_seconds)) => {
+            Some(ttl_seconds.min(channel_ttl_seconds))
+        }
+        (ttl_seconds, channel_ttl_seconds) => ttl_seconds.or(channel_ttl_seconds),
+    })
+}
+
+/// Hard-deletes messages whose TTL has passed, together with their thread
+/// replies, media rows and stored objects, and tells clients they are gone.
+pub async fn delete_expired_messages(
+    app_state: &web::Data<app_state::AppState>,
+    chat_room: &web::Data<Arc<ChatRoom>>,
+    s3_client: &web::Data<s3::Client>,
+) -> Result<(), ApiResponse> {
+    let expired_message_ids: Vec<Uuid> = message::Entity::find()
+        .filter(message::Column::ExpiresAt.lte(Utc::now().naive_utc()))
+        .all(&app_state.db)
+        .await
+        .map_err(|e| ApiResponse::new(500, e.to_string()))?
+        .into_iter()
+        .map(|message_model| message_model.id)
+        .collect();
+
+    if expired_message_ids.is_empty() {
+        return Ok(());
+    }
+
+    let purged_messages = purge_messages(app_state, s3_client, expired_message_ids).await?;
+
+    for message_model in purged_messages.messages {
+        let user_ids = match message_model.recipient_type.as_str() {
+            "CHANNEL" => {
+                get_array_of_users_by_channel_id(app_state.clone(), message_model.reference_id)
+                    .await?
+            }
+            "CONVERSATION" => {
+                get_conversation_participant_ids(&app_state.db, message_model.reference_id).await?
+            }
+            _ => vec![message_model.reference_id, message_model.user_id],
+        };
+
+        let mut response_dto: MessageDTO = message_model.into();
+        response_dto.message_type = "DELETE_MESSAGE".to_string();
+        response_dto.content = None;
+        response_dto.deleted = true;
+
+        chat_room.send_message(&user_ids, &serde_json::to_string(&response_dto).unwrap());
+    }
+
+    Ok(())
+}
+
+pub(crate) struct PurgedMessages {
+    pub(crate) messages: Vec<message::Model>,
+    pub(crate) media_deleted: u64,
+}
+
+/// Hard-deletes messages together with their thread replies, `media` rows and
+/// stored objects. Bookmarks on them are kept but marked unavailable, read
+/// cursors pointing at them keep their timestamp.
+pub(crate) async fn purge_messages(
+    app_state: &web::Data<app_state::AppState>,
+    s3_client: &web::Data<s3::Client>,
+    message_ids: Vec<Uuid>,
+) -> Result<PurgedMessages, ApiResponse> {
+    // Replies are removed by the parent_id cascade, so they are collected here too
+    let message_models = message::Entity::find()
+        .filter(
+            Condition::any()
+                .add(message::Column::Id.is_in(message_ids.clone()))
+                .add(message::Column::ParentId.is_in(message_ids)),
+        )
+        .all(&app_state.db)
+        .await
+        .map_err(|e| ApiResponse::new(500, e.to_string()))?;
+
+    let message_ids: Vec<Uuid> = message_models
+        .iter()
+        .map(|message_model| message_model.id)
+        .collect();
+
+    let media_models = media::Entity::find()
+        .filter(media::Column::MessageId.is_in(message_ids.clone()))
+        .all(&app_state.db)
+        .await
+        .map_err(|e| ApiResponse::new(500, e.to_string()))?;
+
+    for media_model in media_models.iter() {
+        if !s3_client.delete_file(&media_model.key).await {
+            log::error!("Failed to delete file {} from storage", media_model.key);
+        }
+    }
+
+    let media_deleted = media::Entity::delete_many()
+        .filter(media::Column::Id.is_in(media_models.iter().map(|media_model| media_model.id)))
+        .exec(&app_state.db)
+        .await
+        .map_err(|e| ApiResponse::new(500, e.to_string()))?
+        .rows_affected;
+
+    mark_bookmarks_unavailable(&app_state.db, message_ids.clone()).await?;
+
+    message::Entity::delete_many()
+        .filter(message::Column::Id.is_in(message_ids))
+        .exec(&app_state.db)
+        .await
+        .map_err(|e| ApiResponse::new(500, e.to_string()))?;
+
+    Ok(PurgedMessages {
+        messages: message_models,
+        media_deleted,
+    })
+}
+
+pub async fn get_array_of_users_by_channel_id(
+    app_state: web::Data<app_state::AppState>,
+    channel_id: Uuid,
+) -> Result<Vec<Uuid>, ApiResponse> {
+    let channel_organization_id = channel::Entity::find()
+        .filter(channel::Column::Id.eq(channel_id))
+        .one(&app_state.db)
+        .await
+        .map_err(|e| ApiResponse::new(500, e.to_string()))?
+        .ok_or(ApiResponse::new(404, "Channel not found".to_string()))?
+        .organization_id;
+
+    let role_ids: Vec<Uuid> = channel_role_access::Entity::find()
+        .filter(channel_role_access::Column::ChannelId.eq(channel_id))
+        .filter(channel_role_access::Column::Deleted.eq(false))
+        .all(&app_state.db)
+        .await
+        .map_err(|e| ApiResponse::new(500, e.to_string()))?
+        .into_iter()
+        .map(|role_access| role_access.role_id)
+        .collect();
+
+    let mut user_ids: HashSet<Uuid> = user_role_access::Entity::find()
+        .filter(user_role_access::Column::RoleId.is_in(role_ids.clone()))
+        .find_also_related(user::Entity)
+        .all(&app_state.db)
+        .await
+        .map_err(|e| ApiResponse::new(500, e.to_string()))?
         .into_iter()
         .filter(|(_user_role_access, user_opt)| {
             if let Some(user) = user_opt {
@@ -579,6 +1275,7 @@ async fn get_array_of_users_by_channel_id(
                 .eq(true)
                 .or(role::Column::ManageChannels.eq(true)),
         )
+        .filter(role::Column::OrganizationId.eq(channel_organization_id))
         .all(&app_state.db)
         .await
         .map_err(|e| ApiResponse::new(500, e.to_string()))?
@@ -610,6 +1307,133 @@ async fn get_array_of_users_by_channel_id(
     Ok(unique_user_ids)
 }
 
+/// Checks that the user can read a channel, DM or conversation and returns the
+/// condition selecting its messages. Shared by history listing and export.
+pub(crate) async fn get_readable_conversation_condition(
+    app_state: &web::Data<app_state::AppState>,
+    user_id: Uuid,
+    recipient_type: &str,
+    reference_id: Uuid,
+) -> Result<Condition, ApiResponse> {
+    let conversation_condition = match recipient_type {
+        "USER" => Condition::any()
+            .add(
+                Condition::all()
+                    .add(message::Column::UserId.eq(user_id))
+                    .add(message::Column::ReferenceId.eq(reference_id)),
+            )
+            .add(
+                Condition::all()
+                    .add(message::Column::UserId.eq(reference_id))
+                    .add(message::Column::ReferenceId.eq(user_id)),
+            ),
+        _ => Condition::all().add(message::Column::ReferenceId.eq(reference_id)),
+    };
+
+    if recipient_type == "CHANNEL" {
+        let has_user_access_to_channel =
+            get_user_has_access_to_channel(app_state.clone(), user_id, reference_id).await?;
+
+        if !has_user_access_to_channel {
+            return Err(ApiResponse::new(
+                400,
+                "User does not have access to channel".to_string(),
+            ));
+        }
+    }
+
+    if recipient_type == "CONVERSATION" {
+        check_conversation_participant(&app_state.db, user_id, reference_id).await?;
+    }
+
+    Ok(conversation_condition)
+}
+
+pub(crate) enum MessageCursor {
+    Message(chrono::NaiveDateTime, Uuid),
+    Timestamp(chrono::NaiveDateTime),
+}
+
+impl MessageCursor {
+    fn before_condition(&self) -> Condition {
+        match self {
+            MessageCursor::Message(date_created, id) => Condition::any()
+                .add(message::Column::DateCreated.lt(*date_created))
+                .add(
+                    Condition::all()
+                        .add(message::Column::DateCreated.eq(*date_created))
+                        .add(message::Column::Id.lt(*id)),
+                ),
+            MessageCursor::Timestamp(date_created) => {
+                Condition::all().add(message::Column::DateCreated.lt(*date_created))
+            }
+        }
+    }
+
+    pub(crate) fn after_condition(&self) -> Condition {
+        match self {
+            MessageCursor::Message(date_created, id) => Condition::any()
+                .add(message::Column::DateCreated.gt(*date_created))
+                .add(
+                    Condition::all()
+                        .add(message::Column::DateCreated.eq(*date_created))
+                        .add(message::Column::Id.gt(*id)),
+                ),
+            MessageCursor::Timestamp(date_created) => {
+                Condition::all().add(message::Column::DateCreated.gt(*date_created))
+            }
+        }
+    }
+}
+
+/// Parses a `before`/`after` value, which is either a message id from the same
+/// conversation or an RFC 3339 timestamp.
+async fn parse_message_cursor(
+    app_state: &web::Data<app_state::AppState>,
+    value: &str,
+    recipient_type: &str,
+    conversation_condition: Condition,
+) -> Result<MessageCursor, ApiResponse> {
+    if let Ok(message_id) = value.parse::<Uuid>() {
+        let cursor_message = message::Entity::find()
+            .filter(message::Column::Id.eq(message_id))
+            .filter(message::Column::RecipientType.eq(recipient_type))
+            .filter(conversation_condition)
+            .one(&app_state.db)
+            .await
+            .map_err(|e| ApiResponse::new(500, e.to_string()))?
+            .ok_or(ApiResponse::new(404, "Message not found".to_string()))?;
+
+        return Ok(MessageCursor::Message(
+            cursor_message.date_created,
+            cursor_message.id,
+        ));
+    }
+
+    parse_timestamp(value)
+        .map(MessageCursor::Timestamp)
+        .ok_or(ApiResponse::new(
+            400,
+            "Cursor must be a message ID or an RFC 3339 timestamp.".to_string(),
+        ))
+}
+
+/// Accepts an RFC 3339 timestamp, a naive `YYYY-MM-DDTHH:MM:SS` timestamp taken
+/// as UTC, or a bare `YYYY-MM-DD` date taken as midnight UTC.
+pub(crate) fn parse_timestamp(value: &str) -> Option<chrono::NaiveDateTime> {
+    if let Ok(date_time) = chrono::DateTime::parse_from_rfc3339(value) {
+        return Some(date_time.naive_utc());
+    }
+
+    if let Ok(date_time) = chrono::NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M:%S%.f") {
+        return Some(date_time);
+    }
+
+    chrono::NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .ok()
+        .and_then(|date| date.and_hms_opt(0, 0, 0))
+}
+
 pub async fn get_user_has_access_to_channel(
     app_state: web::Data<app_state::AppState>,
     user_id: Uuid,
@@ -678,21 +1502,415 @@ pub async fn get_user_has_access_to_channel(
     Ok(user_role_access.is_some())
 }
 
+async fn validate_thread_parent(
+    app_state: &web::Data<app_state::AppState>,
+    user_id: Uuid,
+    parent_id: Uuid,
+    recipient_type: &str,
+    reference_id: Uuid,
+) -> Result<message::Model, ApiResponse> {
+    let parent = message::Entity::find()
+        .filter(message::Column::Id.eq(parent_id))
+        .filter(message::Column::Deleted.eq(false))
+        .one(&app_state.db)
+        .await
+        .map_err(|e| ApiResponse::new(500, e.to_string()))?
+        .ok_or(ApiResponse::new(
+            400,
+            "Parent message not found".to_string(),
+        ))?;
+
+    if parent.message_type != "MESSAGE" {
+        return Err(ApiResponse::new(
+            400,
+            "Parent message type is not MESSAGE".to_string(),
+        ));
+    }
+
+    // Threads are a single level deep, replies are always attached to the root
+    if parent.parent_id.is_some() {
+        return Err(ApiResponse::new(
+            400,
+            "Cannot reply to a message that is itself a reply".to_string(),
+        ));
+    }
+
+    let is_same_conversation = match recipient_type {
+        "CHANNEL" => parent.recipient_type == "CHANNEL" && parent.reference_id == reference_id,
+        "USER" => {
+            parent.recipient_type == "USER"
+                && ((parent.user_id == user_id && parent.reference_id == reference_id)
+                    || (parent.user_id == reference_id && parent.reference_id == user_id))
+        }
+        "CONVERSATION" => {
+            parent.recipient_type == "CONVERSATION" && parent.reference_id == reference_id
+        }
+        _ => false,
+    };
+
+    if !is_same_conversation {
+        return Err(ApiResponse::new(
+            400,
+            "Parent message belongs to a different conversation".to_string(),
+        ));
+    }
+
+    Ok(parent)
+}
+
+pub(crate) async fn check_user_can_read_message(
+    app_state: &web::Data<app_state::AppState>,
+    req: HttpRequest,
+    user_id: Uuid,
+    message_model: &message::Model,
+) -> Result<(), ApiResponse> {
+    match message_model.recipient_type.as_str() {
+        "USER" => {
+            if message_model.user_id != user_id && message_model.reference_id != user_id {
+                return Err(ApiResponse::new(
+                    403,
+                    "You do not have permission to view this message".to_string(),
+                ));
+            }
+        }
+        "CHANNEL" => {
+            let has_user_access_to_channel = get_user_has_access_to_channel(
+                app_state.clone(),
+                user_id,
+                message_model.reference_id,
+            )
+            .await?;
+
+            if !has_user_access_to_channel {
+                return Err(ApiResponse::new(
+                    400,
+                    "User does not have access to channel".to_string(),
+                ));
+            }
+
+            let user_can_read = check_chat_permission(
+                &app_state.db,
+                req,
+                ChatPermission::CanRead,
+                message_model.reference_id,
+            )
+            .await;
+
+            if !user_can_read {
+                return Err(ApiResponse::new(
+                    403,
+                    "You do not have permission to read this channel.".to_string(),
+                ));
+            }
+        }
+        "CONVERSATION" => {
+            check_conversation_participant(&app_state.db, user_id, message_model.reference_id)
+                .await?;
+        }
+        _ => {
+            return Err(ApiResponse::new(400, "Invalid recipient type".to_string()));
+        }
+    }
+
+    Ok(())
+}
+
+async fn get_readable_thread_roots(
+    app_state: &web::Data<app_state::AppState>,
+    req: HttpRequest,
+    user_id: Uuid,
+    message_ids: Vec<Uuid>,
+) -> Result<Vec<message::Model>, ApiResponse> {
+    let roots = message::Entity::find()
+        .filter(message::Column::Id.is_in(message_ids))
+        .filter(message::Column::ParentId.is_null())
+        .all(&app_state.db)
+        .await
+        .map_err(|e| ApiResponse::new(500, e.to_string()))?;
+
+    for root in roots.iter() {
+        check_user_can_read_message(app_state, req.clone(), user_id, root).await?;
+    }
+
+    Ok(roots)
+}
+
+/// Users that can read a channel under the same rules as `check_chat_permission`:
+/// administrators of the channel's organization and roles granted read or write access.
+pub(crate) async fn get_array_of_readers_by_channel_id(
+    app_state: &web::Data<app_state::AppState>,
+    channel_id: Uuid,
+) -> Result<Vec<Uuid>, ApiResponse> {
+    let channel_organization_id = channel::Entity::find()
+        .filter(channel::Column::Id.eq(channel_id))
+        .one(&app_state.db)
+        .await
+        .map_err(|e| ApiResponse::new(500, e.to_string()))?
+        .ok_or(ApiResponse::new(404, "Channel not found".to_string()))?
+        .organization_id;
+
+    let mut role_ids: Vec<Uuid> = channel_role_access::Entity::find()
+        .filter(
+            Condition::all()
+                .add(channel_role_access::Column::ChannelId.eq(channel_id))
+                .add(channel_role_access::Column::Deleted.eq(false))
+                .add(
+                    Condition::any()
+                        .add(channel_role_access::Column::CanRead.eq(true))
+                        .add(channel_role_access::Column::CanWrite.eq(true)),
+                ),
+        )
+        .all(&app_state.db)
+        .await
+        .map_err(|e| ApiResponse::new(500, e.to_string()))?
+        .into_iter()
+        .map(|role_access| role_access.role_id)
+        .collect();
+
+    let administrator_role_ids: Vec<Uuid> = role::Entity::find()
+        .filter(role::Column::Administrator.eq(true))
+        .filter(role::Column::OrganizationId.eq(channel_organization_id))
+        .all(&app_state.db)
+        .await
+        .map_err(|e| ApiResponse::new(500, e.to_string()))?
+        .into_iter()
+        .map(|role| role.id)
+        .collect();
+
+    role_ids.extend(administrator_role_ids);
+
+    let user_ids: HashSet<Uuid> = user_role_access::Entity::find()
+        .filter(user_role_access::Column::RoleId.is_in(role_ids))
+        .filter(user_role_access::Column::Deleted.eq(false))
+        .find_also_related(user::Entity)
+        .all(&app_state.db)
+        .await
+        .map_err(|e| ApiResponse::new(500, e.to_string()))?
+        .into_iter()
+        .filter(|(_user_role_access, user_opt)| {
+            if let Some(user) = user_opt {
+                !user.deleted
+            } else {
+                false
+            }
+        })
+        .map(|(user_role_access, _user)| user_role_access.user_id)
+        .collect();
+
+    Ok(user_ids.into_iter().collect())
+}
+
+#[get("/thread")]
+async fn get_thread(
+    req: HttpRequest,
+    app_state: web::Data<app_state::AppState>,
+    query: web::Query<HashMap<String, String>>,
+) -> Result<ApiResponse, ApiResponse> {
+    let user_id = get_user_id_from_http_request(req.clone())?;
+
+    let message_id = query
+        .get("message_id")
+        .ok_or(ApiResponse::new(400, "Message ID is required.".to_string()))?
+        .parse::<Uuid>()
+        .map_err(|e| ApiResponse::new(400, e.to_string()))?;
+
+    let page = query
+        .get("page")
+        .unwrap_or(&"1".to_string())
+        .parse::<u64>()
+        .unwrap_or(1)
+        .max(1);
+
+    let per_page = query
+        .get("per_page")
+        .unwrap_or(&"30".to_string())
+        .parse::<u64>()
+        .unwrap_or(30)
+        .clamp(1, MAX_PER_PAGE);
+
+    let offset = get_page_offset(page, per_page)?;
+
+    let root = message::Entity::find()
+        .filter(message::Column::Id.eq(message_id))
+        .one(&app_state.db)
+        .await
+        .map_err(|e| ApiResponse::new(500, e.to_string()))?
+        .ok_or(ApiResponse::new(404, "Message not found".to_string()))?;
+
+    if root.parent_id.is_some() {
+        return Err(ApiResponse::new(
+            400,
+            "Message is not the root of a thread".to_string(),
+        ));
+    }
+
+    check_user_can_read_message(&app_state, req, user_id, &root).await?;
+
+    let replies = message::Entity::find()
+        .filter(message::Column::ParentId.eq(root.id))
+        .filter(message::Column::Deleted.eq(false))
+        .order_by_asc(message::Column::DateCreated)
+        .offset(offset)
+        .limit(per_page)
+        .all(&app_state.db)
+        .await
+        .map_err(|e| ApiResponse::new(500, e.to_string()))?;
+
+    let response_dto = ThreadDTO {
+        root: root.into(),
+        replies: replies.into_iter().map(MessageDTO::from).collect(),
+    };
+
+    Ok(ApiResponse::new(
+        200,
+        serde_json::to_string(&response_dto).unwrap(),
+    ))
+}
+
+#[post("/thread/count")]
+async fn get_thread_reply_counts(
+    req: HttpRequest,
+    app_state: web::Data<app_state::AppState>,
+    message_ids_dto: web::Json<MessageIdsDTO>,
+) -> Result<ApiResponse, ApiResponse> {
+    let user_id = get_user_id_from_http_request(req.clone())?;
+
+    let roots =
+        get_readable_thread_roots(&app_state, req, user_id, message_ids_dto.ids.clone()).await?;
+
+    let root_ids: Vec<Uuid> = roots.iter().map(|root| root.id).collect();
+
+    let counts: HashMap<Uuid, i64> = message::Entity::find()
+        .select_only()
+        .column(message::Column::ParentId)
+        .column_as(message::Column::Id.count(), "reply_count")
+        .filter(message::Column::ParentId.is_in(root_ids.clone()))
+        .filter(message::Column::Deleted.eq(false))
+        .group_by(message::Column::ParentId)
+        .into_tuple::<(Uuid, i64)>()
+        .all(&app_state.db)
+        .await
+        .map_err(|e| ApiResponse::new(500, e.to_string()))?
+        .into_iter()
+        .collect();
+
+    let response_dtos: Vec<ThreadReplyCountDTO> = root_ids
+        .into_iter()
+        .map(|root_id| ThreadReplyCountDTO {
+            message_id: root_id,
+            reply_count: counts.get(&root_id).copied().unwrap_or(0),
+        })
+        .collect();
+
+    Ok(ApiResponse::new(
+        200,
+        serde_json::to_string(&response_dtos).unwrap(),
+    ))
+}
+
+#[post("/thread/latest")]
+async fn get_thread_latest_replies(
+    req: HttpRequest,
+    app_state: web::Data<app_state::AppState>,
+    message_ids_dto: web::Json<MessageIdsDTO>,
+) -> Result<ApiResponse, ApiResponse> {
+    let user_id = get_user_id_from_http_request(req.clone())?;
+
+    let roots =
+        get_readable_thread_roots(&app_state, req, user_id, message_ids_dto.ids.clone()).await?;
+
+    let root_ids: Vec<Uuid> = roots.iter().map(|root| root.id).collect();
+
+    // One row per thread, the newest reply first
+    let mut latest_replies: HashMap<Uuid, message::Model> = message::Entity::find()
+        .distinct_on([(message::Entity, message::Column::ParentId)])
+        .filter(message::Column::ParentId.is_in(root_ids.clone()))
+        .filter(message::Column::Deleted.eq(false))
+        .order_by_asc(message::Column::ParentId)
+        .order_by_desc(message::Column::DateCreated)
+        .order_by_desc(message::Column::Id)
+        .all(&app_state.db)
+        .await
+        .map_err(|e| ApiResponse::new(500, e.to_string()))?
+        .into_iter()
+        .filter_map(|reply| reply.parent_id.map(|parent_id| (parent_id, reply)))
+        .collect();
+
+    let response_dtos: Vec<ThreadLatestReplyDTO> = root_ids
+        .into_iter()
+        .map(|root_id| ThreadLatestReplyDTO {
+            message_id: root_id,
+            latest_reply: latest_replies.remove(&root_id).map(MessageDTO::from),
+        })
+        .collect();
+
+    Ok(ApiResponse::new(
+        200,
+        serde_json::to_string(&response_dtos).unwrap(),
+    ))
+}
+
+#[get("/revisions")]
+async fn get_message_revisions(
+    req: HttpRequest,
+    app_state: web::Data<app_state::AppState>,
+    query: web::Query<HashMap<String, String>>,
+) -> Result<ApiResponse, ApiResponse> {
+    let user_id = get_user_id_from_http_request(req.clone())?;
+
+    let message_id = query
+        .get("message_id")
+        .ok_or(ApiResponse::new(400, "Message ID is required.".to_string()))?
+        .parse::<Uuid>()
+        .map_err(|e| ApiResponse::new(400, e.to_string()))?;
+
+    let message_model = message::Entity::find()
+        .filter(message::Column::Id.eq(message_id))
+        .one(&app_state.db)
+        .await
+        .map_err(|e| ApiResponse::new(500, e.to_string()))?
+        .ok_or(ApiResponse::new(404, "Message not found".to_string()))?;
+
+    check_user_can_read_message(&app_state, req, user_id, &message_model).await?;
+
+    let revisions = message_revision::Entity::find()
+        .filter(message_revision::Column::MessageId.eq(message_id))
+        .order_by_asc(message_revision::Column::DateEdited)
+        .all(&app_state.db)
+        .await
+        .map_err(|e| ApiResponse::new(500, e.to_string()))?;
+
+    let response_dtos: Vec<MessageRevisionDTO> = revisions
+        .into_iter()
+        .map(MessageRevisionDTO::from)
+        .collect();
+
+    Ok(ApiResponse::new(
+        200,
+        serde_json::to_string(&response_dtos).unwrap(),
+    ))
+}
+
 #[get("/search")]
 pub async fn search_messages(
     app_state: web::Data<app_state::AppState>,
     req: HttpRequest,
     query: web::Query<HashMap<String, String>>,
 ) -> Result<ApiResponse, ApiResponse> {
-    let user_id = get_user_id_from_http_request(req)?;
+    let user_id = get_user_id_from_http_request(req.clone())?;
 
-    let content = query.get("content").unwrap_or(&"".to_string()).to_string();
+    let search_terms = match query.get("q") {
+        Some(search_query) => parse_search_query(search_query)?,
+        None => vec![],
+    };
 
-    let from_user_id = query
-        .get("user_id")
-        .unwrap_or(&Uuid::nil().to_string())
-        .parse::<Uuid>()
-        .unwrap_or(Uuid::nil());
+    let content = format!(
+        "{} {}",
+        query.get("content").unwrap_or(&"".to_string()),
+        get_search_text(&search_terms)
+    )
+    .trim()
+    .to_string();
 
     let recipient_type = query
         .get("recipient_type")
@@ -701,56 +1919,241 @@ pub async fn search_messages(
 
     let reference_id = query
         .get("reference_id")
-        .unwrap_or(&Uuid::nil().to_string())
-        .parse::<Uuid>()
-        .unwrap_or(Uuid::nil());
+        .map(|reference_id| reference_id.parse::<Uuid>())
+        .transpose()
+        .map_err(|e| ApiResponse::new(400, e.to_string()))?;
 
-    let mut query = message::Entity::find()
-        .filter(message::Column::MessageType.eq("MESSAGE"))
-        .filter(message::Column::Deleted.eq(false))
-        .order_by_desc(message::Column::DateCreated);
+    let from_user_id = query
+        .get("user_id")
+        .map(|from_user_id| from_user_id.parse::<Uuid>())
+        .transpose()
+        .map_err(|e| ApiResponse::new(400, e.to_string()))?;
 
-    if !content.is_empty() {
-        query = query.filter(message::Column::Content.contains(content));
-    }
+    let date_from = query
+        .get("date_from")
+        .map(|date_from| {
+            parse_timestamp(date_from).ok_or(ApiResponse::new(
+                400,
+                "date_from must be a date or an RFC 3339 timestamp.".to_string(),
+            ))
+        })
+        .transpose()?;
 
-    if from_user_id != Uuid::nil() {
-        query = query.filter(message::Column::UserId.eq(from_user_id));
-    }
+    let date_to = query
+        .get("date_to")
+        .map(|date_to| {
+            parse_timestamp(date_to).ok_or(ApiResponse::new(
+                400,
+                "date_to must be a date or an RFC 3339 timestamp.".to_string(),
+            ))
+        })
+        .transpose()?;
 
-    if recipient_type == "USER" && from_user_id != Uuid::nil() {
-        query = query.filter(message::Column::RecipientType.eq("USER"));
-        query = query.filter(message::Column::ReferenceId.eq(user_id));
-    } else if recipient_type == "CHANNEL" && reference_id != Uuid::nil() {
-        query = query.filter(message::Column::RecipientType.eq("CHANNEL"));
-        query = query.filter(message::Column::ReferenceId.eq(reference_id));
+    let has_media = query
+        .get("has_media")
+        .map(|has_media| has_media.parse::<bool>())
+        .transpose()
+        .map_err(|e| ApiResponse::new(400, e.to_string()))?;
 
-        let has_access_to_channel =
-            get_user_has_access_to_channel(app_state.clone(), user_id, reference_id).await?;
+    let page = query
+        .get("page")
+        .unwrap_or(&"1".to_string())
+        .parse::<u64>()
+        .unwrap_or(1)
+        .max(1);
+
+    let per_page = query
+        .get("per_page")
+        .unwrap_or(&"10".to_string())
+        .parse::<u64>()
+        .unwrap_or(10)
+        .clamp(1, MAX_PER_PAGE);
+
+    let offset = get_page_offset(page, per_page)?;
+
+    let channel_scope = match (recipient_type.as_str(), reference_id) {
+        ("CHANNEL", Some(reference_id)) => {
+            let has_access_to_channel =
+                get_user_has_access_to_channel(app_state.clone(), user_id, reference_id).await?;
+
+            if !has_access_to_channel {
+                return Err(ApiResponse::new(
+                    400,
+                    "User does not have access to channel".to_string(),
+                ));
+            }
+
+            let user_can_read = check_chat_permission(
+                &app_state.db,
+                req.clone(),
+                ChatPermission::CanRead,
+                reference_id,
+            )
+            .await;
+
+            if !user_can_read {
+                return Err(ApiResponse::new(
+                    403,
+                    "You do not have permission to read this channel.".to_string(),
+                ));
+            }
 
-        if !has_access_to_channel {
+            Some(vec![reference_id])
+        }
+        ("CHANNEL", None) | ("", _) => {
+            Some(get_chat_permission_channel_ids(&app_state.db, req, ChatPermission::CanRead).await)
+        }
+        ("USER", _) | ("CONVERSATION", _) => None,
+        _ => {
             return Err(ApiResponse::new(
                 400,
-                "User does not have access to channel".to_string(),
+                "Recipient type must be either CHANNEL, USER or CONVERSATION.".to_string(),
             ));
         }
-    } else {
-        return Err(ApiResponse::new(
-            400,
-            "Recipient type must be either USER or CHANNEL.".to_string(),
+    };
+
+    let mut scope_condition = Condition::any();
+
+    if let Some(channel_ids) = channel_scope {
+        scope_condition = scope_condition.add(
+            Condition::all()
+                .add(message::Column::RecipientType.eq("CHANNEL"))
+                .add(message::Column::ReferenceId.is_in(channel_ids)),
+        );
+    }
+
+    if recipient_type.is_empty() || recipient_type == "USER" {
+        let direct_message_condition = match reference_id.filter(|_| recipient_type == "USER") {
+            Some(other_user_id) => Condition::any()
+                .add(
+                    Condition::all()
+                        .add(message::Column::UserId.eq(user_id))
+                        .add(message::Column::ReferenceId.eq(other_user_id)),
+                )
+                .add(
+                    Condition::all()
+                        .add(message::Column::UserId.eq(other_user_id))
+                        .add(message::Column::ReferenceId.eq(user_id)),
+                ),
+            None => Condition::any()
+                .add(message::Column::UserId.eq(user_id))
+                .add(message::Column::ReferenceId.eq(user_id)),
+        };
+
+        scope_condition = scope_condition.add(
+            Condition::all()
+                .add(message::Column::RecipientType.eq("USER"))
+                .add(direct_message_condition),
+        );
+    }
+
+    if recipient_type.is_empty() || recipient_type == "CONVERSATION" {
+        let conversation_ids = match reference_id.filter(|_| recipient_type == "CONVERSATION") {
+            Some(conversation_id) => {
+                check_conversation_participant(&app_state.db, user_id, conversation_id).await?;
+                vec![conversation_id]
+            }
+            None => get_conversation_ids_by_user_id(&app_state.db, user_id).await?,
+        };
+
+        scope_condition = scope_condition.add(
+            Condition::all()
+                .add(message::Column::RecipientType.eq("CONVERSATION"))
+                .add(message::Column::ReferenceId.is_in(conversation_ids)),
+        );
+    }
+
+    let mut search_query = message::Entity::find()
+        .filter(message::Column::MessageType.is_in(CONTENT_MESSAGE_TYPES))
+        .filter(message::Column::Deleted.eq(false))
+        .filter(scope_condition);
+
+    if let Some(from_user_id) = from_user_id {
+        search_query = search_query.filter(message::Column::UserId.eq(from_user_id));
+    }
+
+    if let Some(date_from) = date_from {
+        search_query = search_query.filter(message::Column::DateCreated.gte(date_from));
+    }
+
+    if let Some(date_to) = date_to {
+        search_query = search_query.filter(message::Column::DateCreated.lt(date_to));
+    }
+
+    if let Some(has_media) = has_media {
+        let has_media_condition = get_has_condition(SearchHas::File);
+
+        search_query = search_query.filter(if has_media {
+            has_media_condition
+        } else {
+            has_media_condition.not()
+        });
+    }
+
+    if !search_terms.is_empty() {
+        let organization_id = get_organization_id_from_user_id(&app_state.db, user_id).await?;
+
+        search_query = search_query.filter(get_search_condition(
+            &search_terms,
+            organization_id,
+            user_id,
         ));
     }
 
-    let messages = query
-        .limit(10)
+    if content.is_empty() {
+        search_query = search_query
+            .column_as(Expr::cust("0::real"), "rank")
+            .column_as(Expr::cust(ESCAPED_CONTENT), "snippet");
+    } else {
+        search_query = search_query
+            .filter(Expr::cust_with_values(
+                "message.content_tsv @@ websearch_to_tsquery('english', $1)",
+                [content.clone()],
+            ))
+            .column_as(
+                Expr::cust_with_values(
+                    "ts_rank(message.content_tsv, websearch_to_tsquery('english', $1))",
+                    [content.clone()],
+                ),
+                "rank",
+            )
+            .column_as(
+                Expr::cust_with_values(
+                    format!(
+                        "ts_headline('english', {}, websearch_to_tsquery('english', $1), 'StartSel=<mark>, StopSel=</mark>, MaxFragments=2')",
+                        ESCAPED_CONTENT
+                    ),
+                    [content],
+                ),
+                "snippet",
+            )
+            .order_by_desc(Expr::cust("rank"));
+    }
+
+    let search_results = search_query
+        .order_by_desc(message::Column::DateCreated)
+        .offset(offset)
+        .limit(per_page)
+        .into_model::<MessageSearchResult>()
         .all(&app_state.db)
         .await
         .map_err(|e| ApiResponse::new(500, e.to_string()))?;
 
-    let response_dtos: Vec<MessageDTO> = messages.into_iter().map(MessageDTO::from).collect();
+    let response_dtos: Vec<MessageSearchResultDTO> = search_results
+        .into_iter()
+        .map(MessageSearchResultDTO::from)
+        .collect();
 
     Ok(ApiResponse::new(
         200,
         serde_json::to_string(&response_dtos).unwrap(),
     ))
 }
+
+/// Offset of a 1-based page, rejecting pages past what the database can skip.
+pub(crate) fn get_page_offset(page: u64, per_page: u64) -> Result<u64, ApiResponse> {
+    page.saturating_sub(1)
+        .checked_mul(per_page)
+        .filter(|offset| *offset <= i64::MAX as u64)
+        .ok_or(ApiResponse::new(400, "Page is out of range.".to_string()))
+}
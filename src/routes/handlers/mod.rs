@@ -1,13 +1,21 @@
 pub mod auth_handler;
+pub(crate) mod bookmark_handler;
 pub(crate) mod channel_handler;
 pub(crate) mod channel_role_access_handler;
 pub(crate) mod chat_handler;
+pub(crate) mod command_handler;
+pub(crate) mod conversation_handler;
+pub(crate) mod export_handler;
 pub(crate) mod flag_handler;
 pub mod media_handler;
 pub(crate) mod message_handler;
 pub(crate) mod organization_handler;
+pub(crate) mod pin_handler;
+pub(crate) mod poll_handler;
 pub(crate) mod presence_handler;
+pub(crate) mod reaction_handler;
 pub(crate) mod role_handler;
+pub(crate) mod scheduled_message_handler;
 pub(crate) mod seen_message_handler;
 pub(crate) mod user_channel_view_handler;
 pub(crate) mod user_handler;
@@ -1,9 +1,20 @@
+use crate::routes::handlers::message_handler::{
+    get_page_offset, MAX_MESSAGE_EDIT_WINDOW_SECONDS, MAX_PER_PAGE,
+};
 use crate::utils::api_response::ApiResponse;
-use crate::utils::jwt::get_client_secret_from_request;
+use crate::utils::jwt::{get_client_secret_from_request, get_user_id_from_http_request};
+use crate::utils::logging::log_info;
+use crate::utils::organization_util::get_organization_id_from_user_id;
+use crate::utils::permissions::{check_permission, Permission};
+use crate::utils::retention::MAX_RETENTION_DAYS;
+use crate::utils::serde_util::deserialize_some;
 use crate::utils::{app_state, constants};
-use actix_web::{get, web, HttpRequest};
-use entity::organization;
-use sea_orm::{ColumnTrait, EntityTrait, QueryFilter};
+use actix_web::{get, patch, web, HttpRequest};
+use entity::{organization, retention_action};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, EntityTrait, IntoActiveModel, QueryFilter, QueryOrder,
+    QuerySelect, Set,
+};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use uuid::Uuid;
@@ -13,12 +24,67 @@ struct OrganizationDTO {
     id: Uuid,
 }
 
+#[derive(Serialize, Deserialize)]
+struct OrganizationSettingsDTO {
+    message_edit_window_seconds: Option<i64>,
+    message_retention_days: Option<i64>,
+    dm_retention_days: Option<i64>,
+}
+
+/// Settings left out of the request keep their stored value, `null` clears them.
+#[derive(Serialize, Deserialize)]
+struct OrganizationSettingsUpdateDTO {
+    #[serde(default, deserialize_with = "deserialize_some")]
+    message_edit_window_seconds: Option<Option<i64>>,
+    #[serde(default, deserialize_with = "deserialize_some")]
+    message_retention_days: Option<Option<i64>>,
+    #[serde(default, deserialize_with = "deserialize_some")]
+    dm_retention_days: Option<Option<i64>>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct RetentionActionDTO {
+    id: Uuid,
+    recipient_type: String,
+    reference_id: Option<Uuid>,
+    cutoff: String,
+    messages_deleted: i64,
+    media_deleted: i64,
+    user_channel_views_deleted: i64,
+    date_created: String,
+}
+
+impl From<retention_action::Model> for RetentionActionDTO {
+    fn from(model: retention_action::Model) -> Self {
+        Self {
+            id: model.id,
+            recipient_type: model.recipient_type,
+            reference_id: model.reference_id,
+            cutoff: model.cutoff.to_string(),
+            messages_deleted: model.messages_deleted,
+            media_deleted: model.media_deleted,
+            user_channel_views_deleted: model.user_channel_views_deleted,
+            date_created: model.date_created.to_string(),
+        }
+    }
+}
+
 impl From<organization::Model> for OrganizationDTO {
     fn from(model: organization::Model) -> Self {
         Self { id: model.id }
     }
 }
 
+impl From<organization::Model> for OrganizationSettingsDTO {
+    fn from(model: organization::Model) -> Self {
+        Self {
+            message_edit_window_seconds: model.message_edit_window_seconds,
+            message_retention_days: model.message_retention_days,
+            dm_retention_days: model.dm_retention_days,
+        }
+    }
+}
+
 #[get("/")]
 pub async fn get_organization_exists(
     app_state: web::Data<app_state::AppState>,
@@ -67,3 +133,163 @@ pub async fn get_all_organizations(
         serde_json::to_string(&response_dtos).unwrap(),
     ))
 }
+
+#[get("/settings")]
+pub async fn get_organization_settings(
+    req: HttpRequest,
+    app_state: web::Data<app_state::AppState>,
+) -> Result<ApiResponse, ApiResponse> {
+    let user_id = get_user_id_from_http_request(req)?;
+
+    let organization_id = get_organization_id_from_user_id(&app_state.db, user_id).await?;
+
+    let organization = organization::Entity::find()
+        .filter(organization::Column::Id.eq(organization_id))
+        .one(&app_state.db)
+        .await
+        .map_err(|e| ApiResponse::new(500, e.to_string()))?
+        .ok_or(ApiResponse::new(404, "Organization not found".to_string()))?;
+
+    Ok(ApiResponse::new(
+        200,
+        serde_json::to_string(&OrganizationSettingsDTO::from(organization)).unwrap(),
+    ))
+}
+
+#[patch("/settings")]
+pub async fn update_organization_settings(
+    req: HttpRequest,
+    app_state: web::Data<app_state::AppState>,
+    settings_dto: web::Json<OrganizationSettingsUpdateDTO>,
+) -> Result<ApiResponse, ApiResponse> {
+    let user_id = get_user_id_from_http_request(req.clone())?;
+
+    let is_admin = check_permission(&app_state.db, req.clone(), Permission::Administrator).await;
+
+    if !is_admin {
+        return Err(ApiResponse::new(
+            403,
+            "You do not have permission to manage organization settings.".to_string(),
+        ));
+    }
+
+    if settings_dto
+        .message_edit_window_seconds
+        .flatten()
+        .is_some_and(|seconds| !(0..=MAX_MESSAGE_EDIT_WINDOW_SECONDS).contains(&seconds))
+    {
+        return Err(ApiResponse::new(
+            400,
+            format!(
+                "Message edit window must be between 0 and {} seconds.",
+                MAX_MESSAGE_EDIT_WINDOW_SECONDS
+            ),
+        ));
+    }
+
+    if [
+        settings_dto.message_retention_days.flatten(),
+        settings_dto.dm_retention_days.flatten(),
+    ]
+    .iter()
+    .flatten()
+    .any(|retention_days| !(1..=MAX_RETENTION_DAYS).contains(retention_days))
+    {
+        return Err(ApiResponse::new(
+            400,
+            format!(
+                "Retention must be between 1 and {} days.",
+                MAX_RETENTION_DAYS
+            ),
+        ));
+    }
+
+    let organization_id = get_organization_id_from_user_id(&app_state.db, user_id).await?;
+
+    let organization = organization::Entity::find()
+        .filter(organization::Column::Id.eq(organization_id))
+        .one(&app_state.db)
+        .await
+        .map_err(|e| ApiResponse::new(500, e.to_string()))?
+        .ok_or(ApiResponse::new(404, "Organization not found".to_string()))?;
+
+    let mut organization = organization.into_active_model();
+    if let Some(message_edit_window_seconds) = settings_dto.message_edit_window_seconds {
+        organization.message_edit_window_seconds = Set(message_edit_window_seconds);
+    }
+    if let Some(message_retention_days) = settings_dto.message_retention_days {
+        organization.message_retention_days = Set(message_retention_days);
+    }
+    if let Some(dm_retention_days) = settings_dto.dm_retention_days {
+        organization.dm_retention_days = Set(dm_retention_days);
+    }
+
+    let updated_organization = organization
+        .update(&app_state.db)
+        .await
+        .map_err(|e| ApiResponse::new(500, e.to_string()))?;
+
+    log_info(
+        req,
+        format!("Updated settings of organization {}", organization_id),
+    );
+
+    Ok(ApiResponse::new(
+        200,
+        serde_json::to_string(&OrganizationSettingsDTO::from(updated_organization)).unwrap(),
+    ))
+}
+
+/// Lists what the retention worker has removed from the organization, newest first.
+#[get("/retention-actions")]
+pub async fn get_retention_actions(
+    req: HttpRequest,
+    app_state: web::Data<app_state::AppState>,
+    query: web::Query<HashMap<String, String>>,
+) -> Result<ApiResponse, ApiResponse> {
+    let user_id = get_user_id_from_http_request(req.clone())?;
+
+    let is_admin = check_permission(&app_state.db, req, Permission::Administrator).await;
+
+    if !is_admin {
+        return Err(ApiResponse::new(
+            403,
+            "You do not have permission to view retention actions.".to_string(),
+        ));
+    }
+
+    let page = query
+        .get("page")
+        .and_then(|page| page.parse::<u64>().ok())
+        .unwrap_or(1)
+        .max(1);
+
+    let per_page = query
+        .get("per_page")
+        .and_then(|per_page| per_page.parse::<u64>().ok())
+        .unwrap_or(50)
+        .clamp(1, MAX_PER_PAGE);
+
+    let offset = get_page_offset(page, per_page)?;
+
+    let organization_id = get_organization_id_from_user_id(&app_state.db, user_id).await?;
+
+    let retention_actions = retention_action::Entity::find()
+        .filter(retention_action::Column::OrganizationId.eq(organization_id))
+        .order_by_desc(retention_action::Column::DateCreated)
+        .offset(offset)
+        .limit(per_page)
+        .all(&app_state.db)
+        .await
+        .map_err(|e| ApiResponse::new(500, e.to_string()))?;
+
+    let response_dtos: Vec<RetentionActionDTO> = retention_actions
+        .into_iter()
+        .map(RetentionActionDTO::from)
+        .collect();
+
+    Ok(ApiResponse::new(
+        200,
+        serde_json::to_string(&response_dtos).unwrap(),
+    ))
+}
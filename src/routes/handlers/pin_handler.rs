@@ -0,0 +1,344 @@
+use crate::routes::handlers::conversation_handler::{
+    check_conversation_participant, get_conversation_participant_ids,
+};
+use crate::routes::handlers::message_handler::{
+    get_array_of_readers_by_channel_id, get_user_has_access_to_channel, MessageDTO,
+};
+use crate::utils::api_response::ApiResponse;
+use crate::utils::app_state;
+use crate::utils::chat::ChatRoom;
+use crate::utils::jwt::get_user_id_from_http_request;
+use crate::utils::logging::log_info;
+use crate::utils::permissions::{
+    check_chat_permission, check_permission, ChatPermission, Permission,
+};
+use actix_web::{delete, get, post, web, HttpRequest, Result};
+use chrono::Utc;
+use entity::{message, pinned_message};
+use sea_orm::ActiveValue::Set;
+use sea_orm::{ActiveModelTrait, ColumnTrait, EntityTrait, ModelTrait, QueryFilter, QueryOrder};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use uuid::Uuid;
+
+#[derive(Serialize, Deserialize)]
+struct PinMessageDTO {
+    message_id: Uuid,
+}
+
+#[derive(Serialize, Deserialize)]
+struct PinnedMessageDTO {
+    id: Uuid,
+    message_id: Uuid,
+    pinned_by: Uuid,
+    date_pinned: String,
+    message: MessageDTO,
+}
+
+#[derive(Serialize, Deserialize)]
+struct PinEventDTO {
+    message_type: String,
+    message_id: Uuid,
+    user_id: Uuid,
+    recipient_type: String,
+    reference_id: Uuid,
+}
+
+#[post("/")]
+pub async fn pin_message(
+    req: HttpRequest,
+    app_state: web::Data<app_state::AppState>,
+    pin_message_dto: web::Json<PinMessageDTO>,
+    chat_room: web::Data<Arc<ChatRoom>>,
+) -> Result<ApiResponse, ApiResponse> {
+    let user_id = get_user_id_from_http_request(req.clone())?;
+
+    let message_model =
+        get_pinnable_message(&app_state, req.clone(), user_id, pin_message_dto.message_id).await?;
+
+    let existing_pin = pinned_message::Entity::find()
+        .filter(pinned_message::Column::MessageId.eq(message_model.id))
+        .one(&app_state.db)
+        .await
+        .map_err(|e| ApiResponse::new(500, e.to_string()))?;
+
+    if existing_pin.is_some() {
+        return Err(ApiResponse::new(
+            409,
+            "Message is already pinned".to_string(),
+        ));
+    }
+
+    let pinned_message_model = pinned_message::ActiveModel {
+        id: Set(Uuid::new_v4()),
+        message_id: Set(message_model.id),
+        recipient_type: Set(message_model.recipient_type.clone()),
+        reference_id: Set(message_model.reference_id),
+        pinned_by: Set(user_id),
+        date_pinned: Set(Utc::now().naive_utc()),
+    }
+    .insert(&app_state.db)
+    .await
+    .map_err(|e| ApiResponse::new(500, e.to_string()))?;
+
+    log_info(req, format!("Pinned message {}", message_model.id));
+
+    send_pin_event(&app_state, &chat_room, &message_model, user_id, "PIN_ADDED").await?;
+
+    let response_dto = PinnedMessageDTO {
+        id: pinned_message_model.id,
+        message_id: pinned_message_model.message_id,
+        pinned_by: pinned_message_model.pinned_by,
+        date_pinned: pinned_message_model.date_pinned.to_string(),
+        message: message_model.into(),
+    };
+
+    Ok(ApiResponse::new(
+        200,
+        serde_json::to_string(&response_dto).unwrap(),
+    ))
+}
+
+#[delete("/")]
+pub async fn unpin_message(
+    req: HttpRequest,
+    app_state: web::Data<app_state::AppState>,
+    pin_message_dto: web::Json<PinMessageDTO>,
+    chat_room: web::Data<Arc<ChatRoom>>,
+) -> Result<ApiResponse, ApiResponse> {
+    let user_id = get_user_id_from_http_request(req.clone())?;
+
+    let message_model =
+        get_pinnable_message(&app_state, req.clone(), user_id, pin_message_dto.message_id).await?;
+
+    let existing_pin = pinned_message::Entity::find()
+        .filter(pinned_message::Column::MessageId.eq(message_model.id))
+        .one(&app_state.db)
+        .await
+        .map_err(|e| ApiResponse::new(500, e.to_string()))?
+        .ok_or(ApiResponse::new(404, "Message is not pinned".to_string()))?;
+
+    existing_pin
+        .delete(&app_state.db)
+        .await
+        .map_err(|e| ApiResponse::new(500, e.to_string()))?;
+
+    log_info(req, format!("Unpinned message {}", message_model.id));
+
+    let event_dto = send_pin_event(
+        &app_state,
+        &chat_room,
+        &message_model,
+        user_id,
+        "PIN_REMOVED",
+    )
+    .await?;
+
+    Ok(ApiResponse::new(
+        200,
+        serde_json::to_string(&event_dto).unwrap(),
+    ))
+}
+
+#[get("/")]
+pub async fn get_pinned_messages(
+    req: HttpRequest,
+    app_state: web::Data<app_state::AppState>,
+    query: web::Query<HashMap<String, String>>,
+) -> Result<ApiResponse, ApiResponse> {
+    let user_id = get_user_id_from_http_request(req.clone())?;
+
+    let recipient_type = query
+        .get("recipient_type")
+        .unwrap_or(&"CHANNEL".to_string())
+        .to_string();
+
+    let reference_id = query
+        .get("reference_id")
+        .ok_or(ApiResponse::new(
+            400,
+            "Reference ID is required.".to_string(),
+        ))?
+        .parse::<Uuid>()
+        .map_err(|e| ApiResponse::new(400, e.to_string()))?;
+
+    let mut query = pinned_message::Entity::find()
+        .find_also_related(message::Entity)
+        .filter(pinned_message::Column::RecipientType.eq(recipient_type.clone()))
+        .filter(message::Column::Deleted.eq(false))
+        .order_by_desc(pinned_message::Column::DatePinned);
+
+    match recipient_type.as_str() {
+        "CHANNEL" => {
+            let user_can_read =
+                check_chat_permission(&app_state.db, req, ChatPermission::CanRead, reference_id)
+                    .await;
+
+            if !user_can_read {
+                return Err(ApiResponse::new(
+                    403,
+                    "You do not have permission to read this channel.".to_string(),
+                ));
+            }
+
+            query = query.filter(pinned_message::Column::ReferenceId.eq(reference_id));
+        }
+        "USER" => {
+            query = query.filter(
+                message::Column::UserId
+                    .eq(user_id)
+                    .and(message::Column::ReferenceId.eq(reference_id))
+                    .or(message::Column::UserId
+                        .eq(reference_id)
+                        .and(message::Column::ReferenceId.eq(user_id))),
+            );
+        }
+        "CONVERSATION" => {
+            check_conversation_participant(&app_state.db, user_id, reference_id).await?;
+
+            query = query.filter(pinned_message::Column::ReferenceId.eq(reference_id));
+        }
+        _ => {
+            return Err(ApiResponse::new(
+                400,
+                "Recipient type must be either CHANNEL, USER or CONVERSATION.".to_string(),
+            ));
+        }
+    }
+
+    let pinned_messages = query
+        .all(&app_state.db)
+        .await
+        .map_err(|e| ApiResponse::new(500, e.to_string()))?;
+
+    let response_dtos: Vec<PinnedMessageDTO> = pinned_messages
+        .into_iter()
+        .filter_map(|(pinned_message_model, message_model)| {
+            message_model.map(|message_model| PinnedMessageDTO {
+                id: pinned_message_model.id,
+                message_id: pinned_message_model.message_id,
+                pinned_by: pinned_message_model.pinned_by,
+                date_pinned: pinned_message_model.date_pinned.to_string(),
+                message: message_model.into(),
+            })
+        })
+        .collect();
+
+    Ok(ApiResponse::new(
+        200,
+        serde_json::to_string(&response_dtos).unwrap(),
+    ))
+}
+
+async fn get_pinnable_message(
+    app_state: &web::Data<app_state::AppState>,
+    req: HttpRequest,
+    user_id: Uuid,
+    message_id: Uuid,
+) -> Result<message::Model, ApiResponse> {
+    let has_pin_messages =
+        check_permission(&app_state.db, req.clone(), Permission::PinMessages).await;
+
+    if !has_pin_messages {
+        return Err(ApiResponse::new(
+            403,
+            "You do not have permission to pin messages.".to_string(),
+        ));
+    }
+
+    let message_model = message::Entity::find()
+        .filter(message::Column::Id.eq(message_id))
+        .filter(message::Column::Deleted.eq(false))
+        .one(&app_state.db)
+        .await
+        .map_err(|e| ApiResponse::new(500, e.to_string()))?
+        .ok_or(ApiResponse::new(404, "Message not found".to_string()))?;
+
+    if message_model.message_type != "MESSAGE" {
+        return Err(ApiResponse::new(
+            400,
+            "Message type is not MESSAGE".to_string(),
+        ));
+    }
+
+    match message_model.recipient_type.as_str() {
+        "CHANNEL" => {
+            let has_user_access_to_channel = get_user_has_access_to_channel(
+                app_state.clone(),
+                user_id,
+                message_model.reference_id,
+            )
+            .await?;
+
+            if !has_user_access_to_channel {
+                return Err(ApiResponse::new(
+                    400,
+                    "User does not have access to channel".to_string(),
+                ));
+            }
+
+            let user_can_read = check_chat_permission(
+                &app_state.db,
+                req,
+                ChatPermission::CanRead,
+                message_model.reference_id,
+            )
+            .await;
+
+            if !user_can_read {
+                return Err(ApiResponse::new(
+                    403,
+                    "You do not have permission to read this channel.".to_string(),
+                ));
+            }
+        }
+        "USER" => {
+            if message_model.user_id != user_id && message_model.reference_id != user_id {
+                return Err(ApiResponse::new(
+                    403,
+                    "You do not have permission to view this message".to_string(),
+                ));
+            }
+        }
+        "CONVERSATION" => {
+            check_conversation_participant(&app_state.db, user_id, message_model.reference_id)
+                .await?;
+        }
+        _ => {
+            return Err(ApiResponse::new(400, "Invalid recipient type".to_string()));
+        }
+    }
+
+    Ok(message_model)
+}
+
+async fn send_pin_event(
+    app_state: &web::Data<app_state::AppState>,
+    chat_room: &web::Data<Arc<ChatRoom>>,
+    message_model: &message::Model,
+    user_id: Uuid,
+    message_type: &str,
+) -> Result<PinEventDTO, ApiResponse> {
+    let event_dto = PinEventDTO {
+        message_type: message_type.to_string(),
+        message_id: message_model.id,
+        user_id,
+        recipient_type: message_model.recipient_type.clone(),
+        reference_id: message_model.reference_id,
+    };
+
+    let user_ids = match message_model.recipient_type.as_str() {
+        "CHANNEL" => {
+            get_array_of_readers_by_channel_id(app_state, message_model.reference_id).await?
+        }
+        "CONVERSATION" => {
+            get_conversation_participant_ids(&app_state.db, message_model.reference_id).await?
+        }
+        _ => vec![message_model.user_id, message_model.reference_id],
+    };
+
+    chat_room.send_message(&user_ids, &serde_json::to_string(&event_dto).unwrap());
+
+    Ok(event_dto)
+}
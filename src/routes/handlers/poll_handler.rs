@@ -0,0 +1,536 @@
+use crate::routes::handlers::conversation_handler::get_conversation_participant_ids;
+use crate::routes::handlers::message_handler::{
+    check_user_can_read_message, get_array_of_readers_by_channel_id, parse_timestamp,
+    MessageSendDTO,
+};
+use crate::utils::api_response::ApiResponse;
+use crate::utils::app_state;
+use crate::utils::chat::ChatRoom;
+use crate::utils::jwt::get_user_id_from_http_request;
+use crate::utils::logging::log_info;
+use actix_web::{delete, get, post, web, HttpRequest, Result};
+use chrono::Utc;
+use entity::{message, poll, poll_option, poll_vote};
+use sea_orm::ActiveValue::Set;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, ModelTrait, QueryFilter,
+    QueryOrder, QuerySelect, TransactionTrait,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use uuid::Uuid;
+
+const MIN_POLL_OPTIONS: usize = 2;
+const MAX_POLL_OPTIONS: usize = 10;
+const MAX_POLL_OPTION_LENGTH: usize = 200;
+
+#[derive(Serialize, Deserialize)]
+pub(crate) struct PollCreateDTO {
+    options: Vec<String>,
+    #[serde(default)]
+    multiple_choice: bool,
+    #[serde(default)]
+    anonymous: bool,
+    closes_at: Option<String>,
+}
+
+impl PollCreateDTO {
+    /// A single-choice, non-anonymous poll that stays open.
+    pub(crate) fn new(options: Vec<String>) -> Self {
+        Self {
+            options,
+            multiple_choice: false,
+            anonymous: false,
+            closes_at: None,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct PollVoteDTO {
+    poll_id: Uuid,
+    option_id: Uuid,
+}
+
+#[derive(Serialize, Deserialize)]
+pub(crate) struct PollDTO {
+    id: Uuid,
+    message_id: Uuid,
+    multiple_choice: bool,
+    anonymous: bool,
+    closes_at: Option<String>,
+    closed: bool,
+    total_voters: usize,
+    options: Vec<PollOptionDTO>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct PollOptionDTO {
+    id: Uuid,
+    text: String,
+    position: i32,
+    vote_count: usize,
+    /// Left out for anonymous polls.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    voters: Option<Vec<Uuid>>,
+    /// Left out of tallies broadcast to everyone.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    voted: Option<bool>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct PollEventDTO {
+    message_type: String,
+    recipient_type: String,
+    reference_id: Uuid,
+    poll: PollDTO,
+}
+
+/// A POLL message must carry a poll and nothing else may. Polls are published
+/// immediately, so they can't be scheduled.
+pub(crate) fn validate_poll(message_send_dto: &MessageSendDTO) -> Result<(), ApiResponse> {
+    let Some(poll_create_dto) = message_send_dto.poll.as_ref() else {
+        if message_send_dto.message_type == "POLL" {
+            return Err(ApiResponse::new(
+                400,
+                "A POLL message requires a poll.".to_string(),
+            ));
+        }
+
+        return Ok(());
+    };
+
+    if message_send_dto.message_type != "POLL" {
+        return Err(ApiResponse::new(
+            400,
+            "Only POLL messages can have a poll.".to_string(),
+        ));
+    }
+
+    if message_send_dto.send_at.is_some() {
+        return Err(ApiResponse::new(
+            400,
+            "Polls cannot be scheduled.".to_string(),
+        ));
+    }
+
+    if poll_create_dto.options.len() < MIN_POLL_OPTIONS
+        || poll_create_dto.options.len() > MAX_POLL_OPTIONS
+    {
+        return Err(ApiResponse::new(
+            400,
+            format!(
+                "A poll must have between {} and {} options.",
+                MIN_POLL_OPTIONS, MAX_POLL_OPTIONS
+            ),
+        ));
+    }
+
+    if poll_create_dto.options.iter().any(|option| {
+        option.trim().is_empty() || option.trim().chars().count() > MAX_POLL_OPTION_LENGTH
+    }) {
+        return Err(ApiResponse::new(
+            400,
+            format!(
+                "Poll options must be between 1 and {} characters.",
+                MAX_POLL_OPTION_LENGTH
+            ),
+        ));
+    }
+
+    if let Some(closes_at) = poll_create_dto.closes_at.as_deref() {
+        let closes_at = parse_timestamp(closes_at).ok_or(ApiResponse::new(
+            400,
+            "closes_at must be an RFC 3339 timestamp.".to_string(),
+        ))?;
+
+        if closes_at <= Utc::now().naive_utc() {
+            return Err(ApiResponse::new(
+                400,
+                "closes_at must be in the future.".to_string(),
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+pub(crate) async fn create_poll(
+    db: &DatabaseConnection,
+    message_id: Uuid,
+    poll_create_dto: &PollCreateDTO,
+) -> Result<PollDTO, ApiResponse> {
+    let poll_model = poll::ActiveModel {
+        id: Set(Uuid::new_v4()),
+        message_id: Set(message_id),
+        multiple_choice: Set(poll_create_dto.multiple_choice),
+        anonymous: Set(poll_create_dto.anonymous),
+        closes_at: Set(poll_create_dto
+            .closes_at
+            .as_deref()
+            .and_then(parse_timestamp)),
+    }
+    .insert(db)
+    .await
+    .map_err(|e| ApiResponse::new(500, e.to_string()))?;
+
+    let option_models = poll_create_dto
+        .options
+        .iter()
+        .enumerate()
+        .map(|(position, text)| poll_option::ActiveModel {
+            id: Set(Uuid::new_v4()),
+            poll_id: Set(poll_model.id),
+            text: Set(text.trim().to_string()),
+            position: Set(position as i32),
+        });
+
+    poll_option::Entity::insert_many(option_models)
+        .exec(db)
+        .await
+        .map_err(|e| ApiResponse::new(500, e.to_string()))?;
+
+    get_polls_by_message_ids(db, None, vec![message_id])
+        .await?
+        .remove(&message_id)
+        .ok_or(ApiResponse::new(500, "Poll not found".to_string()))
+}
+
+/// Builds the tallies for every poll on the given messages. With a `user_id`,
+/// each option also says whether that user voted for it.
+pub(crate) async fn get_polls_by_message_ids(
+    db: &DatabaseConnection,
+    user_id: Option<Uuid>,
+    message_ids: Vec<Uuid>,
+) -> Result<HashMap<Uuid, PollDTO>, ApiResponse> {
+    let poll_models = poll::Entity::find()
+        .filter(poll::Column::MessageId.is_in(message_ids))
+        .all(db)
+        .await
+        .map_err(|e| ApiResponse::new(500, e.to_string()))?;
+
+    if poll_models.is_empty() {
+        return Ok(HashMap::new());
+    }
+
+    let poll_ids: Vec<Uuid> = poll_models.iter().map(|poll| poll.id).collect();
+
+    let option_models = poll_option::Entity::find()
+        .filter(poll_option::Column::PollId.is_in(poll_ids.clone()))
+        .order_by_asc(poll_option::Column::Position)
+        .all(db)
+        .await
+        .map_err(|e| ApiResponse::new(500, e.to_string()))?;
+
+    let vote_models = poll_vote::Entity::find()
+        .filter(poll_vote::Column::PollId.is_in(poll_ids))
+        .order_by_asc(poll_vote::Column::DateCreated)
+        .all(db)
+        .await
+        .map_err(|e| ApiResponse::new(500, e.to_string()))?;
+
+    let mut voters_by_option_id: HashMap<Uuid, Vec<Uuid>> = HashMap::new();
+    let mut voters_by_poll_id: HashMap<Uuid, HashSet<Uuid>> = HashMap::new();
+
+    for vote_model in vote_models {
+        voters_by_option_id
+            .entry(vote_model.poll_option_id)
+            .or_default()
+            .push(vote_model.user_id);
+
+        voters_by_poll_id
+            .entry(vote_model.poll_id)
+            .or_default()
+            .insert(vote_model.user_id);
+    }
+
+    let mut options_by_poll_id: HashMap<Uuid, Vec<poll_option::Model>> = HashMap::new();
+
+    for option_model in option_models {
+        options_by_poll_id
+            .entry(option_model.poll_id)
+            .or_default()
+            .push(option_model);
+    }
+
+    let now = Utc::now().naive_utc();
+
+    Ok(poll_models
+        .into_iter()
+        .map(|poll_model| {
+            let options = options_by_poll_id
+                .remove(&poll_model.id)
+                .unwrap_or_default()
+                .into_iter()
+                .map(|option_model| {
+                    let voters = voters_by_option_id
+                        .remove(&option_model.id)
+                        .unwrap_or_default();
+
+                    PollOptionDTO {
+                        id: option_model.id,
+                        text: option_model.text,
+                        position: option_model.position,
+                        vote_count: voters.len(),
+                        voted: user_id.map(|user_id| voters.contains(&user_id)),
+                        voters: (!poll_model.anonymous).then_some(voters),
+                    }
+                })
+                .collect();
+
+            let poll_dto = PollDTO {
+                id: poll_model.id,
+                message_id: poll_model.message_id,
+                multiple_choice: poll_model.multiple_choice,
+                anonymous: poll_model.anonymous,
+                closes_at: poll_model.closes_at.map(|closes_at| closes_at.to_string()),
+                closed: poll_model
+                    .closes_at
+                    .is_some_and(|closes_at| closes_at <= now),
+                total_voters: voters_by_poll_id
+                    .get(&poll_model.id)
+                    .map(|voters| voters.len())
+                    .unwrap_or_default(),
+                options,
+            };
+
+            (poll_model.message_id, poll_dto)
+        })
+        .collect())
+}
+
+#[get("/")]
+async fn get_poll(
+    req: HttpRequest,
+    app_state: web::Data<app_state::AppState>,
+    query: web::Query<HashMap<String, String>>,
+) -> Result<ApiResponse, ApiResponse> {
+    let user_id = get_user_id_from_http_request(req.clone())?;
+
+    let message_id = query
+        .get("message_id")
+        .ok_or(ApiResponse::new(400, "Message ID is required".to_string()))?
+        .parse::<Uuid>()
+        .map_err(|e| ApiResponse::new(400, e.to_string()))?;
+
+    let message_model = message::Entity::find()
+        .filter(message::Column::Id.eq(message_id))
+        .filter(message::Column::Deleted.eq(false))
+        .one(&app_state.db)
+        .await
+        .map_err(|e| ApiResponse::new(500, e.to_string()))?
+        .ok_or(ApiResponse::new(404, "Message not found".to_string()))?;
+
+    check_user_can_read_message(&app_state, req, user_id, &message_model).await?;
+
+    let response_dto = get_polls_by_message_ids(&app_state.db, Some(user_id), vec![message_id])
+        .await?
+        .remove(&message_id)
+        .ok_or(ApiResponse::new(404, "Poll not found.".to_string()))?;
+
+    Ok(ApiResponse::new(
+        200,
+        serde_json::to_string(&response_dto).unwrap(),
+    ))
+}
+
+/// Votes for an option. On a single choice poll this replaces the user's
+/// previous vote.
+#[post("/vote")]
+async fn vote(
+    req: HttpRequest,
+    app_state: web::Data<app_state::AppState>,
+    poll_vote_dto: web::Json<PollVoteDTO>,
+    chat_room: web::Data<Arc<ChatRoom>>,
+) -> Result<ApiResponse, ApiResponse> {
+    let user_id = get_user_id_from_http_request(req.clone())?;
+
+    let (poll_model, message_model) =
+        get_votable_poll(&app_state, req.clone(), user_id, &poll_vote_dto).await?;
+
+    // Lock the poll row so concurrent votes by the same user are serialized
+    // and a single choice poll can't end up with two votes
+    let txn = app_state
+        .db
+        .begin()
+        .await
+        .map_err(|e| ApiResponse::new(500, e.to_string()))?;
+
+    poll::Entity::find_by_id(poll_model.id)
+        .lock_exclusive()
+        .one(&txn)
+        .await
+        .map_err(|e| ApiResponse::new(500, e.to_string()))?
+        .ok_or(ApiResponse::new(404, "Poll not found.".to_string()))?;
+
+    let existing_votes = poll_vote::Entity::find()
+        .filter(poll_vote::Column::PollId.eq(poll_model.id))
+        .filter(poll_vote::Column::UserId.eq(user_id))
+        .all(&txn)
+        .await
+        .map_err(|e| ApiResponse::new(500, e.to_string()))?;
+
+    let already_voted = existing_votes
+        .iter()
+        .any(|vote_model| vote_model.poll_option_id == poll_vote_dto.option_id);
+
+    if !already_voted {
+        if !poll_model.multiple_choice {
+            poll_vote::Entity::delete_many()
+                .filter(poll_vote::Column::PollId.eq(poll_model.id))
+                .filter(poll_vote::Column::UserId.eq(user_id))
+                .exec(&txn)
+                .await
+                .map_err(|e| ApiResponse::new(500, e.to_string()))?;
+        }
+
+        poll_vote::ActiveModel {
+            id: Set(Uuid::new_v4()),
+            poll_id: Set(poll_model.id),
+            poll_option_id: Set(poll_vote_dto.option_id),
+            user_id: Set(user_id),
+            date_created: Set(Utc::now().naive_utc()),
+        }
+        .insert(&txn)
+        .await
+        .map_err(|e| ApiResponse::new(500, e.to_string()))?;
+    }
+
+    txn.commit()
+        .await
+        .map_err(|e| ApiResponse::new(500, e.to_string()))?;
+
+    if !already_voted {
+        log_info(
+            req,
+            format!(
+                "Voted for option {} on poll {}",
+                poll_vote_dto.option_id, poll_model.id
+            ),
+        );
+    }
+
+    let response_dto = send_poll_event(&app_state, &chat_room, &message_model, user_id).await?;
+
+    Ok(ApiResponse::new(
+        200,
+        serde_json::to_string(&response_dto).unwrap(),
+    ))
+}
+
+#[delete("/vote")]
+async fn unvote(
+    req: HttpRequest,
+    app_state: web::Data<app_state::AppState>,
+    poll_vote_dto: web::Json<PollVoteDTO>,
+    chat_room: web::Data<Arc<ChatRoom>>,
+) -> Result<ApiResponse, ApiResponse> {
+    let user_id = get_user_id_from_http_request(req.clone())?;
+
+    let (poll_model, message_model) =
+        get_votable_poll(&app_state, req.clone(), user_id, &poll_vote_dto).await?;
+
+    let existing_vote = poll_vote::Entity::find()
+        .filter(poll_vote::Column::PollOptionId.eq(poll_vote_dto.option_id))
+        .filter(poll_vote::Column::UserId.eq(user_id))
+        .one(&app_state.db)
+        .await
+        .map_err(|e| ApiResponse::new(500, e.to_string()))?
+        .ok_or(ApiResponse::new(404, "Vote not found.".to_string()))?;
+
+    existing_vote
+        .delete(&app_state.db)
+        .await
+        .map_err(|e| ApiResponse::new(500, e.to_string()))?;
+
+    log_info(
+        req,
+        format!(
+            "Removed vote for option {} on poll {}",
+            poll_vote_dto.option_id, poll_model.id
+        ),
+    );
+
+    let response_dto = send_poll_event(&app_state, &chat_room, &message_model, user_id).await?;
+
+    Ok(ApiResponse::new(
+        200,
+        serde_json::to_string(&response_dto).unwrap(),
+    ))
+}
+
+async fn get_votable_poll(
+    app_state: &web::Data<app_state::AppState>,
+    req: HttpRequest,
+    user_id: Uuid,
+    poll_vote_dto: &PollVoteDTO,
+) -> Result<(poll::Model, message::Model), ApiResponse> {
+    let (poll_model, message_model) = poll::Entity::find_by_id(poll_vote_dto.poll_id)
+        .find_also_related(message::Entity)
+        .one(&app_state.db)
+        .await
+        .map_err(|e| ApiResponse::new(500, e.to_string()))?
+        .ok_or(ApiResponse::new(404, "Poll not found.".to_string()))?;
+
+    let message_model = message_model
+        .filter(|message_model| !message_model.deleted)
+        .ok_or(ApiResponse::new(404, "Poll not found.".to_string()))?;
+
+    check_user_can_read_message(app_state, req, user_id, &message_model).await?;
+
+    if poll_model
+        .closes_at
+        .is_some_and(|closes_at| closes_at <= Utc::now().naive_utc())
+    {
+        return Err(ApiResponse::new(400, "Poll is closed.".to_string()));
+    }
+
+    poll_option::Entity::find()
+        .filter(poll_option::Column::Id.eq(poll_vote_dto.option_id))
+        .filter(poll_option::Column::PollId.eq(poll_model.id))
+        .one(&app_state.db)
+        .await
+        .map_err(|e| ApiResponse::new(500, e.to_string()))?
+        .ok_or(ApiResponse::new(404, "Poll option not found.".to_string()))?;
+
+    Ok((poll_model, message_model))
+}
+
+/// Pushes the new tallies to everyone who can see the poll and returns them
+/// from the voter's point of view.
+async fn send_poll_event(
+    app_state: &web::Data<app_state::AppState>,
+    chat_room: &web::Data<Arc<ChatRoom>>,
+    message_model: &message::Model,
+    user_id: Uuid,
+) -> Result<PollDTO, ApiResponse> {
+    let poll_dto = get_polls_by_message_ids(&app_state.db, None, vec![message_model.id])
+        .await?
+        .remove(&message_model.id)
+        .ok_or(ApiResponse::new(404, "Poll not found.".to_string()))?;
+
+    let event_dto = PollEventDTO {
+        message_type: "POLL_UPDATED".to_string(),
+        recipient_type: message_model.recipient_type.clone(),
+        reference_id: message_model.reference_id,
+        poll: poll_dto,
+    };
+
+    let user_ids = match message_model.recipient_type.as_str() {
+        "CHANNEL" => {
+            get_array_of_readers_by_channel_id(app_state, message_model.reference_id).await?
+        }
+        "CONVERSATION" => {
+            get_conversation_participant_ids(&app_state.db, message_model.reference_id).await?
+        }
+        _ => vec![message_model.user_id, message_model.reference_id],
+    };
+
+    chat_room.send_message(&user_ids, &serde_json::to_string(&event_dto).unwrap());
+
+    get_polls_by_message_ids(&app_state.db, Some(user_id), vec![message_model.id])
+        .await?
+        .remove(&message_model.id)
+        .ok_or(ApiResponse::new(404, "Poll not found.".to_string()))
+}
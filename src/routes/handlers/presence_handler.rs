@@ -1,3 +1,5 @@
+use crate::routes::handlers::conversation_handler::get_conversation_participant_ids;
+use crate::routes::handlers::user_channel_view_handler::check_user_can_view_conversation;
 use crate::utils::api_response::ApiResponse;
 use crate::utils::app_state;
 use crate::utils::chat::ChatRoom;
@@ -12,47 +14,60 @@ use std::sync::Arc;
 use uuid::Uuid;
 
 #[derive(Serialize, Deserialize)]
-struct ChannelIndicateDTO {
-    reference_id: Uuid,
-    recipient_type: String,
+pub(crate) struct ChannelIndicateDTO {
+    pub(crate) reference_id: Uuid,
+    pub(crate) recipient_type: String,
 }
 
 #[derive(Serialize, Deserialize)]
-struct ChannelIndicateResponseDTO {
+pub(crate) struct ChannelIndicateResponseDTO {
     message_type: String,
     user_id: Uuid,
     reference_id: Uuid,
     recipient_type: String,
 }
 
+#[derive(Serialize, Deserialize)]
+struct SessionDTO {
+    session_id: Uuid,
+    device: Option<String>,
+}
+
+#[get("/sessions")]
+pub async fn get_my_sessions(
+    req: HttpRequest,
+    chat_room: web::Data<Arc<ChatRoom>>,
+) -> actix_web::Result<ApiResponse, ApiResponse> {
+    let user_id = get_user_id_from_http_request(req)?;
+
+    let sessions: Vec<SessionDTO> = chat_room
+        .get_user_sessions(user_id)
+        .into_iter()
+        .map(|(session_id, device)| SessionDTO { session_id, device })
+        .collect();
+
+    Ok(ApiResponse::new(
+        200,
+        serde_json::to_string(&sessions).unwrap(),
+    ))
+}
+
 #[get("/")]
 pub async fn get_all(
     req: HttpRequest,
     app_state: web::Data<app_state::AppState>,
     chat_room: web::Data<Arc<ChatRoom>>,
 ) -> actix_web::Result<ApiResponse, ApiResponse> {
-    let user_ids = chat_room.get_connected_user_ids();
-
     let current_user_id = get_user_id_from_http_request(req)?;
 
     let user_organization_id =
         get_organization_id_from_user_id(&app_state.db, current_user_id).await?;
 
-    //for each user id, exclude them from the list if they do not have the same organization id
-
-    let mut filtered_user_ids: Vec<Uuid> = Vec::new();
-
-    for user_id in user_ids {
-        let organization_id = get_organization_id_from_user_id(&app_state.db, user_id).await?;
-
-        if organization_id == user_organization_id {
-            filtered_user_ids.push(user_id);
-        }
-    }
+    let user_ids = chat_room.get_connected_user_ids(user_organization_id);
 
     Ok(ApiResponse::new(
         200,
-        serde_json::to_string(&filtered_user_ids).unwrap(),
+        serde_json::to_string(&user_ids).unwrap(),
     ))
 }
 
@@ -65,13 +80,32 @@ pub async fn send_typing_indicator_to_channel_id(
 ) -> Result<ApiResponse, ApiResponse> {
     let user_id = get_user_id_from_http_request(req)?;
 
+    let indicate_dto = send_typing_indicator(&app_state, &chat_room, user_id, &dto).await?;
+
+    Ok(ApiResponse::new(
+        200,
+        serde_json::to_string(&indicate_dto).unwrap(),
+    ))
+}
+
+/// Sends a TYPING event to everyone in the conversation, shared by the REST
+/// endpoint and the WebSocket.
+pub(crate) async fn send_typing_indicator(
+    app_state: &web::Data<app_state::AppState>,
+    chat_room: &web::Data<Arc<ChatRoom>>,
+    user_id: Uuid,
+    dto: &ChannelIndicateDTO,
+) -> Result<ChannelIndicateResponseDTO, ApiResponse> {
     let reference_id = dto.reference_id;
     let recipient_type = dto.recipient_type.clone();
 
-    if recipient_type != "CHANNEL" && recipient_type != "USER" {
+    if recipient_type != "CHANNEL" && recipient_type != "USER" && recipient_type != "CONVERSATION" {
         return Err(ApiResponse::new(400, "Invalid recipient type".to_string()));
     }
 
+    check_user_can_view_conversation(app_state, None, user_id, &recipient_type, reference_id)
+        .await?;
+
     let mut user_ids: HashSet<Uuid> = HashSet::new();
 
     if recipient_type == "CHANNEL" {
@@ -80,7 +114,7 @@ pub async fn send_typing_indicator_to_channel_id(
             .filter(entity::channel_role_access::Column::Deleted.eq(false))
             .all(&app_state.db)
             .await
-            .unwrap();
+            .map_err(|e| ApiResponse::new(500, e.to_string()))?;
 
         for channel_role_access in channel_role_accesses {
             let user_role_accesses = entity::user_role_access::Entity::find()
@@ -88,22 +122,25 @@ pub async fn send_typing_indicator_to_channel_id(
                 .filter(entity::user_role_access::Column::Deleted.eq(false))
                 .all(&app_state.db)
                 .await
-                .unwrap();
+                .map_err(|e| ApiResponse::new(500, e.to_string()))?;
 
             for user_role_access in user_role_accesses {
                 user_ids.insert(user_role_access.user_id);
             }
         }
 
+        let organization_id = get_organization_id_from_user_id(&app_state.db, user_id).await?;
+
         let admin_and_manage_channel_roles = entity::role::Entity::find()
             .filter(
                 entity::role::Column::Administrator
                     .eq(true)
                     .or(entity::role::Column::ManageChannels.eq(true)),
             )
+            .filter(entity::role::Column::OrganizationId.eq(organization_id))
             .all(&app_state.db)
             .await
-            .unwrap();
+            .map_err(|e| ApiResponse::new(500, e.to_string()))?;
 
         let admin_and_manage_channel_role_ids: Vec<Uuid> = admin_and_manage_channel_roles
             .into_iter()
@@ -117,7 +154,7 @@ pub async fn send_typing_indicator_to_channel_id(
             .filter(entity::user_role_access::Column::Deleted.eq(false))
             .all(&app_state.db)
             .await
-            .unwrap();
+            .map_err(|e| ApiResponse::new(500, e.to_string()))?;
 
         for user_role_access in admin_and_manage_channel_user_role_accesses {
             user_ids.insert(user_role_access.user_id);
@@ -128,6 +165,10 @@ pub async fn send_typing_indicator_to_channel_id(
         user_ids.insert(reference_id);
     }
 
+    if recipient_type == "CONVERSATION" {
+        user_ids.extend(get_conversation_participant_ids(&app_state.db, reference_id).await?);
+    }
+
     let indicate_dto = ChannelIndicateResponseDTO {
         message_type: "TYPING".to_string(),
         user_id,
@@ -135,13 +176,19 @@ pub async fn send_typing_indicator_to_channel_id(
         recipient_type,
     };
 
-    chat_room.send_message(
+    // DM recipients see the conversation keyed by the sender
+    let subscription_reference_id = if indicate_dto.recipient_type == "USER" {
+        user_id
+    } else {
+        reference_id
+    };
+
+    chat_room.send_subscribed_message(
         &user_ids.into_iter().collect::<Vec<Uuid>>(),
+        &indicate_dto.recipient_type,
+        subscription_reference_id,
         &serde_json::to_string(&indicate_dto).unwrap(),
     );
 
-    Ok(ApiResponse::new(
-        200,
-        serde_json::to_string(&indicate_dto).unwrap(),
-    ))
+    Ok(indicate_dto)
 }
@@ -0,0 +1,312 @@
+use crate::routes::handlers::conversation_handler::{
+    check_conversation_participant, get_conversation_participant_ids,
+};
+use crate::routes::handlers::message_handler::{
+    get_array_of_readers_by_channel_id, get_user_has_access_to_channel,
+};
+use crate::utils::api_response::ApiResponse;
+use crate::utils::app_state;
+use crate::utils::chat::ChatRoom;
+use crate::utils::jwt::get_user_id_from_http_request;
+use crate::utils::logging::log_info;
+use actix_web::{delete, post, web, HttpRequest, Result};
+use chrono::Utc;
+use entity::{message, message_reaction};
+use sea_orm::sea_query::OnConflict;
+use sea_orm::ActiveValue::Set;
+use sea_orm::{
+    ColumnTrait, DatabaseConnection, EntityTrait, ModelTrait, PaginatorTrait, QueryFilter,
+    QueryOrder, QuerySelect,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use uuid::Uuid;
+
+const MAX_EMOJI_LENGTH: usize = 64;
+
+#[derive(Serialize, Deserialize)]
+struct ReactionDTO {
+    message_id: Uuid,
+    emoji: String,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ReactionCountDTO {
+    pub emoji: String,
+    pub count: i64,
+    pub reacted: bool,
+}
+
+#[derive(Serialize, Deserialize)]
+struct ReactionEventDTO {
+    message_type: String,
+    message_id: Uuid,
+    user_id: Uuid,
+    emoji: String,
+    count: u64,
+    recipient_type: String,
+    reference_id: Uuid,
+}
+
+#[post("/")]
+pub async fn add_reaction(
+    req: HttpRequest,
+    app_state: web::Data<app_state::AppState>,
+    reaction_dto: web::Json<ReactionDTO>,
+    chat_room: web::Data<Arc<ChatRoom>>,
+) -> Result<ApiResponse, ApiResponse> {
+    let user_id = get_user_id_from_http_request(req.clone())?;
+
+    let emoji = reaction_dto.emoji.trim().to_string();
+
+    if emoji.is_empty() || emoji.chars().count() > MAX_EMOJI_LENGTH {
+        return Err(ApiResponse::new(
+            400,
+            format!(
+                "Emoji must be between 1 and {} characters",
+                MAX_EMOJI_LENGTH
+            ),
+        ));
+    }
+
+    let message_model = get_reactable_message(&app_state, user_id, reaction_dto.message_id).await?;
+
+    // Reacting twice with the same emoji keeps the first reaction, the unique
+    // index settles concurrent requests
+    let inserted_rows = message_reaction::Entity::insert(message_reaction::ActiveModel {
+        id: Set(Uuid::new_v4()),
+        message_id: Set(message_model.id),
+        user_id: Set(user_id),
+        emoji: Set(emoji.clone()),
+        date_created: Set(Utc::now().naive_utc()),
+    })
+    .on_conflict(
+        OnConflict::columns([
+            message_reaction::Column::MessageId,
+            message_reaction::Column::UserId,
+            message_reaction::Column::Emoji,
+        ])
+        .do_nothing()
+        .to_owned(),
+    )
+    .exec_without_returning(&app_state.db)
+    .await
+    .map_err(|e| ApiResponse::new(500, e.to_string()))?;
+
+    if inserted_rows > 0 {
+        log_info(
+            req,
+            format!("Added reaction {} to message {}", emoji, message_model.id),
+        );
+    }
+
+    let event_dto = send_reaction_event(
+        &app_state,
+        &chat_room,
+        &message_model,
+        user_id,
+        emoji,
+        "REACTION_ADDED",
+    )
+    .await?;
+
+    Ok(ApiResponse::new(
+        200,
+        serde_json::to_string(&event_dto).unwrap(),
+    ))
+}
+
+#[delete("/")]
+pub async fn remove_reaction(
+    req: HttpRequest,
+    app_state: web::Data<app_state::AppState>,
+    reaction_dto: web::Json<ReactionDTO>,
+    chat_room: web::Data<Arc<ChatRoom>>,
+) -> Result<ApiResponse, ApiResponse> {
+    let user_id = get_user_id_from_http_request(req.clone())?;
+
+    let emoji = reaction_dto.emoji.trim().to_string();
+
+    let message_model = get_reactable_message(&app_state, user_id, reaction_dto.message_id).await?;
+
+    let existing_reaction = message_reaction::Entity::find()
+        .filter(message_reaction::Column::MessageId.eq(message_model.id))
+        .filter(message_reaction::Column::UserId.eq(user_id))
+        .filter(message_reaction::Column::Emoji.eq(emoji.clone()))
+        .one(&app_state.db)
+        .await
+        .map_err(|e| ApiResponse::new(500, e.to_string()))?
+        .ok_or(ApiResponse::new(404, "Reaction not found".to_string()))?;
+
+    existing_reaction
+        .delete(&app_state.db)
+        .await
+        .map_err(|e| ApiResponse::new(500, e.to_string()))?;
+
+    log_info(
+        req,
+        format!(
+            "Removed reaction {} from message {}",
+            emoji, message_model.id
+        ),
+    );
+
+    let event_dto = send_reaction_event(
+        &app_state,
+        &chat_room,
+        &message_model,
+        user_id,
+        emoji,
+        "REACTION_REMOVED",
+    )
+    .await?;
+
+    Ok(ApiResponse::new(
+        200,
+        serde_json::to_string(&event_dto).unwrap(),
+    ))
+}
+
+pub async fn get_reaction_counts_by_message_ids(
+    db: &DatabaseConnection,
+    user_id: Uuid,
+    message_ids: Vec<Uuid>,
+) -> Result<HashMap<Uuid, Vec<ReactionCountDTO>>, ApiResponse> {
+    let counts: Vec<(Uuid, String, i64)> = message_reaction::Entity::find()
+        .select_only()
+        .column(message_reaction::Column::MessageId)
+        .column(message_reaction::Column::Emoji)
+        .column_as(message_reaction::Column::Id.count(), "count")
+        .filter(message_reaction::Column::MessageId.is_in(message_ids.clone()))
+        .group_by(message_reaction::Column::MessageId)
+        .group_by(message_reaction::Column::Emoji)
+        .order_by_asc(message_reaction::Column::Emoji)
+        .into_tuple()
+        .all(db)
+        .await
+        .map_err(|e| ApiResponse::new(500, e.to_string()))?;
+
+    let user_reactions: HashSet<(Uuid, String)> = message_reaction::Entity::find()
+        .filter(message_reaction::Column::MessageId.is_in(message_ids))
+        .filter(message_reaction::Column::UserId.eq(user_id))
+        .all(db)
+        .await
+        .map_err(|e| ApiResponse::new(500, e.to_string()))?
+        .into_iter()
+        .map(|reaction| (reaction.message_id, reaction.emoji))
+        .collect();
+
+    let mut reactions_by_message_id: HashMap<Uuid, Vec<ReactionCountDTO>> = HashMap::new();
+
+    for (message_id, emoji, count) in counts {
+        let reacted = user_reactions.contains(&(message_id, emoji.clone()));
+
+        reactions_by_message_id
+            .entry(message_id)
+            .or_default()
+            .push(ReactionCountDTO {
+                emoji,
+                count,
+                reacted,
+            });
+    }
+
+    Ok(reactions_by_message_id)
+}
+
+async fn get_reactable_message(
+    app_state: &web::Data<app_state::AppState>,
+    user_id: Uuid,
+    message_id: Uuid,
+) -> Result<message::Model, ApiResponse> {
+    let message_model = message::Entity::find()
+        .filter(message::Column::Id.eq(message_id))
+        .filter(message::Column::Deleted.eq(false))
+        .one(&app_state.db)
+        .await
+        .map_err(|e| ApiResponse::new(500, e.to_string()))?
+        .ok_or(ApiResponse::new(404, "Message not found".to_string()))?;
+
+    if message_model.message_type != "MESSAGE" {
+        return Err(ApiResponse::new(
+            400,
+            "Message type is not MESSAGE".to_string(),
+        ));
+    }
+
+    match message_model.recipient_type.as_str() {
+        "USER" => {
+            if message_model.user_id != user_id && message_model.reference_id != user_id {
+                return Err(ApiResponse::new(
+                    403,
+                    "You do not have permission to view this message".to_string(),
+                ));
+            }
+        }
+        "CHANNEL" => {
+            let has_user_access_to_channel = get_user_has_access_to_channel(
+                app_state.clone(),
+                user_id,
+                message_model.reference_id,
+            )
+            .await?;
+
+            if !has_user_access_to_channel {
+                return Err(ApiResponse::new(
+                    400,
+                    "User does not have access to channel".to_string(),
+                ));
+            }
+        }
+        "CONVERSATION" => {
+            check_conversation_participant(&app_state.db, user_id, message_model.reference_id)
+                .await?;
+        }
+        _ => {
+            return Err(ApiResponse::new(400, "Invalid recipient type".to_string()));
+        }
+    }
+
+    Ok(message_model)
+}
+
+async fn send_reaction_event(
+    app_state: &web::Data<app_state::AppState>,
+    chat_room: &web::Data<Arc<ChatRoom>>,
+    message_model: &message::Model,
+    user_id: Uuid,
+    emoji: String,
+    message_type: &str,
+) -> Result<ReactionEventDTO, ApiResponse> {
+    let count = message_reaction::Entity::find()
+        .filter(message_reaction::Column::MessageId.eq(message_model.id))
+        .filter(message_reaction::Column::Emoji.eq(emoji.clone()))
+        .count(&app_state.db)
+        .await
+        .map_err(|e| ApiResponse::new(500, e.to_string()))?;
+
+    let event_dto = ReactionEventDTO {
+        message_type: message_type.to_string(),
+        message_id: message_model.id,
+        user_id,
+        emoji,
+        count,
+        recipient_type: message_model.recipient_type.clone(),
+        reference_id: message_model.reference_id,
+    };
+
+    let user_ids = match message_model.recipient_type.as_str() {
+        "CHANNEL" => {
+            get_array_of_readers_by_channel_id(app_state, message_model.reference_id).await?
+        }
+        "CONVERSATION" => {
+            get_conversation_participant_ids(&app_state.db, message_model.reference_id).await?
+        }
+        _ => vec![message_model.user_id, message_model.reference_id],
+    };
+
+    chat_room.send_message(&user_ids, &serde_json::to_string(&event_dto).unwrap());
+
+    Ok(event_dto)
+}
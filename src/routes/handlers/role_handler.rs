@@ -1,9 +1,10 @@
 use crate::utils::api_response::ApiResponse;
 use crate::utils::app_state;
 use crate::utils::chat::ChatRoom;
+use crate::utils::event::ChatEvent;
 use crate::utils::jwt::get_user_id_from_http_request;
 use crate::utils::logging::log_info;
-use crate::utils::message::send_update_status_from_role_id_and_org_id;
+use crate::utils::message::send_event_from_role_id_and_org_id;
 use crate::utils::organization_util::get_organization_id_from_user_id;
 use crate::utils::permissions::{check_permission, Permission};
 use actix_web::{delete, get, patch, post, web, HttpRequest, Result};
@@ -23,6 +24,8 @@ struct RoleDTO {
     manage_users: bool,
     manage_channels: bool,
     manage_roles: bool,
+    pin_messages: Option<bool>,
+    mention_everyone: Option<bool>,
     deleted: Option<bool>,
 }
 
@@ -39,14 +42,11 @@ struct RoleResponseDTO {
     manage_users: bool,
     manage_channels: bool,
     manage_roles: bool,
+    pin_messages: bool,
+    mention_everyone: bool,
     deleted: bool,
 }
 
-#[derive(Serialize, Deserialize)]
-struct MessageDTO {
-    message_type: String,
-}
-
 impl From<role::Model> for RoleDTO {
     fn from(model: role::Model) -> Self {
         Self {
@@ -56,6 +56,8 @@ impl From<role::Model> for RoleDTO {
             manage_users: model.manage_users,
             manage_channels: model.manage_channels,
             manage_roles: model.manage_roles,
+            pin_messages: Some(model.pin_messages),
+            mention_everyone: Some(model.mention_everyone),
             deleted: Some(model.deleted),
         }
     }
@@ -95,6 +97,8 @@ async fn create_role(
         manage_users: Set(role_dto.manage_users),
         manage_channels: Set(role_dto.manage_channels),
         manage_roles: Set(role_dto.manage_roles),
+        pin_messages: Set(role_dto.pin_messages.unwrap_or(false)),
+        mention_everyone: Set(role_dto.mention_everyone.unwrap_or(false)),
         organization_id: Set(user_organization_id),
         ..Default::default()
     }
@@ -193,11 +197,8 @@ async fn update_role(
         .filter(role::Column::OrganizationId.eq(user_organization_id))
         .one(&app_state.db)
         .await
-        .map_err(|e| ApiResponse::new(500, e.to_string()))?;
-
-    if existing_role.is_none() {
-        return Err(ApiResponse::new(404, "Role not found.".to_string()));
-    }
+        .map_err(|e| ApiResponse::new(500, e.to_string()))?
+        .ok_or(ApiResponse::new(404, "Role not found.".to_string()))?;
 
     if role_dto.name.is_empty() {
         return Err(ApiResponse::new(
@@ -206,6 +207,12 @@ async fn update_role(
         ));
     }
 
+    // Permissions left out of the request keep their stored value
+    let pin_messages = role_dto.pin_messages.unwrap_or(existing_role.pin_messages);
+    let mention_everyone = role_dto
+        .mention_everyone
+        .unwrap_or(existing_role.mention_everyone);
+
     let role_model = role::ActiveModel {
         id: Set(role_dto.id.unwrap()),
         name: Set(role_dto.name.clone()),
@@ -213,11 +220,13 @@ async fn update_role(
         manage_users: Set(role_dto.manage_users),
         manage_channels: Set(role_dto.manage_channels),
         manage_roles: Set(role_dto.manage_roles),
+        pin_messages: Set(pin_messages),
+        mention_everyone: Set(mention_everyone),
         organization_id: Set(user_organization_id),
         ..Default::default()
     };
 
-    role_model
+    let role_model = role_model
         .update(&app_state.db)
         .await
         .map_err(|e| ApiResponse::new(500, e.to_string()))?;
@@ -231,10 +240,15 @@ async fn update_role(
         manage_users: role_dto.manage_users,
         manage_channels: role_dto.manage_channels,
         manage_roles: role_dto.manage_roles,
+        pin_messages,
+        mention_everyone,
         deleted: false,
     };
 
-    send_update_status_from_role_id_and_org_id(
+    send_event_from_role_id_and_org_id(
+        &ChatEvent::RoleUpdated {
+            role: role_model.into(),
+        },
         role_dto.id.unwrap(),
         user_organization_id,
         &app_state,
@@ -302,6 +316,8 @@ async fn delete_role(
         manage_users: false,
         manage_channels: false,
         manage_roles: false,
+        pin_messages: false,
+        mention_everyone: false,
         deleted: true,
     };
 
@@ -321,7 +337,10 @@ async fn delete_role(
             .map_err(|e| ApiResponse::new(500, e.to_string()))?;
     }
 
-    send_update_status_from_role_id_and_org_id(
+    send_event_from_role_id_and_org_id(
+        &ChatEvent::RoleDeleted {
+            role_id: role_dto.id,
+        },
         role_dto.id,
         user_organization_id,
         &app_state,
@@ -0,0 +1,410 @@
+use crate::routes::handlers::command_handler::parse_command;
+use crate::routes::handlers::message_handler::{
+    check_user_can_send_message, parse_timestamp, publish_message, unescape_command_content,
+    MessageSendDTO,
+};
+use crate::utils::api_response::ApiResponse;
+use crate::utils::app_state;
+use crate::utils::chat::ChatRoom;
+use crate::utils::jwt::get_user_id_from_http_request;
+use crate::utils::logging::log_info;
+use actix_web::{delete, get, patch, web, HttpRequest, Result};
+use chrono::{NaiveDateTime, Utc};
+use entity::scheduled_message;
+use sea_orm::sea_query::Expr;
+use sea_orm::ActiveValue::Set;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, EntityTrait, IntoActiveModel, QueryFilter, QueryOrder,
+};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use uuid::Uuid;
+
+#[derive(Serialize, Deserialize)]
+pub(crate) struct ScheduledMessageDTO {
+    pub(crate) id: Uuid,
+    user_id: Uuid,
+    content: Option<String>,
+    message_type: String,
+    recipient_type: String,
+    reference_id: Uuid,
+    parent_id: Option<Uuid>,
+    media_ids: Vec<Uuid>,
+    send_at: String,
+    status: String,
+    message_id: Option<Uuid>,
+    failure_reason: Option<String>,
+    date_created: String,
+    ttl_seconds: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    client_message_id: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct ScheduledMessageEditDTO {
+    id: Uuid,
+    content: Option<String>,
+    send_at: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct ScheduledMessageIdDTO {
+    id: Uuid,
+}
+
+impl From<scheduled_message::Model> for ScheduledMessageDTO {
+    fn from(model: scheduled_message::Model) -> Self {
+        Self {
+            id: model.id,
+            user_id: model.user_id,
+            content: model.content,
+            message_type: model.message_type,
+            recipient_type: model.recipient_type,
+            reference_id: model.reference_id,
+            parent_id: model.parent_id,
+            media_ids: serde_json::from_value(model.media_ids).unwrap_or_default(),
+            send_at: model.send_at.to_string(),
+            status: model.status,
+            message_id: model.message_id,
+            failure_reason: model.failure_reason,
+            date_created: model.date_created.to_string(),
+            ttl_seconds: model.ttl_seconds,
+            client_message_id: model.client_message_id,
+        }
+    }
+}
+
+/// Stores a message to be published at its `send_at` time. Permission checks
+/// have already been done by the caller.
+pub(crate) async fn schedule_message(
+    app_state: &web::Data<app_state::AppState>,
+    user_id: Uuid,
+    message_send_dto: &MessageSendDTO,
+) -> Result<ScheduledMessageDTO, ApiResponse> {
+    if let Some(client_message_id) = message_send_dto.client_message_id.as_deref() {
+        let existing_scheduled_message = scheduled_message::Entity::find()
+            .filter(scheduled_message::Column::UserId.eq(user_id))
+            .filter(scheduled_message::Column::ClientMessageId.eq(client_message_id))
+            .one(&app_state.db)
+            .await
+            .map_err(|e| ApiResponse::new(500, e.to_string()))?;
+
+        if let Some(existing_scheduled_message) = existing_scheduled_message {
+            return Ok(existing_scheduled_message.into());
+        }
+    }
+
+    let send_at = parse_send_at(message_send_dto.send_at.as_deref().unwrap_or_default())?;
+
+    let scheduled_message_model = scheduled_message::ActiveModel {
+        id: Set(Uuid::new_v4()),
+        user_id: Set(user_id),
+        content: Set(message_send_dto.content.clone()),
+        message_type: Set(message_send_dto.message_type.clone()),
+        recipient_type: Set(message_send_dto.recipient_type.clone()),
+        reference_id: Set(message_send_dto.reference_id),
+        parent_id: Set(message_send_dto.parent_id),
+        media_ids: Set(serde_json::to_value(&message_send_dto.media_ids).unwrap()),
+        send_at: Set(send_at),
+        status: Set("PENDING".to_string()),
+        message_id: Set(None),
+        failure_reason: Set(None),
+        claimed_at: Set(None),
+        date_created: Set(Utc::now().naive_utc()),
+        ttl_seconds: Set(message_send_dto.ttl_seconds),
+        client_message_id: Set(message_send_dto.client_message_id.clone()),
+    }
+    .insert(&app_state.db)
+    .await
+    .map_err(|e| ApiResponse::new(500, e.to_string()))?;
+
+    Ok(scheduled_message_model.into())
+}
+
+fn parse_send_at(value: &str) -> Result<NaiveDateTime, ApiResponse> {
+    let send_at = parse_timestamp(value).ok_or(ApiResponse::new(
+        400,
+        "send_at must be an RFC 3339 timestamp.".to_string(),
+    ))?;
+
+    if send_at <= Utc::now().naive_utc() {
+        return Err(ApiResponse::new(
+            400,
+            "send_at must be in the future.".to_string(),
+        ));
+    }
+
+    Ok(send_at)
+}
+
+async fn get_pending_scheduled_message(
+    app_state: &web::Data<app_state::AppState>,
+    user_id: Uuid,
+    scheduled_message_id: Uuid,
+) -> Result<scheduled_message::Model, ApiResponse> {
+    scheduled_message::Entity::find()
+        .filter(scheduled_message::Column::Id.eq(scheduled_message_id))
+        .filter(scheduled_message::Column::UserId.eq(user_id))
+        .filter(scheduled_message::Column::Status.eq("PENDING"))
+        .one(&app_state.db)
+        .await
+        .map_err(|e| ApiResponse::new(500, e.to_string()))?
+        .ok_or(ApiResponse::new(
+            404,
+            "Scheduled message not found.".to_string(),
+        ))
+}
+
+#[get("/")]
+async fn get_scheduled_messages(
+    req: HttpRequest,
+    app_state: web::Data<app_state::AppState>,
+) -> Result<ApiResponse, ApiResponse> {
+    let user_id = get_user_id_from_http_request(req.clone())?;
+
+    let scheduled_messages = scheduled_message::Entity::find()
+        .filter(scheduled_message::Column::UserId.eq(user_id))
+        .filter(scheduled_message::Column::Status.eq("PENDING"))
+        .order_by_asc(scheduled_message::Column::SendAt)
+        .all(&app_state.db)
+        .await
+        .map_err(|e| ApiResponse::new(500, e.to_string()))?;
+
+    let response_dtos: Vec<ScheduledMessageDTO> = scheduled_messages
+        .into_iter()
+        .map(ScheduledMessageDTO::from)
+        .collect();
+
+    Ok(ApiResponse::new(
+        200,
+        serde_json::to_string(&response_dtos).unwrap(),
+    ))
+}
+
+#[patch("/")]
+async fn edit_scheduled_message(
+    req: HttpRequest,
+    app_state: web::Data<app_state::AppState>,
+    scheduled_message_edit_dto: web::Json<ScheduledMessageEditDTO>,
+) -> Result<ApiResponse, ApiResponse> {
+    let user_id = get_user_id_from_http_request(req.clone())?;
+
+    let scheduled_message_model =
+        get_pending_scheduled_message(&app_state, user_id, scheduled_message_edit_dto.id).await?;
+
+    let message_type = scheduled_message_model.message_type.clone();
+
+    let mut scheduled_message_model = scheduled_message_model.into_active_model();
+
+    // Edited content goes through the same rules as a new message
+    if let Some(content) = scheduled_message_edit_dto.content.as_deref() {
+        if content.is_empty() {
+            return Err(ApiResponse::new(
+                400,
+                "Content must be at least 1 character".to_string(),
+            ));
+        }
+
+        if message_type == "MESSAGE" && parse_command(content).is_some() {
+            return Err(ApiResponse::new(
+                400,
+                "Commands can't be scheduled.".to_string(),
+            ));
+        }
+
+        let content = unescape_command_content(content).unwrap_or(content.to_string());
+
+        scheduled_message_model.content = Set(Some(content));
+    }
+
+    if let Some(send_at) = scheduled_message_edit_dto.send_at.as_deref() {
+        scheduled_message_model.send_at = Set(parse_send_at(send_at)?);
+    }
+
+    let scheduled_message_model = scheduled_message_model
+        .update(&app_state.db)
+        .await
+        .map_err(|e| ApiResponse::new(500, e.to_string()))?;
+
+    log_info(
+        req,
+        format!("Edited scheduled message {}", scheduled_message_model.id),
+    );
+
+    let response_dto: ScheduledMessageDTO = scheduled_message_model.into();
+
+    Ok(ApiResponse::new(
+        200,
+        serde_json::to_string(&response_dto).unwrap(),
+    ))
+}
+
+#[delete("/")]
+async fn cancel_scheduled_message(
+    req: HttpRequest,
+    app_state: web::Data<app_state::AppState>,
+    scheduled_message_id_dto: web::Json<ScheduledMessageIdDTO>,
+) -> Result<ApiResponse, ApiResponse> {
+    let user_id = get_user_id_from_http_request(req.clone())?;
+
+    let scheduled_message_model =
+        get_pending_scheduled_message(&app_state, user_id, scheduled_message_id_dto.id).await?;
+
+    let mut scheduled_message_model = scheduled_message_model.into_active_model();
+    scheduled_message_model.status = Set("CANCELLED".to_string());
+
+    let scheduled_message_model = scheduled_message_model
+        .update(&app_state.db)
+        .await
+        .map_err(|e| ApiResponse::new(500, e.to_string()))?;
+
+    log_info(
+        req,
+        format!("Cancelled scheduled message {}", scheduled_message_model.id),
+    );
+
+    let response_dto: ScheduledMessageDTO = scheduled_message_model.into();
+
+    Ok(ApiResponse::new(
+        200,
+        serde_json::to_string(&response_dto).unwrap(),
+    ))
+}
+
+/// Claims older than this were left by a publish that never finished.
+const SENDING_TIMEOUT_MINUTES: i64 = 5;
+
+/// Publishes every pending message whose `send_at` has passed. Each row is
+/// claimed by moving it to SENDING first, so a message is never sent twice.
+/// A message that fails is marked FAILED without holding up the rest.
+pub async fn publish_due_scheduled_messages(
+    app_state: &web::Data<app_state::AppState>,
+    chat_room: &web::Data<Arc<ChatRoom>>,
+) -> Result<(), ApiResponse> {
+    fail_abandoned_scheduled_messages(app_state).await?;
+
+    let due_scheduled_messages = scheduled_message::Entity::find()
+        .filter(scheduled_message::Column::Status.eq("PENDING"))
+        .filter(scheduled_message::Column::SendAt.lte(Utc::now().naive_utc()))
+        .order_by_asc(scheduled_message::Column::SendAt)
+        .all(&app_state.db)
+        .await
+        .map_err(|e| ApiResponse::new(500, e.to_string()))?;
+
+    for scheduled_message_model in due_scheduled_messages {
+        let scheduled_message_id = scheduled_message_model.id;
+
+        if let Err(e) =
+            publish_scheduled_message(app_state, chat_room, scheduled_message_model).await
+        {
+            log::error!(
+                "Failed to publish scheduled message {}: {}",
+                scheduled_message_id,
+                e.body
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// A process that stops mid-publish leaves its claim in SENDING. The message
+/// may already have gone out, so it is failed rather than sent again.
+async fn fail_abandoned_scheduled_messages(
+    app_state: &web::Data<app_state::AppState>,
+) -> Result<(), ApiResponse> {
+    let claimed_before =
+        Utc::now().naive_utc() - chrono::Duration::minutes(SENDING_TIMEOUT_MINUTES);
+
+    let abandoned = scheduled_message::Entity::update_many()
+        .col_expr(scheduled_message::Column::Status, Expr::value("FAILED"))
+        .col_expr(
+            scheduled_message::Column::FailureReason,
+            Expr::value("Publishing was interrupted before it could be confirmed."),
+        )
+        .filter(scheduled_message::Column::Status.eq("SENDING"))
+        .filter(scheduled_message::Column::ClaimedAt.lt(claimed_before))
+        .exec(&app_state.db)
+        .await
+        .map_err(|e| ApiResponse::new(500, e.to_string()))?;
+
+    if abandoned.rows_affected > 0 {
+        log::error!(
+            "Failed {} scheduled messages left in SENDING",
+            abandoned.rows_affected
+        );
+    }
+
+    Ok(())
+}
+
+async fn publish_scheduled_message(
+    app_state: &web::Data<app_state::AppState>,
+    chat_room: &web::Data<Arc<ChatRoom>>,
+    scheduled_message_model: scheduled_message::Model,
+) -> Result<(), ApiResponse> {
+    let claimed = scheduled_message::Entity::update_many()
+        .col_expr(scheduled_message::Column::Status, Expr::value("SENDING"))
+        .col_expr(
+            scheduled_message::Column::ClaimedAt,
+            Expr::value(Utc::now().naive_utc()),
+        )
+        .filter(scheduled_message::Column::Id.eq(scheduled_message_model.id))
+        .filter(scheduled_message::Column::Status.eq("PENDING"))
+        .exec(&app_state.db)
+        .await
+        .map_err(|e| ApiResponse::new(500, e.to_string()))?;
+
+    if claimed.rows_affected != 1 {
+        return Ok(());
+    }
+
+    let scheduled_message_id = scheduled_message_model.id;
+    let user_id = scheduled_message_model.user_id;
+
+    let message_send_dto = MessageSendDTO {
+        content: scheduled_message_model.content.clone(),
+        message_type: scheduled_message_model.message_type.clone(),
+        recipient_type: scheduled_message_model.recipient_type.clone(),
+        reference_id: scheduled_message_model.reference_id,
+        media_ids: serde_json::from_value(scheduled_message_model.media_ids.clone())
+            .unwrap_or_default(),
+        parent_id: scheduled_message_model.parent_id,
+        send_at: None,
+        ttl_seconds: scheduled_message_model.ttl_seconds,
+        client_message_id: scheduled_message_model.client_message_id.clone(),
+        poll: None,
+    };
+
+    let result =
+        match check_user_can_send_message(app_state, None, user_id, &message_send_dto).await {
+            Ok(()) => publish_message(app_state, chat_room, user_id, &message_send_dto).await,
+            Err(e) => Err(e),
+        };
+
+    let mut scheduled_message_model = scheduled_message_model.into_active_model();
+
+    match result {
+        Ok(message_dto) => {
+            scheduled_message_model.status = Set("SENT".to_string());
+            scheduled_message_model.message_id = Set(Some(message_dto.id));
+        }
+        Err(e) => {
+            log::error!(
+                "Failed to publish scheduled message {}: {}",
+                scheduled_message_id,
+                e.body
+            );
+
+            scheduled_message_model.status = Set("FAILED".to_string());
+            scheduled_message_model.failure_reason = Set(Some(e.body));
+        }
+    }
+
+    scheduled_message_model
+        .update(&app_state.db)
+        .await
+        .map_err(|e| ApiResponse::new(500, e.to_string()))?;
+
+    Ok(())
+}
@@ -1,11 +1,15 @@
+use crate::routes::handlers::message_handler::check_user_can_read_message;
+use crate::routes::handlers::user_channel_view_handler::advance_read_cursor;
 use crate::utils::api_response::ApiResponse;
 use crate::utils::app_state;
+use crate::utils::chat::ChatRoom;
 use crate::utils::jwt::get_user_id_from_http_request;
 use actix_web::{get, post, web, HttpRequest};
-use entity::{message, seen_message};
-use sea_orm::{ActiveModelTrait, ActiveValue::Set, ColumnTrait, EntityTrait, QueryFilter};
+use entity::{message, user_channel_view};
+use sea_orm::{ColumnTrait, EntityTrait, QueryFilter, QueryOrder};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::sync::Arc;
 use uuid::Uuid;
 
 #[derive(Serialize, Deserialize)]
@@ -13,80 +17,52 @@ struct MessageIdsDTO {
     ids: Vec<Uuid>,
 }
 
-#[derive(Serialize, Deserialize)]
-struct MessageDTO {
-    id: Uuid,
-    user_id: Uuid,
-    content: Option<String>,
-    date_created: String,
-    date_updated: String,
-    message_type: String,
-    recipient_type: String,
-    reference_id: Uuid,
-    deleted: bool,
-}
-
-impl From<message::Model> for MessageDTO {
-    fn from(model: message::Model) -> Self {
-        Self {
-            id: model.id,
-            user_id: model.user_id,
-            content: model.content,
-            date_created: model.date_created.to_string(),
-            date_updated: model.date_updated.to_string(),
-            message_type: model.message_type.to_string(),
-            recipient_type: model.recipient_type.to_string(),
-            reference_id: model.reference_id,
-            deleted: model.deleted,
-        }
-    }
-}
-
+/// Moves the user's read cursor in each conversation up to the newest of the
+/// given messages.
 #[post("/")]
 async fn set_seen_message(
     req: HttpRequest,
     app_state: web::Data<app_state::AppState>,
     message_ids_dto: web::Json<MessageIdsDTO>,
+    chat_room: web::Data<Arc<ChatRoom>>,
 ) -> Result<ApiResponse, ApiResponse> {
-    let user_id = get_user_id_from_http_request(req)?;
-
-    let message_ids = message_ids_dto.ids.clone();
-
-    for message_id in message_ids {
-        let message = message::Entity::find()
-            .filter(message::Column::Id.eq(message_id))
-            .filter(message::Column::Deleted.eq(false))
-            .one(&app_state.db)
-            .await
-            .map_err(|e| ApiResponse::new(500, e.to_string()))?;
-
-        if message.is_none() {
-            continue;
-        }
-
-        let seen_message_entity = seen_message::Entity::find()
-            .filter(seen_message::Column::UserId.eq(user_id))
-            .filter(seen_message::Column::MessageId.eq(message_id))
-            .one(&app_state.db)
-            .await
-            .map_err(|e| ApiResponse::new(500, e.to_string()))?;
-
-        if seen_message_entity.is_some() {
-            continue;
-        }
-
-        let new_seen_message = seen_message::ActiveModel {
-            id: Set(Uuid::new_v4()),
-            user_id: Set(user_id),
-            message_id: Set(message_id),
-            date_seen: Set(chrono::Utc::now().naive_utc()),
-            ..Default::default()
+    let user_id = get_user_id_from_http_request(req.clone())?;
+
+    let messages = message::Entity::find()
+        .filter(message::Column::Id.is_in(message_ids_dto.ids.clone()))
+        .filter(message::Column::Deleted.eq(false))
+        .order_by_desc(message::Column::DateCreated)
+        .all(&app_state.db)
+        .await
+        .map_err(|e| ApiResponse::new(500, e.to_string()))?;
+
+    let mut newest_messages: HashMap<(String, Uuid), message::Model> = HashMap::new();
+
+    for message_model in messages {
+        // DM cursors are keyed by the other participant
+        let reference_id = match message_model.recipient_type.as_str() {
+            "USER" if message_model.reference_id == user_id => message_model.user_id,
+            _ => message_model.reference_id,
         };
 
-        new_seen_message
-            .insert(&app_state.db)
-            .await
-            .map_err(|e| ApiResponse::new(500, e.to_string()))?;
+        newest_messages
+            .entry((message_model.recipient_type.clone(), reference_id))
+            .or_insert(message_model);
+    }
+
+    for ((recipient_type, reference_id), message_model) in newest_messages {
+        check_user_can_read_message(&app_state, req.clone(), user_id, &message_model).await?;
+
+        advance_read_cursor(
+            &app_state,
+            &chat_room,
+            user_id,
+            &recipient_type,
+            reference_id,
+            Some(message_model.id),
+            message_model.date_created,
+        )
+        .await?;
     }
 
     Ok(ApiResponse::new(
@@ -95,35 +71,52 @@ async fn set_seen_message(
     ))
 }
 
+/// Lists the users whose read cursor has reached the message.
 #[get("/")]
 async fn get_seen_users_by_message_id(
+    req: HttpRequest,
     app_state: web::Data<app_state::AppState>,
     query: web::Query<HashMap<String, String>>,
 ) -> Result<ApiResponse, ApiResponse> {
+    let user_id = get_user_id_from_http_request(req.clone())?;
+
     let message_id = query
         .get("message_id")
         .unwrap_or(&Uuid::nil().to_string())
         .parse::<Uuid>()
         .unwrap_or(Uuid::nil());
 
-    let message = message::Entity::find()
+    let message_model = message::Entity::find()
         .filter(message::Column::Id.eq(message_id))
         .filter(message::Column::Deleted.eq(false))
         .one(&app_state.db)
         .await
-        .map_err(|e| ApiResponse::new(500, e.to_string()))?;
-
-    if message.is_none() {
-        return Err(ApiResponse::new(404, "Message not found".to_string()));
-    }
-
-    let seen_messages = seen_message::Entity::find()
-        .filter(seen_message::Column::MessageId.eq(message_id))
+        .map_err(|e| ApiResponse::new(500, e.to_string()))?
+        .ok_or(ApiResponse::new(404, "Message not found".to_string()))?;
+
+    check_user_can_read_message(&app_state, req, user_id, &message_model).await?;
+
+    let mut cursor_query = user_channel_view::Entity::find()
+        .filter(user_channel_view::Column::RecipientType.eq(message_model.recipient_type.clone()))
+        .filter(user_channel_view::Column::LastViewed.gte(message_model.date_created))
+        .filter(user_channel_view::Column::UserId.ne(message_model.user_id));
+
+    cursor_query = match message_model.recipient_type.as_str() {
+        // Only the recipient's cursor, which points back at the sender
+        "USER" => cursor_query
+            .filter(user_channel_view::Column::UserId.eq(message_model.reference_id))
+            .filter(user_channel_view::Column::ReferenceId.eq(message_model.user_id)),
+        _ => cursor_query
+            .filter(user_channel_view::Column::ReferenceId.eq(message_model.reference_id)),
+    };
+
+    let user_ids: Vec<Uuid> = cursor_query
         .all(&app_state.db)
         .await
-        .map_err(|e| ApiResponse::new(500, e.to_string()))?;
-
-    let user_ids: Vec<Uuid> = seen_messages.into_iter().map(|sm| sm.user_id).collect();
+        .map_err(|e| ApiResponse::new(500, e.to_string()))?
+        .into_iter()
+        .map(|user_channel_view| user_channel_view.user_id)
+        .collect();
 
     Ok(ApiResponse::new(
         200,
@@ -1,57 +1,77 @@
+use crate::routes::handlers::conversation_handler::{
+    check_conversation_participant, get_conversation_ids_by_user_id,
+    get_conversation_participant_ids,
+};
+use crate::routes::handlers::message_handler::{
+    check_user_can_read_message, get_array_of_readers_by_channel_id, CONTENT_MESSAGE_TYPES,
+};
 use crate::utils::api_response::ApiResponse;
 use crate::utils::app_state;
+use crate::utils::chat::ChatRoom;
 use crate::utils::jwt::get_user_id_from_http_request;
+use crate::utils::logging::log_info;
 use crate::utils::organization_util::get_organization_id_from_user_id;
 use crate::utils::permissions::{
-    check_chat_permission, check_permission, ChatPermission, Permission,
-};
-use actix_web::{get, patch, web, HttpRequest, Result};
-use entity::{
-    channel, channel_role_access, message, role, user, user_channel_view, user_role_access,
+    check_chat_permission, check_chat_permission_by_user_id, check_permission,
+    get_chat_permission_channel_ids, ChatPermission, Permission,
 };
+use actix_web::{get, patch, post, web, HttpRequest, Result};
+use chrono::NaiveDateTime;
+use entity::{channel, message, message_mention, user_channel_view};
+use sea_orm::sea_query::{Expr, IntoCondition, OnConflict};
 use sea_orm::ActiveValue::Set;
 use sea_orm::{
-    ActiveModelTrait, ColumnTrait, EntityTrait, IntoActiveModel, JoinType, QueryFilter, QueryOrder,
-    QuerySelect, RelationTrait,
+    ColumnTrait, Condition, DatabaseConnection, EntityTrait, JoinType, QueryFilter, QueryOrder,
+    QuerySelect, RelationDef, RelationTrait,
 };
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
 use uuid::Uuid;
 
 #[derive(Serialize, Deserialize)]
-struct ChannelViewDTO {
-    recipient_type: String,
-    reference_id: Uuid,
+pub(crate) struct ChannelViewDTO {
+    pub(crate) recipient_type: String,
+    pub(crate) reference_id: Uuid,
 }
 
 #[derive(Serialize, Deserialize)]
-struct ResponseDTO {
-    id: Uuid,
-    last_viewed: chrono::NaiveDateTime,
+struct UnreadChannelDTO {
     recipient_type: String,
     reference_id: Uuid,
-    user_id: Uuid,
+    unread_count: i64,
+    mention_count: i64,
+    first_unread_message_id: Option<Uuid>,
+    muted_until: Option<String>,
 }
 
 #[derive(Serialize, Deserialize)]
-struct ChannelsDto {
-    ids: Vec<ChannelViewDTO>,
+struct MarkUnreadDTO {
+    message_id: Uuid,
 }
 
 #[derive(Serialize, Deserialize)]
-struct ChannelDTO {
-    id: Uuid,
-    name: String,
-    deleted: Option<bool>,
+struct ReadStateDTO {
+    recipient_type: String,
+    reference_id: Uuid,
+    last_read_at: String,
+    first_unread_message_id: Option<Uuid>,
 }
 
-impl From<channel::Model> for ChannelDTO {
-    fn from(model: channel::Model) -> Self {
-        Self {
-            id: model.id,
-            name: model.name,
-            deleted: Some(model.deleted),
-        }
-    }
+#[derive(Serialize)]
+struct ReadStateEventDTO<'a> {
+    message_type: String,
+    read_states: &'a [ReadStateDTO],
+}
+
+#[derive(Serialize, Deserialize)]
+struct ReadReceiptEventDTO {
+    message_type: String,
+    user_id: Uuid,
+    recipient_type: String,
+    reference_id: Uuid,
+    last_read_message_id: Option<Uuid>,
+    last_read_at: String,
 }
 
 #[patch("/")]
@@ -59,207 +79,524 @@ pub async fn set_channel_last_viewed(
     app_state: web::Data<app_state::AppState>,
     req: HttpRequest,
     channel_id_dto: web::Json<ChannelViewDTO>,
+    chat_room: web::Data<Arc<ChatRoom>>,
 ) -> Result<ApiResponse, ApiResponse> {
     let user_id = get_user_id_from_http_request(req.clone())?;
 
-    let channel_id = channel_id_dto.reference_id;
-    let channel_type = channel_id_dto.recipient_type.clone();
+    mark_conversation_read(
+        &app_state,
+        &chat_room,
+        Some(req),
+        user_id,
+        &channel_id_dto.recipient_type,
+        channel_id_dto.reference_id,
+    )
+    .await?;
 
-    if channel_type == "CHANNEL" {
-        let does_user_have_access = check_chat_permission(
-            &app_state.db,
-            req.clone(),
-            ChatPermission::CanRead,
-            channel_id,
-        )
-        .await;
+    Ok(ApiResponse::new(
+        200,
+        "Channel last viewed updated".to_string(),
+    ))
+}
+
+/// Moves the user's read cursor for a conversation to now, shared by the REST
+/// endpoint and the WebSocket.
+pub(crate) async fn mark_conversation_read(
+    app_state: &web::Data<app_state::AppState>,
+    chat_room: &web::Data<Arc<ChatRoom>>,
+    req: Option<HttpRequest>,
+    user_id: Uuid,
+    recipient_type: &str,
+    reference_id: Uuid,
+) -> Result<(), ApiResponse> {
+    check_user_can_view_conversation(app_state, req, user_id, recipient_type, reference_id).await?;
+
+    advance_read_cursor(
+        app_state,
+        chat_room,
+        user_id,
+        recipient_type,
+        reference_id,
+        None,
+        chrono::Utc::now().naive_utc(),
+    )
+    .await?;
+
+    Ok(())
+}
 
-        if !does_user_have_access {
+/// Checks the user can read a channel, DM or group conversation. Without a
+/// request the channel permission is checked by user id alone.
+pub(crate) async fn check_user_can_view_conversation(
+    app_state: &web::Data<app_state::AppState>,
+    req: Option<HttpRequest>,
+    user_id: Uuid,
+    recipient_type: &str,
+    reference_id: Uuid,
+) -> Result<(), ApiResponse> {
+    match recipient_type {
+        "CHANNEL" => {
+            let does_user_have_access = match req {
+                Some(req) => {
+                    check_chat_permission(&app_state.db, req, ChatPermission::CanRead, reference_id)
+                        .await
+                }
+                None => {
+                    check_chat_permission_by_user_id(
+                        &app_state.db,
+                        user_id,
+                        ChatPermission::CanRead,
+                        reference_id,
+                    )
+                    .await
+                }
+            };
+
+            if !does_user_have_access {
+                return Err(ApiResponse::new(
+                    403,
+                    "You do not have permission to view this channel.".to_string(),
+                ));
+            }
+        }
+        "CONVERSATION" => {
+            check_conversation_participant(&app_state.db, user_id, reference_id).await?;
+        }
+        "USER" => {}
+        _ => {
             return Err(ApiResponse::new(
-                403,
-                "You do not have permission to view this channel.".to_string(),
+                400,
+                "Recipient type must be either CHANNEL, USER or CONVERSATION.".to_string(),
             ));
         }
     }
 
-    let user_channel_view = user_channel_view::Entity::find()
-        .filter(user_channel_view::Column::UserId.eq(user_id))
-        .filter(user_channel_view::Column::RecipientType.eq(channel_type.clone()))
-        .filter(user_channel_view::Column::ReferenceId.eq(channel_id))
-        .one(&app_state.db)
-        .await
-        .map_err(|e| ApiResponse::new(500, e.to_string()))?;
+    Ok(())
+}
 
-    if user_channel_view.is_none() {
-        let new_user_channel_view = user_channel_view::ActiveModel {
-            id: Set(Uuid::new_v4()),
-            user_id: Set(user_id),
-            recipient_type: Set(channel_type),
-            reference_id: Set(channel_id),
-            last_viewed: Set(chrono::Utc::now().naive_utc()),
-            ..Default::default()
-        };
-
-        new_user_channel_view
-            .insert(&app_state.db)
+/// Moves the user's read cursor for a conversation forward to `read_at` and
+/// sends a READ_RECEIPT to everyone in it. DMs are keyed by the other user.
+/// Returns false without notifying anyone when the cursor was already there.
+pub(crate) async fn advance_read_cursor(
+    app_state: &web::Data<app_state::AppState>,
+    chat_room: &web::Data<Arc<ChatRoom>>,
+    user_id: Uuid,
+    recipient_type: &str,
+    reference_id: Uuid,
+    last_read_message_id: Option<Uuid>,
+    read_at: NaiveDateTime,
+) -> Result<bool, ApiResponse> {
+    // The timestamp guard keeps concurrent updates from moving a cursor back
+    let rows_affected = user_channel_view::Entity::insert(user_channel_view::ActiveModel {
+        id: Set(Uuid::new_v4()),
+        user_id: Set(user_id),
+        recipient_type: Set(recipient_type.to_string()),
+        reference_id: Set(reference_id),
+        last_viewed: Set(read_at),
+        muted_until: Set(None),
+        last_read_message_id: Set(last_read_message_id),
+    })
+    .on_conflict(
+        get_user_channel_view_conflict()
+            .update_columns([
+                user_channel_view::Column::LastViewed,
+                user_channel_view::Column::LastReadMessageId,
+            ])
+            .action_and_where(
+                Expr::col((
+                    user_channel_view::Entity,
+                    user_channel_view::Column::LastViewed,
+                ))
+                .lt(read_at),
+            )
+            .to_owned(),
+    )
+    .exec_without_returning(&app_state.db)
+    .await
+    .map_err(|e| ApiResponse::new(500, e.to_string()))?;
+
+    if rows_affected == 0 {
+        return Ok(false);
+    }
+
+    send_read_receipt(
+        app_state,
+        chat_room,
+        user_id,
+        recipient_type,
+        reference_id,
+        last_read_message_id,
+        read_at,
+    )
+    .await?;
+
+    Ok(true)
+}
+
+/// Tells everyone in the conversation where the user's read cursor now is.
+async fn send_read_receipt(
+    app_state: &web::Data<app_state::AppState>,
+    chat_room: &web::Data<Arc<ChatRoom>>,
+    user_id: Uuid,
+    recipient_type: &str,
+    reference_id: Uuid,
+    last_read_message_id: Option<Uuid>,
+    read_at: NaiveDateTime,
+) -> Result<(), ApiResponse> {
+    let event_dto = ReadReceiptEventDTO {
+        message_type: "READ_RECEIPT".to_string(),
+        user_id,
+        recipient_type: recipient_type.to_string(),
+        reference_id,
+        last_read_message_id,
+        last_read_at: read_at.to_string(),
+    };
+
+    let user_ids = match recipient_type {
+        "CHANNEL" => get_array_of_readers_by_channel_id(app_state, reference_id).await?,
+        "CONVERSATION" => get_conversation_participant_ids(&app_state.db, reference_id).await?,
+        _ => vec![user_id, reference_id],
+    };
+
+    chat_room.send_message(&user_ids, &serde_json::to_string(&event_dto).unwrap());
+
+    Ok(())
+}
+
+/// Moves the user's cursor to now in every channel, DM and group with unread
+/// messages.
+#[post("/mark-all-read")]
+pub async fn mark_all_read(
+    app_state: web::Data<app_state::AppState>,
+    req: HttpRequest,
+    chat_room: web::Data<Arc<ChatRoom>>,
+) -> Result<ApiResponse, ApiResponse> {
+    let user_id = get_user_id_from_http_request(req.clone())?;
+
+    let scopes = get_unread_scopes(&app_state, req.clone(), user_id).await?;
+    let read_at = chrono::Utc::now().naive_utc();
+
+    let mut read_states: Vec<ReadStateDTO> = Vec::new();
+
+    for UnreadScope {
+        recipient_type,
+        key_column,
+        unread_condition,
+    } in scopes
+    {
+        let reference_ids: Vec<Uuid> = message::Entity::find()
+            .select_only()
+            .column(key_column)
+            .distinct()
+            .join(
+                JoinType::LeftJoin,
+                get_user_channel_view_relation(user_id, recipient_type, key_column),
+            )
+            .filter(unread_condition)
+            .into_tuple()
+            .all(&app_state.db)
             .await
             .map_err(|e| ApiResponse::new(500, e.to_string()))?;
-    } else {
-        let user_channel_view = user_channel_view.unwrap();
 
-        let mut user_channel_view = user_channel_view.into_active_model();
-        user_channel_view.last_viewed = Set(chrono::Utc::now().naive_utc());
-        user_channel_view.user_id = Set(user_id);
-        user_channel_view.recipient_type = Set(channel_type);
-        user_channel_view.reference_id = Set(channel_id);
+        if reference_ids.is_empty() {
+            continue;
+        }
 
-        user_channel_view
-            .update(&app_state.db)
+        // Existing views are moved and missing ones added in one statement
+        let user_channel_views: Vec<user_channel_view::ActiveModel> = reference_ids
+            .iter()
+            .map(|reference_id| user_channel_view::ActiveModel {
+                id: Set(Uuid::new_v4()),
+                user_id: Set(user_id),
+                recipient_type: Set(recipient_type.to_string()),
+                reference_id: Set(*reference_id),
+                last_viewed: Set(read_at),
+                muted_until: Set(None),
+                last_read_message_id: Set(None),
+            })
+            .collect();
+
+        user_channel_view::Entity::insert_many(user_channel_views)
+            .on_conflict(
+                get_user_channel_view_conflict()
+                    .update_columns([
+                        user_channel_view::Column::LastViewed,
+                        user_channel_view::Column::LastReadMessageId,
+                    ])
+                    .to_owned(),
+            )
+            .exec_without_returning(&app_state.db)
             .await
             .map_err(|e| ApiResponse::new(500, e.to_string()))?;
+
+        read_states.extend(reference_ids.into_iter().map(|reference_id| ReadStateDTO {
+            recipient_type: recipient_type.to_string(),
+            reference_id,
+            last_read_at: read_at.to_string(),
+            first_unread_message_id: None,
+        }));
+    }
+
+    log_info(
+        req,
+        format!("Marked {} conversations as read", read_states.len()),
+    );
+
+    send_read_state_event(&chat_room, user_id, &read_states);
+
+    for read_state in read_states.iter() {
+        send_read_receipt(
+            &app_state,
+            &chat_room,
+            user_id,
+            &read_state.recipient_type,
+            read_state.reference_id,
+            None,
+            read_at,
+        )
+        .await?;
     }
 
     Ok(ApiResponse::new(
         200,
-        "Channel last viewed updated".to_string(),
+        serde_json::to_string(&read_states).unwrap(),
     ))
 }
 
-#[get("/unread")]
-pub async fn get_unread_channels(
+/// Moves the user's cursor back so the message and everything after it is
+/// unread again.
+#[post("/mark-unread")]
+pub async fn mark_unread(
     app_state: web::Data<app_state::AppState>,
     req: HttpRequest,
+    mark_unread_dto: web::Json<MarkUnreadDTO>,
+    chat_room: web::Data<Arc<ChatRoom>>,
 ) -> Result<ApiResponse, ApiResponse> {
     let user_id = get_user_id_from_http_request(req.clone())?;
 
-    let user_organization_id = get_organization_id_from_user_id(&app_state.db, user_id).await?;
+    let message_model = message::Entity::find()
+        .filter(message::Column::Id.eq(mark_unread_dto.message_id))
+        .filter(message::Column::Deleted.eq(false))
+        .one(&app_state.db)
+        .await
+        .map_err(|e| ApiResponse::new(500, e.to_string()))?
+        .ok_or(ApiResponse::new(404, "Message not found".to_string()))?;
 
-    // Check if the user has the ManageChannels permission
-    let has_manage_channels =
-        check_permission(&app_state.db, req, Permission::ManageChannels).await;
+    check_user_can_read_message(&app_state, req.clone(), user_id, &message_model).await?;
 
-    // Fetch all channels or only those the user has access to
-    let channels: Vec<channel::Model> = if has_manage_channels {
-        channel::Entity::find()
-            .filter(channel::Column::Deleted.eq(false))
-            .filter(channel::Column::OrganizationId.eq(user_organization_id))
-            .all(&app_state.db)
-            .await
-            .map_err(|e| ApiResponse::new(500, e.to_string()))?
-    } else {
-        channel::Entity::find()
-            .distinct()
-            .join(JoinType::Join, channel::Relation::ChannelRoleAccess.def())
-            .join(JoinType::Join, channel_role_access::Relation::Role.def())
-            .join(JoinType::Join, role::Relation::UserRoleAccess.def())
-            .join(JoinType::Join, user_role_access::Relation::User.def())
-            .filter(channel::Column::Deleted.eq(false))
-            .filter(channel_role_access::Column::Deleted.eq(false))
-            .filter(user_role_access::Column::Deleted.eq(false))
-            .filter(channel_role_access::Column::CanRead.eq(true))
-            .filter(user::Column::Id.eq(user_id))
-            .all(&app_state.db)
-            .await
-            .map_err(|e| ApiResponse::new(500, e.to_string()))?
+    let reference_id = match message_model.recipient_type.as_str() {
+        "USER" if message_model.reference_id == user_id => message_model.user_id,
+        _ => message_model.reference_id,
     };
 
-    let mut unread_channels: Vec<ChannelViewDTO> = Vec::new();
+    let read_at = message_model.date_created - chrono::Duration::microseconds(1);
+
+    user_channel_view::Entity::insert(user_channel_view::ActiveModel {
+        id: Set(Uuid::new_v4()),
+        user_id: Set(user_id),
+        recipient_type: Set(message_model.recipient_type.clone()),
+        reference_id: Set(reference_id),
+        last_viewed: Set(read_at),
+        muted_until: Set(None),
+        last_read_message_id: Set(None),
+    })
+    .on_conflict(
+        get_user_channel_view_conflict()
+            .update_columns([
+                user_channel_view::Column::LastViewed,
+                user_channel_view::Column::LastReadMessageId,
+            ])
+            .to_owned(),
+    )
+    .exec_without_returning(&app_state.db)
+    .await
+    .map_err(|e| ApiResponse::new(500, e.to_string()))?;
+
+    log_info(
+        req,
+        format!(
+            "Marked conversation unread from message {}",
+            message_model.id
+        ),
+    );
+
+    send_read_receipt(
+        &app_state,
+        &chat_room,
+        user_id,
+        &message_model.recipient_type,
+        reference_id,
+        None,
+        read_at,
+    )
+    .await?;
+
+    let read_states = vec![ReadStateDTO {
+        recipient_type: message_model.recipient_type,
+        reference_id,
+        last_read_at: read_at.to_string(),
+        first_unread_message_id: Some(message_model.id),
+    }];
+
+    send_read_state_event(&chat_room, user_id, &read_states);
 
-    // Iterate over each channel to check for unread messages
-    for channel in channels {
-        // Find the most recent message in the channel
-        let last_message = message::Entity::find()
-            .filter(message::Column::ReferenceId.eq(channel.id))
-            .filter(message::Column::RecipientType.eq("CHANNEL".to_string()))
-            .order_by_desc(message::Column::DateCreated) // Order by most recent message
-            .one(&app_state.db)
-            .await
-            .map_err(|e| ApiResponse::new(500, e.to_string()))?;
+    Ok(ApiResponse::new(
+        200,
+        serde_json::to_string(&read_states).unwrap(),
+    ))
+}
 
-        // Skip channels with no messages
-        if last_message.is_none() {
-            continue;
-        }
+/// Keeps the user's other sessions in step with read state changed in bulk.
+fn send_read_state_event(
+    chat_room: &web::Data<Arc<ChatRoom>>,
+    user_id: Uuid,
+    read_states: &[ReadStateDTO],
+) {
+    if read_states.is_empty() {
+        return;
+    }
 
-        let user_channel_view = user_channel_view::Entity::find()
-            .filter(user_channel_view::Column::UserId.eq(user_id))
-            .filter(user_channel_view::Column::RecipientType.eq("CHANNEL".to_string()))
-            .filter(user_channel_view::Column::ReferenceId.eq(channel.id))
-            .one(&app_state.db)
-            .await
-            .map_err(|e| ApiResponse::new(500, e.to_string()))?;
+    let event_dto = ReadStateEventDTO {
+        message_type: "READ_STATE_UPDATED".to_string(),
+        read_states,
+    };
 
-        // If no user_channel_view exists, mark the channel as unread
-        if user_channel_view.is_none() {
-            unread_channels.push(ChannelViewDTO {
-                recipient_type: "CHANNEL".to_string(),
-                reference_id: channel.id,
-            });
-        } else {
-            let user_channel_view = user_channel_view.unwrap();
-            let last_viewed = user_channel_view.last_viewed;
-
-            // Compare the last message's timestamp with the last_viewed timestamp
-            if last_message.unwrap().date_created > last_viewed {
-                unread_channels.push(ChannelViewDTO {
-                    recipient_type: "CHANNEL".to_string(),
-                    reference_id: channel.id,
-                });
-            }
-        }
-    }
+    chat_room.send_message(&vec![user_id], &serde_json::to_string(&event_dto).unwrap());
+}
 
-    // Fetch all users the current user has messaged with
-    let users: Vec<user::Model> = user::Entity::find()
-        .filter(user::Column::Id.ne(user_id)) // Exclude current user
-        .filter(user::Column::OrganizationId.eq(user_organization_id))
-        .all(&app_state.db)
-        .await
-        .map_err(|e| ApiResponse::new(500, e.to_string()))?;
+/// Mutes a chat for the user until `muted_until`, or unmutes it when `None`.
+pub(crate) async fn set_muted_until(
+    db: &DatabaseConnection,
+    user_id: Uuid,
+    recipient_type: &str,
+    reference_id: Uuid,
+    muted_until: Option<NaiveDateTime>,
+) -> Result<(), ApiResponse> {
+    user_channel_view::Entity::insert(user_channel_view::ActiveModel {
+        id: Set(Uuid::new_v4()),
+        user_id: Set(user_id),
+        recipient_type: Set(recipient_type.to_string()),
+        reference_id: Set(reference_id),
+        // A view created by muting leaves the chat as unread as having no view
+        last_viewed: Set(chrono::DateTime::UNIX_EPOCH.naive_utc()),
+        muted_until: Set(muted_until),
+        last_read_message_id: Set(None),
+    })
+    .on_conflict(
+        get_user_channel_view_conflict()
+            .update_column(user_channel_view::Column::MutedUntil)
+            .to_owned(),
+    )
+    .exec_without_returning(db)
+    .await
+    .map_err(|e| ApiResponse::new(500, e.to_string()))?;
+
+    Ok(())
+}
 
-    // Iterate over each user to check for unread direct messages
-    for user in users {
-        // Find the most recent message from the user to the current user
-        let last_message = message::Entity::find()
-            .filter(message::Column::ReferenceId.eq(user_id))
-            .filter(message::Column::RecipientType.eq("USER".to_string()))
-            .filter(message::Column::UserId.eq(user.id))
-            .order_by_desc(message::Column::DateCreated)
-            .one(&app_state.db)
-            .await
-            .map_err(|e| ApiResponse::new(500, e.to_string()))?;
+/// Each user has one view per conversation, upserts conflict on it.
+pub(crate) fn get_user_channel_view_conflict() -> OnConflict {
+    OnConflict::columns([
+        user_channel_view::Column::UserId,
+        user_channel_view::Column::RecipientType,
+        user_channel_view::Column::ReferenceId,
+    ])
+}
 
-        // Skip if there are no messages from this user
-        if last_message.is_none() {
-            continue;
-        }
+#[get("/unread")]
+pub async fn get_unread_channels(
+    app_state: web::Data<app_state::AppState>,
+    req: HttpRequest,
+) -> Result<ApiResponse, ApiResponse> {
+    let user_id = get_user_id_from_http_request(req.clone())?;
+
+    let scopes = get_unread_scopes(&app_state, req, user_id).await?;
 
-        let user_channel_view = user_channel_view::Entity::find()
-            .filter(user_channel_view::Column::UserId.eq(user_id))
-            .filter(user_channel_view::Column::RecipientType.eq("USER".to_string()))
-            .filter(user_channel_view::Column::ReferenceId.eq(user.id))
-            .one(&app_state.db)
+    // Mutes are kept on the view rows, so only the few active ones are loaded
+    let muted_views: HashMap<(String, Uuid), NaiveDateTime> = user_channel_view::Entity::find()
+        .filter(user_channel_view::Column::UserId.eq(user_id))
+        .filter(user_channel_view::Column::MutedUntil.gt(chrono::Utc::now().naive_utc()))
+        .all(&app_state.db)
+        .await
+        .map_err(|e| ApiResponse::new(500, e.to_string()))?
+        .into_iter()
+        .filter_map(|user_channel_view| {
+            user_channel_view.muted_until.map(|muted_until| {
+                (
+                    (
+                        user_channel_view.recipient_type,
+                        user_channel_view.reference_id,
+                    ),
+                    muted_until,
+                )
+            })
+        })
+        .collect();
+
+    let mut unread_channels: Vec<UnreadChannelDTO> = Vec::new();
+
+    for UnreadScope {
+        recipient_type,
+        key_column,
+        unread_condition,
+    } in scopes
+    {
+        // Counts every unread message and the unread ones mentioning the user,
+        // there is at most one mention row per message and user
+        let counts: Vec<(Uuid, i64, i64)> = message::Entity::find()
+            .select_only()
+            .column(key_column)
+            .column_as(message::Column::Id.count(), "unread_count")
+            .column_as(
+                Expr::col((message_mention::Entity, message_mention::Column::Id)).count(),
+                "mention_count",
+            )
+            .join(
+                JoinType::LeftJoin,
+                get_user_channel_view_relation(user_id, recipient_type, key_column),
+            )
+            .join(JoinType::LeftJoin, get_message_mention_relation(user_id))
+            .filter(unread_condition.clone())
+            .group_by(key_column)
+            .into_tuple()
+            .all(&app_state.db)
             .await
             .map_err(|e| ApiResponse::new(500, e.to_string()))?;
 
-        // If no user_channel_view exists, mark the message as unread
-        if user_channel_view.is_none() {
-            unread_channels.push(ChannelViewDTO {
-                recipient_type: "USER".to_string(),
-                reference_id: user.id,
-            });
-        } else {
-            let user_channel_view = user_channel_view.unwrap();
-            let last_viewed = user_channel_view.last_viewed;
-
-            // Compare the last message's timestamp with the last_viewed timestamp
-            if last_message.unwrap().date_created > last_viewed {
-                unread_channels.push(ChannelViewDTO {
-                    recipient_type: "USER".to_string(),
-                    reference_id: user.id,
-                });
-            }
-        }
+        let first_unread_message_ids: HashMap<Uuid, Uuid> = message::Entity::find()
+            .select_only()
+            .column(key_column)
+            .column(message::Column::Id)
+            .distinct_on([(message::Entity, key_column)])
+            .join(
+                JoinType::LeftJoin,
+                get_user_channel_view_relation(user_id, recipient_type, key_column),
+            )
+            .filter(unread_condition)
+            .order_by_asc(key_column)
+            .order_by_asc(message::Column::DateCreated)
+            .order_by_asc(message::Column::Id)
+            .into_tuple::<(Uuid, Uuid)>()
+            .all(&app_state.db)
+            .await
+            .map_err(|e| ApiResponse::new(500, e.to_string()))?
+            .into_iter()
+            .collect();
+
+        unread_channels.extend(counts.into_iter().map(
+            |(reference_id, unread_count, mention_count)| {
+                UnreadChannelDTO {
+                    recipient_type: recipient_type.to_string(),
+                    reference_id,
+                    unread_count,
+                    mention_count,
+                    first_unread_message_id: first_unread_message_ids.get(&reference_id).copied(),
+                    muted_until: muted_views
+                        .get(&(recipient_type.to_string(), reference_id))
+                        .map(|muted_until| muted_until.to_string()),
+                }
+            },
+        ));
     }
 
     Ok(ApiResponse::new(
@@ -267,3 +604,131 @@ pub async fn get_unread_channels(
         serde_json::to_string(&unread_channels).unwrap(),
     ))
 }
+
+/// Messages in a DM are keyed by their sender, everything else by the
+/// conversation they were sent to.
+struct UnreadScope {
+    recipient_type: &'static str,
+    key_column: message::Column,
+    /// Matches the user's unread messages once the view relation is joined.
+    unread_condition: Condition,
+}
+
+async fn get_unread_scopes(
+    app_state: &web::Data<app_state::AppState>,
+    req: HttpRequest,
+    user_id: Uuid,
+) -> Result<Vec<UnreadScope>, ApiResponse> {
+    let user_organization_id = get_organization_id_from_user_id(&app_state.db, user_id).await?;
+
+    // Check if the user has the ManageChannels permission
+    let has_manage_channels =
+        check_permission(&app_state.db, req.clone(), Permission::ManageChannels).await;
+
+    // Fetch all channels or only those the user can read, under the same
+    // rules as `check_chat_permission`
+    let mut channel_query = channel::Entity::find()
+        .filter(channel::Column::Deleted.eq(false))
+        .filter(channel::Column::OrganizationId.eq(user_organization_id));
+
+    if !has_manage_channels {
+        let readable_channel_ids =
+            get_chat_permission_channel_ids(&app_state.db, req, ChatPermission::CanRead).await;
+
+        channel_query = channel_query.filter(channel::Column::Id.is_in(readable_channel_ids));
+    }
+
+    let channel_ids: Vec<Uuid> = channel_query
+        .select_only()
+        .column(channel::Column::Id)
+        .into_tuple()
+        .all(&app_state.db)
+        .await
+        .map_err(|e| ApiResponse::new(500, e.to_string()))?;
+
+    let conversation_ids = get_conversation_ids_by_user_id(&app_state.db, user_id).await?;
+
+    let scopes = [
+        (
+            "CHANNEL",
+            message::Column::ReferenceId,
+            message::Column::ReferenceId.is_in(channel_ids),
+        ),
+        (
+            "USER",
+            message::Column::UserId,
+            message::Column::ReferenceId.eq(user_id),
+        ),
+        (
+            "CONVERSATION",
+            message::Column::ReferenceId,
+            message::Column::ReferenceId.is_in(conversation_ids),
+        ),
+    ];
+
+    Ok(scopes
+        .into_iter()
+        .map(
+            |(recipient_type, key_column, scope_condition)| UnreadScope {
+                recipient_type,
+                key_column,
+                unread_condition: Condition::all()
+                    .add(message::Column::RecipientType.eq(recipient_type))
+                    .add(scope_condition)
+                    .add(message::Column::UserId.ne(user_id))
+                    .add(message::Column::Deleted.eq(false))
+                    .add(message::Column::MessageType.is_in(CONTENT_MESSAGE_TYPES))
+                    // Thread replies don't make a conversation unread
+                    .add(message::Column::ParentId.is_null())
+                    .add(
+                        Condition::any()
+                            .add(
+                                Expr::col((
+                                    user_channel_view::Entity,
+                                    user_channel_view::Column::Id,
+                                ))
+                                .is_null(),
+                            )
+                            .add(
+                                Expr::col((message::Entity, message::Column::DateCreated)).gt(
+                                    Expr::col((
+                                        user_channel_view::Entity,
+                                        user_channel_view::Column::LastViewed,
+                                    )),
+                                ),
+                            ),
+                    ),
+            },
+        )
+        .collect())
+}
+
+/// Joins each message to the user's view of the conversation it belongs to.
+fn get_user_channel_view_relation(
+    user_id: Uuid,
+    recipient_type: &'static str,
+    key_column: message::Column,
+) -> RelationDef {
+    message::Entity::belongs_to(user_channel_view::Entity)
+        .from(key_column)
+        .to(user_channel_view::Column::ReferenceId)
+        .on_condition(move |_left, right| {
+            Condition::all()
+                .add(Expr::col((right.clone(), user_channel_view::Column::UserId)).eq(user_id))
+                .add(
+                    Expr::col((right, user_channel_view::Column::RecipientType)).eq(recipient_type),
+                )
+        })
+        .into()
+}
+
+/// Joins each message to the mention of `user_id` in it, if any.
+fn get_message_mention_relation(user_id: Uuid) -> RelationDef {
+    message::Relation::MessageMention
+        .def()
+        .on_condition(move |_left, right| {
+            Expr::col((right, message_mention::Column::UserId))
+                .eq(user_id)
+                .into_condition()
+        })
+}
@@ -1,9 +1,10 @@
 use crate::utils;
 use crate::utils::api_response::ApiResponse;
 use crate::utils::chat::ChatRoom;
+use crate::utils::event::ChatEvent;
 use crate::utils::jwt::get_user_id_from_http_request;
 use crate::utils::logging::log_info;
-use crate::utils::message::send_update_status_to_all_users;
+use crate::utils::message::send_event_to_all_users;
 use crate::utils::organization_util::get_organization_id_from_user_id;
 use crate::utils::permissions::{
     check_chat_permission, check_permission, ChatPermission, Permission,
@@ -370,7 +371,15 @@ pub async fn update_display_name(
             display_name: updated_user.display_name.clone(),
         };
 
-        send_update_status_to_all_users(user_organization_id, &app_state, &chat_room).await;
+        send_event_to_all_users(
+            &ChatEvent::UserRenamed {
+                user: updated_user.into(),
+            },
+            user_organization_id,
+            &app_state,
+            &chat_room,
+        )
+        .await;
 
         Ok(ApiResponse::new(
             200,
@@ -443,12 +452,20 @@ pub async fn update_profile_image(
 
         let user_organization_id = get_organization_id_from_user_id(&app_state.db, user_id).await?;
 
-        active_user
+        let updated_user = active_user
             .update(&app_state.db)
             .await
             .map_err(|e| ApiResponse::new(500, e.to_string()))?;
 
-        send_update_status_to_all_users(user_organization_id, &app_state, &chat_room).await;
+        send_event_to_all_users(
+            &ChatEvent::UserUpdated {
+                user: updated_user.into(),
+            },
+            user_organization_id,
+            &app_state,
+            &chat_room,
+        )
+        .await;
 
         Ok(ApiResponse::new(
             200,
@@ -1,9 +1,10 @@
 use crate::utils::api_response::ApiResponse;
 use crate::utils::app_state;
 use crate::utils::chat::ChatRoom;
+use crate::utils::event::ChatEvent;
 use crate::utils::jwt::get_user_id_from_http_request;
 use crate::utils::logging::log_info;
-use crate::utils::message::send_update_status_from_role_id_and_org_id;
+use crate::utils::message::send_event_from_role_id_and_org_id;
 use crate::utils::organization_util::get_organization_id_from_user_id;
 use crate::utils::permissions::{check_permission, Permission};
 use actix_web::{delete, get, post, web, HttpRequest, Result};
@@ -28,11 +29,6 @@ struct UserRoleAccessIdDTO {
     id: Uuid,
 }
 
-#[derive(Serialize, Deserialize)]
-struct MessageDTO {
-    message_type: String,
-}
-
 impl From<user_role_access::Model> for UserRoleAccessDTO {
     fn from(model: user_role_access::Model) -> Self {
         Self {
@@ -118,7 +114,11 @@ pub async fn create_user_role_access(
 
     let response_dto: UserRoleAccessDTO = user_role_access_model.into();
 
-    send_update_status_from_role_id_and_org_id(
+    send_event_from_role_id_and_org_id(
+        &ChatEvent::RoleGranted {
+            user_id: user_role_access_dto.user_id,
+            role_id: user_role_access_dto.role_id,
+        },
         user_role_access_dto.role_id,
         user_organization_id,
         &app_state,
@@ -234,7 +234,11 @@ pub async fn delete(
         format!("User role access {} was deleted", user_role_access_dto.id),
     );
 
-    send_update_status_from_role_id_and_org_id(
+    send_event_from_role_id_and_org_id(
+        &ChatEvent::RoleRevoked {
+            user_id: updated_user_role_access.user_id,
+            role_id: updated_user_role_access.role_id,
+        },
         updated_user_role_access.role_id,
         user_organization_id,
         &app_state,
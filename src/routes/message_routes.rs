@@ -11,6 +11,10 @@ pub fn config(config: &mut web::ServiceConfig) {
             .service(handlers::message_handler::get_by_channel_and_per_page)
             .service(handlers::message_handler::edit_message)
             .service(handlers::message_handler::delete_message)
-            .service(handlers::message_handler::search_messages),
+            .service(handlers::message_handler::search_messages)
+            .service(handlers::message_handler::get_thread)
+            .service(handlers::message_handler::get_thread_reply_counts)
+            .service(handlers::message_handler::get_thread_latest_replies)
+            .service(handlers::message_handler::get_message_revisions),
     );
 }
@@ -5,6 +5,9 @@ pub fn config(config: &mut web::ServiceConfig) {
     config.service(
         web::scope("/organization")
             .service(handlers::organization_handler::get_organization_exists)
-            .service(handlers::organization_handler::get_all_organizations),
+            .service(handlers::organization_handler::get_all_organizations)
+            .service(handlers::organization_handler::get_organization_settings)
+            .service(handlers::organization_handler::update_organization_settings)
+            .service(handlers::organization_handler::get_retention_actions),
     );
 }
@@ -0,0 +1,14 @@
+use super::handlers;
+use crate::middlewares;
+use actix_web::web;
+use actix_web_lab::middleware::from_fn;
+
+pub fn config(config: &mut web::ServiceConfig) {
+    config.service(
+        web::scope("/pin")
+            .wrap(from_fn(middlewares::auth_middleware::check_auth_middleware))
+            .service(handlers::pin_handler::pin_message)
+            .service(handlers::pin_handler::unpin_message)
+            .service(handlers::pin_handler::get_pinned_messages),
+    );
+}
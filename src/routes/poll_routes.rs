@@ -0,0 +1,14 @@
+use super::handlers;
+use crate::middlewares;
+use actix_web::web;
+use actix_web_lab::middleware::from_fn;
+
+pub fn config(config: &mut web::ServiceConfig) {
+    config.service(
+        web::scope("/poll")
+            .wrap(from_fn(middlewares::auth_middleware::check_auth_middleware))
+            .service(handlers::poll_handler::get_poll)
+            .service(handlers::poll_handler::vote)
+            .service(handlers::poll_handler::unvote),
+    );
+}
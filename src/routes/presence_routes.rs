@@ -8,6 +8,7 @@ pub fn config(config: &mut web::ServiceConfig) {
         web::scope("/presence")
             .wrap(from_fn(middlewares::auth_middleware::check_auth_middleware))
             .service(handlers::presence_handler::get_all)
+            .service(handlers::presence_handler::get_my_sessions)
             .service(handlers::presence_handler::send_typing_indicator_to_channel_id),
     );
 }
@@ -0,0 +1,13 @@
+use super::handlers;
+use crate::middlewares;
+use actix_web::web;
+use actix_web_lab::middleware::from_fn;
+
+pub fn config(config: &mut web::ServiceConfig) {
+    config.service(
+        web::scope("/reaction")
+            .wrap(from_fn(middlewares::auth_middleware::check_auth_middleware))
+            .service(handlers::reaction_handler::add_reaction)
+            .service(handlers::reaction_handler::remove_reaction),
+    );
+}
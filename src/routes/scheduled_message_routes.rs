@@ -0,0 +1,14 @@
+use super::handlers;
+use crate::middlewares;
+use actix_web::web;
+use actix_web_lab::middleware::from_fn;
+
+pub fn config(config: &mut web::ServiceConfig) {
+    config.service(
+        web::scope("/scheduled-message")
+            .wrap(from_fn(middlewares::auth_middleware::check_auth_middleware))
+            .service(handlers::scheduled_message_handler::get_scheduled_messages)
+            .service(handlers::scheduled_message_handler::edit_scheduled_message)
+            .service(handlers::scheduled_message_handler::cancel_scheduled_message),
+    );
+}
@@ -8,6 +8,8 @@ pub fn config(config: &mut web::ServiceConfig) {
         web::scope("/user-channel-view")
             .wrap(from_fn(middlewares::auth_middleware::check_auth_middleware))
             .service(handlers::user_channel_view_handler::set_channel_last_viewed)
-            .service(handlers::user_channel_view_handler::get_unread_channels),
+            .service(handlers::user_channel_view_handler::get_unread_channels)
+            .service(handlers::user_channel_view_handler::mark_all_read)
+            .service(handlers::user_channel_view_handler::mark_unread),
     );
 }
@@ -2,4 +2,5 @@ use sea_orm::DatabaseConnection;
 
 pub struct AppState {
     pub db: DatabaseConnection,
+    pub webhook_client: reqwest::Client,
 }
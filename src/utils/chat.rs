@@ -1,13 +1,98 @@
-use actix::{Actor, ActorContext, Addr, AsyncContext, StreamHandler};
+use crate::utils::app_state::AppState;
+use crate::utils::socket_command::handle_socket_command;
+use actix::{Actor, ActorContext, ActorFutureExt, Addr, AsyncContext, StreamHandler, WrapFuture};
+use actix_web::web;
 use actix_web_actors::ws;
+use chrono::Utc;
 use serde::Serialize;
-use std::collections::{HashMap, HashSet};
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 use uuid::Uuid;
 
 pub struct ChatRoom {
     sessions: Arc<Mutex<HashSet<Addr<MyWebSocket>>>>,
-    user_sessions: Arc<Mutex<HashMap<Uuid, Addr<MyWebSocket>>>>, // Store user sessions
+    user_sessions: Arc<Mutex<HashMap<Uuid, HashMap<Uuid, UserSession>>>>, // Store user sessions by session id
+    /// Online users by organization, so presence stays within each tenant.
+    /// Always locked after `user_sessions`.
+    organization_users: Arc<Mutex<HashMap<Uuid, HashSet<Uuid>>>>,
+    /// Logs of users who are connected or were recently. Always locked after
+    /// `user_sessions` so replays and live events can't interleave, and before
+    /// `organization_users`.
+    event_logs: Arc<Mutex<HashMap<Uuid, EventLog>>>,
+}
+
+/// Events sent to a user are kept so a reconnecting client can resume. The
+/// count and byte limits bound each log, and logs are dropped once the user
+/// has been gone for `REPLAY_WINDOW`.
+const MAX_REPLAY_EVENTS: usize = 500;
+const MAX_REPLAY_BYTES: usize = 256 * 1024;
+const REPLAY_WINDOW: Duration = Duration::from_secs(5 * 60);
+
+/// The user's recent events in sequence order. Event bodies are stored without
+/// their `seq` and shared between every recipient.
+struct EventLog {
+    next_seq: u64,
+    events: VecDeque<(u64, Arc<str>)>,
+    bytes: usize,
+    /// When the user's last session closed.
+    disconnected_at: Option<Instant>,
+}
+
+impl EventLog {
+    fn new() -> Self {
+        EventLog {
+            // A new log starts far above any sequence a client kept from an
+            // earlier log or process, so stale resumes fall outside it
+            next_seq: chrono::Utc::now().timestamp_micros() as u64,
+            events: VecDeque::new(),
+            bytes: 0,
+            disconnected_at: None,
+        }
+    }
+
+    fn is_expired(&self) -> bool {
+        self.disconnected_at
+            .is_some_and(|disconnected_at| disconnected_at.elapsed() >= REPLAY_WINDOW)
+    }
+
+    fn push(&mut self, event: Arc<str>) -> u64 {
+        let seq = self.next_seq;
+        self.next_seq += 1;
+
+        self.bytes += event.len();
+        self.events.push_back((seq, event));
+
+        while self.events.len() > 1
+            && (self.events.len() > MAX_REPLAY_EVENTS || self.bytes > MAX_REPLAY_BYTES)
+        {
+            if let Some((_, event)) = self.events.pop_front() {
+                self.bytes -= event.len();
+            }
+        }
+
+        seq
+    }
+}
+
+/// Adds `seq` as the first field of a serialized event without parsing it.
+fn with_seq(seq: u64, event: &str) -> String {
+    match event.strip_prefix('{') {
+        Some(fields) if fields.trim_start().starts_with('}') => {
+            format!("{{\"seq\":{}{}", seq, fields.trim_start())
+        }
+        Some(fields) => format!("{{\"seq\":{},{}", seq, fields),
+        None => event.to_string(),
+    }
+}
+
+/// One of a user's open connections, a user can hold one per device.
+struct UserSession {
+    addr: Addr<MyWebSocket>,
+    device: Option<String>,
+    /// Conversations the session has subscribed to over the socket, keyed
+    /// by recipient type and reference id.
+    subscriptions: HashSet<(String, Uuid)>,
 }
 
 #[derive(Serialize)]
@@ -15,40 +100,237 @@ struct MessageDTO {
     message_type: String,
 }
 
+#[derive(Serialize)]
+struct ConnectedDTO {
+    message_type: String,
+    session_id: Uuid,
+    seq: u64,
+}
+
 impl ChatRoom {
     pub fn new() -> Self {
         ChatRoom {
             sessions: Arc::new(Mutex::new(HashSet::new())),
             user_sessions: Arc::new(Mutex::new(HashMap::new())),
+            organization_users: Arc::new(Mutex::new(HashMap::new())),
+            event_logs: Arc::new(Mutex::new(HashMap::new())),
         }
     }
 
+    /// Stamps the event with each user's next sequence number and logs it for
+    /// replay. Users without a recent session have no log and are skipped.
     pub fn send_message(&self, user_ids: &Vec<Uuid>, message: &str) {
+        let event: Arc<str> = Arc::from(message);
+
+        let user_sessions = self.user_sessions.lock().unwrap();
+        let mut event_logs = self.event_logs.lock().unwrap();
+        for user_id in user_ids {
+            let Some(event_log) = event_logs.get_mut(user_id) else {
+                continue;
+            };
+
+            if event_log.is_expired() {
+                continue;
+            }
+
+            let seq = event_log.push(event.clone());
+
+            if let Some(sessions) = user_sessions.get(user_id) {
+                let message = with_seq(seq, &event);
+                for session in sessions.values() {
+                    session.addr.do_send(MyMessage(message.clone()));
+                }
+            }
+        }
+    }
+
+    /// Like `send_message`, but sessions that have subscribed to conversations
+    /// only get the message when it belongs to one of them. These events are
+    /// short-lived, so they aren't sequenced or replayed.
+    pub fn send_subscribed_message(
+        &self,
+        user_ids: &Vec<Uuid>,
+        recipient_type: &str,
+        reference_id: Uuid,
+        message: &str,
+    ) {
+        let subscription = (recipient_type.to_string(), reference_id);
         let user_sessions = self.user_sessions.lock().unwrap();
         for user_id in user_ids {
-            if let Some(session) = user_sessions.get(user_id) {
-                session.do_send(MyMessage(message.to_string()));
+            if let Some(sessions) = user_sessions.get(user_id) {
+                for session in sessions.values() {
+                    if session.subscriptions.is_empty()
+                        || session.subscriptions.contains(&subscription)
+                    {
+                        session.addr.do_send(MyMessage(message.to_string()));
+                    }
+                }
             }
         }
     }
 
-    pub fn add_session(&self, user_id: Uuid, addr: Addr<MyWebSocket>) {
+    /// Returns false when the session has already closed.
+    pub fn set_subscribed(
+        &self,
+        user_id: Uuid,
+        session_id: Uuid,
+        recipient_type: &str,
+        reference_id: Uuid,
+        subscribed: bool,
+    ) -> bool {
+        let mut user_sessions = self.user_sessions.lock().unwrap();
+        let session = match user_sessions
+            .get_mut(&user_id)
+            .and_then(|sessions| sessions.get_mut(&session_id))
+        {
+            Some(session) => session,
+            None => return false,
+        };
+
+        let subscription = (recipient_type.to_string(), reference_id);
+        if subscribed {
+            session.subscriptions.insert(subscription);
+        } else {
+            session.subscriptions.remove(&subscription);
+        }
+
+        true
+    }
+
+    /// Registers the session and, with `resume_from`, replays the events sent
+    /// to the user after it. Sends RESYNC_REQUIRED when some of them are no
+    /// longer in the log.
+    pub fn add_session(
+        &self,
+        user_id: Uuid,
+        organization_id: Uuid,
+        session_id: Uuid,
+        device: Option<String>,
+        resume_from: Option<u64>,
+        addr: Addr<MyWebSocket>,
+    ) {
         self.sessions.lock().unwrap().insert(addr.clone());
-        self.user_sessions.lock().unwrap().insert(user_id, addr);
 
-        let user_ids: Vec<Uuid> = self.user_sessions.lock().unwrap().keys().cloned().collect();
-        let update_message = MessageDTO {
-            message_type: "UPDATE_USERS".to_string(),
+        // Only the first session brings the user online
+        let came_online = {
+            let mut user_sessions = self.user_sessions.lock().unwrap();
+            let mut event_logs = self.event_logs.lock().unwrap();
+
+            event_logs.retain(|_, event_log| !event_log.is_expired());
+
+            let event_log = event_logs.entry(user_id).or_insert_with(EventLog::new);
+            event_log.disconnected_at = None;
+
+            let last_seq = event_log.next_seq - 1;
+
+            let connected_dto = ConnectedDTO {
+                message_type: "CONNECTED".to_string(),
+                session_id,
+                seq: last_seq,
+            };
+            addr.do_send(MyMessage(serde_json::to_string(&connected_dto).unwrap()));
+
+            if let Some(resume_from) = resume_from {
+                let first_seq = event_log
+                    .events
+                    .front()
+                    .map_or(event_log.next_seq, |(seq, _)| *seq);
+
+                if resume_from.saturating_add(1) < first_seq || resume_from > last_seq {
+                    let resync_dto = MessageDTO {
+                        message_type: "RESYNC_REQUIRED".to_string(),
+                    };
+                    addr.do_send(MyMessage(serde_json::to_string(&resync_dto).unwrap()));
+                } else {
+                    for (seq, event) in event_log
+                        .events
+                        .iter()
+                        .filter(|(seq, _)| *seq > resume_from)
+                    {
+                        addr.do_send(MyMessage(with_seq(*seq, event)));
+                    }
+                }
+            }
+
+            let sessions = user_sessions.entry(user_id).or_default();
+            sessions.insert(
+                session_id,
+                UserSession {
+                    addr,
+                    device,
+                    subscriptions: HashSet::new(),
+                },
+            );
+            let came_online = sessions.len() == 1;
+
+            if came_online {
+                self.organization_users
+                    .lock()
+                    .unwrap()
+                    .entry(organization_id)
+                    .or_default()
+                    .insert(user_id);
+            }
+
+            came_online
         };
 
-        self.send_message(&user_ids, &serde_json::to_string(&update_message).unwrap());
+        if came_online {
+            self.send_update_users(organization_id);
+        }
     }
 
-    pub fn remove_session(&self, user_id: Uuid, addr: &Addr<MyWebSocket>) {
+    pub fn remove_session(
+        &self,
+        user_id: Uuid,
+        organization_id: Uuid,
+        session_id: Uuid,
+        addr: &Addr<MyWebSocket>,
+    ) {
         self.sessions.lock().unwrap().remove(addr);
-        self.user_sessions.lock().unwrap().remove(&user_id);
 
-        let user_ids: Vec<Uuid> = self.user_sessions.lock().unwrap().keys().cloned().collect();
+        // The user stays online until their last session closes
+        let went_offline = {
+            let mut user_sessions = self.user_sessions.lock().unwrap();
+            match user_sessions.get_mut(&user_id) {
+                Some(sessions) => {
+                    sessions.remove(&session_id);
+
+                    if sessions.is_empty() {
+                        user_sessions.remove(&user_id);
+
+                        // The log is kept for a while so the user can resume
+                        let mut event_logs = self.event_logs.lock().unwrap();
+                        if let Some(event_log) = event_logs.get_mut(&user_id) {
+                            event_log.disconnected_at = Some(Instant::now());
+                        }
+                        event_logs.retain(|_, event_log| !event_log.is_expired());
+
+                        let mut organization_users = self.organization_users.lock().unwrap();
+                        if let Some(user_ids) = organization_users.get_mut(&organization_id) {
+                            user_ids.remove(&user_id);
+
+                            if user_ids.is_empty() {
+                                organization_users.remove(&organization_id);
+                            }
+                        }
+
+                        true
+                    } else {
+                        false
+                    }
+                }
+                None => false,
+            }
+        };
+
+        if went_offline {
+            self.send_update_users(organization_id);
+        }
+    }
+
+    fn send_update_users(&self, organization_id: Uuid) {
+        let user_ids = self.get_connected_user_ids(organization_id);
         let update_message = MessageDTO {
             message_type: "UPDATE_USERS".to_string(),
         };
@@ -56,8 +338,28 @@ impl ChatRoom {
         self.send_message(&user_ids, &serde_json::to_string(&update_message).unwrap());
     }
 
-    pub fn get_connected_user_ids(&self) -> Vec<Uuid> {
-        self.user_sessions.lock().unwrap().keys().cloned().collect()
+    pub fn get_connected_user_ids(&self, organization_id: Uuid) -> Vec<Uuid> {
+        self.organization_users
+            .lock()
+            .unwrap()
+            .get(&organization_id)
+            .map(|user_ids| user_ids.iter().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    /// Returns the id and device label of each of the user's open sessions.
+    pub fn get_user_sessions(&self, user_id: Uuid) -> Vec<(Uuid, Option<String>)> {
+        self.user_sessions
+            .lock()
+            .unwrap()
+            .get(&user_id)
+            .map(|sessions| {
+                sessions
+                    .iter()
+                    .map(|(session_id, session)| (*session_id, session.device.clone()))
+                    .collect()
+            })
+            .unwrap_or_default()
     }
 }
 
@@ -68,19 +370,59 @@ pub struct MyMessage(pub String);
 
 // WebSocket connection actor
 pub struct MyWebSocket {
-    pub room: Arc<ChatRoom>,
+    pub app_state: web::Data<AppState>,
+    pub room: web::Data<Arc<ChatRoom>>,
     pub user_id: Uuid,
+    pub organization_id: Uuid,
+    pub session_id: Uuid,
+    pub device: Option<String>,
+    pub resume_from: Option<u64>,
+    /// The `exp` of the token the socket was opened with.
+    pub token_expires_at: usize,
+}
+
+impl MyWebSocket {
+    fn token_expired(&self) -> bool {
+        Utc::now().timestamp() >= self.token_expires_at as i64
+    }
+
+    fn close_session(ctx: &mut ws::WebsocketContext<Self>, description: &str) {
+        ctx.close(Some(ws::CloseReason {
+            code: ws::CloseCode::Policy,
+            description: Some(description.to_string()),
+        }));
+        ctx.stop();
+    }
 }
 
 impl Actor for MyWebSocket {
     type Context = ws::WebsocketContext<Self>;
 
     fn started(&mut self, ctx: &mut Self::Context) {
-        self.room.add_session(self.user_id, ctx.address());
+        self.room.add_session(
+            self.user_id,
+            self.organization_id,
+            self.session_id,
+            self.device.clone(),
+            self.resume_from,
+            ctx.address(),
+        );
+
+        // The session ends with its token, the client reconnects with a new one
+        let expires_in = (self.token_expires_at as i64 - Utc::now().timestamp()).max(0) as u64;
+
+        ctx.run_later(Duration::from_secs(expires_in), |_, ctx| {
+            Self::close_session(ctx, "Token expired");
+        });
     }
 
     fn stopped(&mut self, ctx: &mut Self::Context) {
-        self.room.remove_session(self.user_id, &ctx.address());
+        self.room.remove_session(
+            self.user_id,
+            self.organization_id,
+            self.session_id,
+            &ctx.address(),
+        );
     }
 }
 
@@ -101,6 +443,30 @@ impl StreamHandler<Result<ws::Message, ws::ProtocolError>> for MyWebSocket {
                 ctx.pong(&msg);
             }
             Ok(ws::Message::Pong(_)) => {}
+            Ok(ws::Message::Text(text)) => {
+                if self.token_expired() {
+                    Self::close_session(ctx, "Token expired");
+                    return;
+                }
+
+                let app_state = self.app_state.clone();
+                let room = self.room.clone();
+                let user_id = self.user_id;
+                let session_id = self.session_id;
+
+                // Commands run one at a time in the order they arrived. The actor
+                // waits on each, which also stops reading frames until it's done
+                ctx.wait(
+                    async move {
+                        handle_socket_command(&app_state, &room, user_id, session_id, &text).await
+                    }
+                    .into_actor(self)
+                    .map(|response, _, ctx| match response {
+                        Some(response) => ctx.text(response),
+                        None => Self::close_session(ctx, "User not found"),
+                    }),
+                );
+            }
             Ok(ws::Message::Binary(_)) => {}
             Ok(ws::Message::Close(_)) => {
                 ctx.stop();
@@ -0,0 +1,122 @@
+use entity::{channel, role, user};
+use serde::Serialize;
+use uuid::Uuid;
+
+/// Bumped whenever a variant's fields change in a way older clients can't read.
+pub(crate) const EVENT_SCHEMA_VERSION: u32 = 1;
+
+/// Changes to organization data pushed over the WebSocket. Each event carries
+/// what changed so clients can patch their state instead of refetching it.
+///
+/// Serialized as `{"message_type": "CHANNEL_UPDATED", "version": 1, ...}`
+/// with the variant's fields alongside.
+#[derive(Serialize)]
+#[serde(tag = "message_type", rename_all = "SCREAMING_SNAKE_CASE")]
+pub(crate) enum ChatEvent {
+    /// A channel was created. Sent to everyone who can read it.
+    ChannelCreated { channel: ChannelEventDTO },
+    /// A channel's name, description or message settings changed.
+    ChannelUpdated { channel: ChannelEventDTO },
+    /// A channel was deleted along with its role access.
+    ChannelDeleted { channel_id: Uuid },
+    /// Members of the role can now see the channel.
+    ChannelAccessGranted { channel_id: Uuid, role_id: Uuid },
+    /// Members of the role can no longer see the channel through it.
+    ChannelAccessRevoked { channel_id: Uuid, role_id: Uuid },
+    /// A role's name or permissions changed.
+    RoleUpdated { role: RoleEventDTO },
+    /// A role was deleted and taken away from everyone who held it.
+    RoleDeleted { role_id: Uuid },
+    /// The user was given the role.
+    RoleGranted { user_id: Uuid, role_id: Uuid },
+    /// The role was taken away from the user.
+    RoleRevoked { user_id: Uuid, role_id: Uuid },
+    /// A user's display name changed.
+    UserRenamed { user: UserEventDTO },
+    /// A user's profile image changed.
+    UserUpdated { user: UserEventDTO },
+}
+
+#[derive(Serialize)]
+struct VersionedEventDTO<'a> {
+    version: u32,
+    #[serde(flatten)]
+    event: &'a ChatEvent,
+}
+
+impl ChatEvent {
+    pub(crate) fn to_json(&self) -> String {
+        serde_json::to_string(&VersionedEventDTO {
+            version: EVENT_SCHEMA_VERSION,
+            event: self,
+        })
+        .unwrap()
+    }
+}
+
+#[derive(Serialize)]
+pub(crate) struct ChannelEventDTO {
+    id: Uuid,
+    name: String,
+    description: Option<String>,
+    message_ttl_seconds: Option<i64>,
+    message_retention_days: Option<i64>,
+}
+
+impl From<channel::Model> for ChannelEventDTO {
+    fn from(model: channel::Model) -> Self {
+        Self {
+            id: model.id,
+            name: model.name,
+            description: model.description,
+            message_ttl_seconds: model.message_ttl_seconds,
+            message_retention_days: model.message_retention_days,
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub(crate) struct RoleEventDTO {
+    id: Uuid,
+    name: String,
+    administrator: bool,
+    manage_users: bool,
+    manage_channels: bool,
+    manage_roles: bool,
+    pin_messages: bool,
+    mention_everyone: bool,
+}
+
+impl From<role::Model> for RoleEventDTO {
+    fn from(model: role::Model) -> Self {
+        Self {
+            id: model.id,
+            name: model.name,
+            administrator: model.administrator,
+            manage_users: model.manage_users,
+            manage_channels: model.manage_channels,
+            manage_roles: model.manage_roles,
+            pin_messages: model.pin_messages,
+            mention_everyone: model.mention_everyone,
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub(crate) struct UserEventDTO {
+    id: Uuid,
+    username: String,
+    display_name: String,
+    profile_image: Option<String>,
+}
+
+impl From<user::Model> for UserEventDTO {
+    fn from(model: user::Model) -> Self {
+        Self {
+            id: model.id,
+            username: model.username,
+            display_name: model.display_name,
+            profile_image: model.profile_image,
+        }
+    }
+}
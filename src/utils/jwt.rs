@@ -71,11 +71,11 @@ pub fn get_user_id_from_http_request(req: HttpRequest) -> Result<Uuid, ApiRespon
     Ok(user_id)
 }
 
-pub fn get_user_id_from_token(token: String) -> Result<Uuid, ApiResponse> {
-    let user_id =
-        get_id_from_jwt(token).map_err(|_| ApiResponse::new(401, "Unauthorized".to_string()))?;
+pub fn get_claims_from_token(token: String) -> Result<Claims, ApiResponse> {
+    let claim_data =
+        decode_jwt(token).map_err(|_| ApiResponse::new(401, "Unauthorized".to_string()))?;
 
-    Ok(user_id)
+    Ok(claim_data.claims)
 }
 
 pub async fn get_client_secret_from_request(req: &HttpRequest) -> Result<String, ApiResponse> {
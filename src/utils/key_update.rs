@@ -24,7 +24,6 @@ pub async fn update_profile_key(
             id: Set(Uuid::new_v4()),
             user_id: Set(user_id),
             key: Set(key),
-            ..Default::default()
         };
         new_key.insert(db).await?;
     }
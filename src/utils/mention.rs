@@ -0,0 +1,199 @@
+use crate::utils::api_response::ApiResponse;
+use crate::utils::permissions::{check_permission_by_user_id, Permission};
+use entity::{message, message_mention, role, user_role_access};
+use sea_orm::ActiveValue::Set;
+use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter};
+use std::collections::{HashMap, HashSet};
+use uuid::Uuid;
+
+const CHANNEL_MENTIONS: [&str; 2] = ["@channel", "@everyone"];
+
+#[derive(Default)]
+pub struct ParsedMentions {
+    pub user_ids: HashSet<Uuid>,
+    pub role_ids: HashSet<Uuid>,
+    pub channel: bool,
+}
+
+/// Finds `<@user_id>`, `<@&role_id>`, `@channel` and `@everyone` in message content.
+pub fn parse_mentions(content: &str) -> ParsedMentions {
+    let mut mentions = ParsedMentions::default();
+
+    for (index, _) in content.match_indices("<@") {
+        let rest = &content[index + 2..];
+
+        let Some(end) = rest.find('>') else {
+            continue;
+        };
+
+        let (is_role, id) = match rest[..end].strip_prefix('&') {
+            Some(id) => (true, id),
+            None => (false, &rest[..end]),
+        };
+
+        if let Ok(id) = id.parse::<Uuid>() {
+            if is_role {
+                mentions.role_ids.insert(id);
+            } else {
+                mentions.user_ids.insert(id);
+            }
+        }
+    }
+
+    mentions.channel = content.split_whitespace().any(|word| {
+        CHANNEL_MENTIONS
+            .iter()
+            .any(|mention| word.trim_end_matches(|c: char| c.is_ascii_punctuation()) == *mention)
+    });
+
+    mentions
+}
+
+/// Resolves the mentions in a message to the users who should be notified,
+/// keyed by user id with the most specific mention type (USER, ROLE, CHANNEL).
+/// Only users in `reader_ids` can be mentioned. Role and channel-wide mentions
+/// are dropped unless the sender has the MentionEveryone permission.
+pub async fn get_mentioned_users(
+    db: &DatabaseConnection,
+    user_id: Uuid,
+    message_model: &message::Model,
+    organization_id: Uuid,
+    reader_ids: &[Uuid],
+) -> Result<HashMap<Uuid, String>, ApiResponse> {
+    let mentions = parse_mentions(message_model.content.as_deref().unwrap_or_default());
+
+    let readers: HashSet<Uuid> = reader_ids
+        .iter()
+        .copied()
+        .filter(|reader_id| *reader_id != message_model.user_id)
+        .collect();
+
+    let mut mentioned_users: HashMap<Uuid, String> = HashMap::new();
+
+    let can_mention_everyone = (mentions.channel || !mentions.role_ids.is_empty())
+        && message_model.recipient_type == "CHANNEL"
+        && check_permission_by_user_id(db, user_id, Permission::MentionEveryone).await;
+
+    if can_mention_everyone && mentions.channel {
+        for reader_id in readers.iter() {
+            mentioned_users.insert(*reader_id, "CHANNEL".to_string());
+        }
+    }
+
+    if can_mention_everyone && !mentions.role_ids.is_empty() {
+        let role_ids: Vec<Uuid> = role::Entity::find()
+            .filter(role::Column::Id.is_in(mentions.role_ids))
+            .filter(role::Column::OrganizationId.eq(organization_id))
+            .filter(role::Column::Deleted.eq(false))
+            .all(db)
+            .await
+            .map_err(|e| ApiResponse::new(500, e.to_string()))?
+            .into_iter()
+            .map(|role| role.id)
+            .collect();
+
+        let role_user_ids = user_role_access::Entity::find()
+            .filter(user_role_access::Column::RoleId.is_in(role_ids))
+            .filter(user_role_access::Column::Deleted.eq(false))
+            .all(db)
+            .await
+            .map_err(|e| ApiResponse::new(500, e.to_string()))?
+            .into_iter()
+            .map(|user_role_access| user_role_access.user_id)
+            .filter(|user_id| readers.contains(user_id));
+
+        for user_id in role_user_ids {
+            mentioned_users.insert(user_id, "ROLE".to_string());
+        }
+    }
+
+    for user_id in mentions.user_ids {
+        if readers.contains(&user_id) {
+            mentioned_users.insert(user_id, "USER".to_string());
+        }
+    }
+
+    Ok(mentioned_users)
+}
+
+pub async fn save_mentions(
+    db: &DatabaseConnection,
+    message_id: Uuid,
+    mentioned_users: &HashMap<Uuid, String>,
+) -> Result<(), ApiResponse> {
+    if mentioned_users.is_empty() {
+        return Ok(());
+    }
+
+    let mention_models =
+        mentioned_users
+            .iter()
+            .map(|(user_id, mention_type)| message_mention::ActiveModel {
+                id: Set(Uuid::new_v4()),
+                message_id: Set(message_id),
+                user_id: Set(*user_id),
+                mention_type: Set(mention_type.clone()),
+            });
+
+    message_mention::Entity::insert_many(mention_models)
+        .exec(db)
+        .await
+        .map_err(|e| ApiResponse::new(500, e.to_string()))?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const USER_ID: &str = "8d3c5b1e-2f4a-4c6b-9e7d-1a2b3c4d5e6f";
+    const ROLE_ID: &str = "0f9e8d7c-6b5a-4938-8271-605f4e3d2c1b";
+
+    #[test]
+    fn parses_empty_content() {
+        let mentions = parse_mentions("");
+
+        assert!(mentions.user_ids.is_empty());
+        assert!(mentions.role_ids.is_empty());
+        assert!(!mentions.channel);
+    }
+
+    #[test]
+    fn parses_user_and_role_mentions() {
+        let mentions = parse_mentions(&format!(
+            "<@{USER_ID}> and <@&{ROLE_ID}>, again <@{USER_ID}>"
+        ));
+
+        assert_eq!(mentions.user_ids, HashSet::from([USER_ID.parse().unwrap()]));
+        assert_eq!(mentions.role_ids, HashSet::from([ROLE_ID.parse().unwrap()]));
+        assert!(!mentions.channel);
+    }
+
+    #[test]
+    fn parses_mentions_between_multi_byte_text() {
+        let mentions = parse_mentions(&format!("héllo ☕<@{USER_ID}>ü <@<@&{ROLE_ID}>"));
+
+        assert_eq!(mentions.user_ids, HashSet::from([USER_ID.parse().unwrap()]));
+        assert_eq!(mentions.role_ids, HashSet::from([ROLE_ID.parse().unwrap()]));
+    }
+
+    #[test]
+    fn ignores_malformed_mentions() {
+        let mentions = parse_mentions(&format!("<@not-an-id> <@&> <@{USER_ID}"));
+
+        assert!(mentions.user_ids.is_empty());
+        assert!(mentions.role_ids.is_empty());
+    }
+
+    #[test]
+    fn parses_channel_mentions() {
+        assert!(parse_mentions("@channel").channel);
+        assert!(parse_mentions("heads up @everyone!").channel);
+        assert!(parse_mentions("ping @channel, please").channel);
+
+        assert!(!parse_mentions("@channels").channel);
+        assert!(!parse_mentions("team@everyone").channel);
+        assert!(!parse_mentions("@Channel").channel);
+    }
+}
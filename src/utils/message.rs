@@ -1,23 +1,33 @@
 use crate::utils::app_state;
 use crate::utils::chat::ChatRoom;
+use crate::utils::event::ChatEvent;
 use actix_web::web::Data;
 use sea_orm::{ColumnTrait, EntityTrait, QueryFilter};
-use serde::{Deserialize, Serialize};
 use std::collections::HashSet;
 use std::sync::Arc;
 use uuid::Uuid;
 
-#[derive(Serialize, Deserialize)]
-struct MessageDTO {
-    message_type: String,
-}
-
-pub async fn send_update_status_from_role_id_and_org_id(
+/// Sends the event to the role's members and to everyone who manages roles.
+pub async fn send_event_from_role_id_and_org_id(
+    event: &ChatEvent,
     role_id: Uuid,
     organization_id: Uuid,
     app_state: &Data<app_state::AppState>,
     chat_room: &Data<Arc<ChatRoom>>,
 ) {
+    let user_ids = get_user_ids_from_role_id_and_org_id(role_id, organization_id, app_state).await;
+
+    chat_room.send_message(
+        &user_ids.into_iter().collect::<Vec<Uuid>>(),
+        &event.to_json(),
+    );
+}
+
+pub async fn get_user_ids_from_role_id_and_org_id(
+    role_id: Uuid,
+    organization_id: Uuid,
+    app_state: &Data<app_state::AppState>,
+) -> HashSet<Uuid> {
     let user_role_accesses = entity::user_role_access::Entity::find()
         .filter(entity::user_role_access::Column::RoleId.eq(role_id))
         .all(&app_state.db)
@@ -56,21 +66,38 @@ pub async fn send_update_status_from_role_id_and_org_id(
         user_ids.insert(user_role_access.user_id);
     }
 
-    let update_message_dto = MessageDTO {
-        message_type: "UPDATE_STATUS".to_string(),
-    };
+    user_ids
+}
+
+/// Sends the event to everyone who can read the channel.
+pub async fn send_event_from_channel_id(
+    event: &ChatEvent,
+    channel_id: Uuid,
+    app_state: &Data<app_state::AppState>,
+    chat_room: &Data<Arc<ChatRoom>>,
+) {
+    let user_ids = get_user_ids_from_channel_id(channel_id, app_state).await;
 
     chat_room.send_message(
         &user_ids.into_iter().collect::<Vec<Uuid>>(),
-        &serde_json::to_string(&update_message_dto).unwrap(),
+        &event.to_json(),
     );
 }
 
-pub async fn send_update_status_from_channel_id(
+pub async fn get_user_ids_from_channel_id(
     channel_id: Uuid,
     app_state: &Data<app_state::AppState>,
-    chat_room: &Data<Arc<ChatRoom>>,
-) {
+) -> HashSet<Uuid> {
+    let mut user_ids: HashSet<Uuid> = HashSet::new();
+
+    let Some(channel_model) = entity::channel::Entity::find_by_id(channel_id)
+        .one(&app_state.db)
+        .await
+        .unwrap()
+    else {
+        return user_ids;
+    };
+
     let channel_role_accesses = entity::channel_role_access::Entity::find()
         .filter(entity::channel_role_access::Column::ChannelId.eq(channel_id))
         .filter(entity::channel_role_access::Column::Deleted.eq(false))
@@ -78,8 +105,6 @@ pub async fn send_update_status_from_channel_id(
         .await
         .unwrap();
 
-    let mut user_ids: HashSet<Uuid> = HashSet::new();
-
     for channel_role_access in channel_role_accesses {
         let user_role_accesses = entity::user_role_access::Entity::find()
             .filter(entity::user_role_access::Column::RoleId.eq(channel_role_access.role_id))
@@ -99,6 +124,7 @@ pub async fn send_update_status_from_channel_id(
                 .eq(true)
                 .or(entity::role::Column::ManageChannels.eq(true)),
         )
+        .filter(entity::role::Column::OrganizationId.eq(channel_model.organization_id))
         .all(&app_state.db)
         .await
         .unwrap();
@@ -119,17 +145,12 @@ pub async fn send_update_status_from_channel_id(
         user_ids.insert(user_role_access.user_id);
     }
 
-    let update_message_dto = MessageDTO {
-        message_type: "UPDATE_STATUS".to_string(),
-    };
-
-    chat_room.send_message(
-        &user_ids.into_iter().collect::<Vec<Uuid>>(),
-        &serde_json::to_string(&update_message_dto).unwrap(),
-    );
+    user_ids
 }
 
-pub async fn send_update_status_to_all_users(
+/// Sends the event to every user in the organization.
+pub async fn send_event_to_all_users(
+    event: &ChatEvent,
     organization_id: Uuid,
     app_state: &Data<app_state::AppState>,
     chat_room: &Data<Arc<ChatRoom>>,
@@ -143,12 +164,8 @@ pub async fn send_update_status_to_all_users(
 
     let user_ids: Vec<Uuid> = users.into_iter().map(|user| user.id).collect();
 
-    let update_message_dto = MessageDTO {
-        message_type: "UPDATE_STATUS".to_string(),
-    };
-
     chat_room.send_message(
         &user_ids.into_iter().collect::<Vec<Uuid>>(),
-        &serde_json::to_string(&update_message_dto).unwrap(),
+        &event.to_json(),
     );
 }
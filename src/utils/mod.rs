@@ -2,12 +2,20 @@ pub mod api_response;
 pub mod app_state;
 pub(crate) mod chat;
 pub(crate) mod constants;
+pub(crate) mod event;
 pub(crate) mod flag;
 pub mod jwt;
 pub mod key_update;
 pub(crate) mod logging;
+pub(crate) mod mention;
 pub(crate) mod message;
 pub mod organization_util;
 pub mod permissions;
+pub(crate) mod retention;
 pub mod s3;
+pub(crate) mod scheduler;
+pub(crate) mod search_query;
 pub(crate) mod seed;
+pub(crate) mod serde_util;
+pub(crate) mod socket_command;
+pub mod webhook;
@@ -1,8 +1,8 @@
 use crate::utils::constants;
 use crate::utils::jwt::get_user_id_from_http_request;
 use actix_web::HttpRequest;
-use entity::{channel_role_access, role, user, user_role_access};
-use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter};
+use entity::{channel, channel_role_access, role, user, user_role_access};
+use sea_orm::{ColumnTrait, Condition, DatabaseConnection, EntityTrait, QueryFilter};
 use uuid::Uuid;
 
 #[derive(Clone, Copy)]
@@ -11,6 +11,8 @@ pub enum Permission {
     ManageChannels,
     ManageRoles,
     ManageUsers,
+    PinMessages,
+    MentionEveryone,
 }
 
 #[derive(Clone, Copy)]
@@ -35,7 +37,17 @@ pub async fn check_permission(
         None => return false,
     };
 
-    let user_role_accesses = match get_user_role_accesses(db, user.id).await {
+    check_permission_by_user_id(db, user.id, permission).await
+}
+
+/// Same as `check_permission` for work that isn't tied to a request, such as
+/// background jobs acting on behalf of a user.
+pub async fn check_permission_by_user_id(
+    db: &DatabaseConnection,
+    user_id: Uuid,
+    permission: Permission,
+) -> bool {
+    let user_role_accesses = match get_user_role_accesses(db, user_id).await {
         Some(accesses) => accesses,
         None => return false,
     };
@@ -66,40 +78,33 @@ pub async fn check_chat_permission(
 ) -> bool {
     let user_id = get_user_id_from_http_request(req.clone()).unwrap();
 
-    let user_organization_id = match user::Entity::find()
-        .filter(user::Column::Id.eq(user_id))
-        .one(db)
-        .await
-    {
-        Ok(Some(user)) => user.organization_id,
-        _ => return false,
-    };
-
-    let channel = match entity::channel::Entity::find()
-        .filter(entity::channel::Column::Id.eq(channel_id))
-        .one(db)
-        .await
-    {
-        Ok(Some(channel)) => channel,
-        _ => return false,
-    };
-
-    if channel.organization_id != user_organization_id {
-        return false;
-    }
-
     let has_client_secret = check_client_secret(req.clone());
 
     if has_client_secret {
-        return true;
+        return get_channel_in_user_organization(db, user_id, channel_id)
+            .await
+            .is_some();
     }
 
-    let user = match get_user_by_request(db, &req).await {
-        Some(user) => user,
-        None => return false,
-    };
+    check_chat_permission_by_user_id(db, user_id, permission, channel_id).await
+}
 
-    let user_role_accesses = match get_user_role_accesses(db, user.id).await {
+/// Same as `check_chat_permission` for work that isn't tied to a request, such as
+/// background jobs acting on behalf of a user.
+pub async fn check_chat_permission_by_user_id(
+    db: &DatabaseConnection,
+    user_id: Uuid,
+    permission: ChatPermission,
+    channel_id: Uuid,
+) -> bool {
+    if get_channel_in_user_organization(db, user_id, channel_id)
+        .await
+        .is_none()
+    {
+        return false;
+    }
+
+    let user_role_accesses = match get_user_role_accesses(db, user_id).await {
         Some(accesses) => accesses,
         None => return false,
     };
@@ -127,6 +132,96 @@ pub async fn check_chat_permission(
     false
 }
 
+async fn get_channel_in_user_organization(
+    db: &DatabaseConnection,
+    user_id: Uuid,
+    channel_id: Uuid,
+) -> Option<channel::Model> {
+    let user_organization_id = match user::Entity::find()
+        .filter(user::Column::Id.eq(user_id))
+        .one(db)
+        .await
+    {
+        Ok(Some(user)) => user.organization_id,
+        _ => return None,
+    };
+
+    match channel::Entity::find()
+        .filter(channel::Column::Id.eq(channel_id))
+        .one(db)
+        .await
+    {
+        Ok(Some(channel)) if channel.organization_id == user_organization_id => Some(channel),
+        _ => None,
+    }
+}
+
+/// Returns every channel the requesting user passes `check_chat_permission` for,
+/// resolved in bulk so that cross-channel queries don't check one channel at a time.
+pub async fn get_chat_permission_channel_ids(
+    db: &DatabaseConnection,
+    req: HttpRequest,
+    permission: ChatPermission,
+) -> Vec<Uuid> {
+    let user = match get_user_by_request(db, &req).await {
+        Some(user) => user,
+        None => return vec![],
+    };
+
+    let organization_channel_ids: Vec<Uuid> = match channel::Entity::find()
+        .filter(channel::Column::OrganizationId.eq(user.organization_id))
+        .all(db)
+        .await
+    {
+        Ok(channels) => channels.into_iter().map(|channel| channel.id).collect(),
+        Err(_) => return vec![],
+    };
+
+    if check_client_secret(req.clone()) {
+        return organization_channel_ids;
+    }
+
+    let user_role_accesses = match get_user_role_accesses(db, user.id).await {
+        Some(accesses) => accesses,
+        None => return vec![],
+    };
+
+    let role_ids: Vec<Uuid> = user_role_accesses
+        .into_iter()
+        .map(|access| access.role_id)
+        .collect();
+
+    let roles = role::Entity::find()
+        .filter(role::Column::Id.is_in(role_ids.clone()))
+        .all(db)
+        .await
+        .unwrap_or_default();
+
+    if roles.iter().any(|role| role.administrator) {
+        return organization_channel_ids;
+    }
+
+    let permission_condition = match permission {
+        ChatPermission::CanRead => Condition::any()
+            .add(channel_role_access::Column::CanRead.eq(true))
+            .add(channel_role_access::Column::CanWrite.eq(true)),
+        ChatPermission::CanWrite => {
+            Condition::all().add(channel_role_access::Column::CanWrite.eq(true))
+        }
+    };
+
+    channel_role_access::Entity::find()
+        .filter(channel_role_access::Column::RoleId.is_in(role_ids))
+        .filter(channel_role_access::Column::ChannelId.is_in(organization_channel_ids))
+        .filter(permission_condition)
+        .all(db)
+        .await
+        .unwrap_or_default()
+        .into_iter()
+        .map(|access| access.channel_id)
+        .collect()
+}
+
 fn check_client_secret(req: HttpRequest) -> bool {
     let secret = (*constants::CLIENT_SECRET).clone();
 
@@ -158,14 +253,11 @@ async fn get_user_role_accesses(
     db: &DatabaseConnection,
     user_id: Uuid,
 ) -> Option<Vec<user_role_access::Model>> {
-    match user_role_access::Entity::find()
+    user_role_access::Entity::find()
         .filter(user_role_access::Column::UserId.eq(user_id))
         .all(db)
         .await
-    {
-        Ok(accesses) => Some(accesses),
-        _ => None,
-    }
+        .ok()
 }
 
 async fn get_role_by_id(db: &DatabaseConnection, role_id: Uuid) -> Option<role::Model> {
@@ -201,6 +293,8 @@ fn match_permission(permission: Permission, role: &role::Model) -> bool {
         Permission::ManageChannels => role.manage_channels,
         Permission::ManageRoles => role.manage_roles,
         Permission::ManageUsers => role.manage_users,
+        Permission::PinMessages => role.pin_messages,
+        Permission::MentionEveryone => role.mention_everyone,
     }
 }
 
@@ -0,0 +1,271 @@
+use crate::routes::handlers::message_handler::purge_messages;
+use crate::utils::api_response::ApiResponse;
+use crate::utils::app_state::AppState;
+use crate::utils::s3;
+use actix_web::web;
+use chrono::{NaiveDateTime, Utc};
+use entity::{
+    channel, conversation, message, organization, retention_action, user, user_channel_view,
+};
+use sea_orm::sea_query::{Query, SelectStatement};
+use sea_orm::ActiveValue::Set;
+use sea_orm::{ActiveModelTrait, ColumnTrait, Condition, EntityTrait, QueryFilter, QuerySelect};
+use uuid::Uuid;
+
+const RETENTION_BATCH_SIZE: u64 = 1000;
+
+/// Longest retention an organization or channel can set, about 100 years.
+pub(crate) const MAX_RETENTION_DAYS: i64 = 36500;
+
+struct RetentionScope {
+    organization_id: Uuid,
+    recipient_type: &'static str,
+    reference_id: Option<Uuid>,
+    cutoff: NaiveDateTime,
+    message_condition: Condition,
+    user_channel_view_condition: Condition,
+}
+
+#[derive(Default)]
+struct RetentionCounts {
+    messages_deleted: u64,
+    media_deleted: u64,
+    user_channel_views_deleted: u64,
+}
+
+/// Purges everything older than each organization's retention settings. A
+/// channel's own setting overrides the organization's channel setting, and DMs
+/// and group conversations share the organization's DM setting. Every scope
+/// that had something removed is recorded as a `retention_action`. Thread
+/// roots stay until every reply in the thread is past the cutoff.
+pub async fn enforce_retention_policies(
+    app_state: &web::Data<AppState>,
+    s3_client: &web::Data<s3::Client>,
+) -> Result<(), ApiResponse> {
+    let organizations = organization::Entity::find()
+        .all(&app_state.db)
+        .await
+        .map_err(|e| ApiResponse::new(500, e.to_string()))?;
+
+    // One organization failing shouldn't hold back the others
+    for organization_model in organizations {
+        let organization_id = organization_model.id;
+
+        if let Err(e) =
+            enforce_organization_retention(app_state, s3_client, organization_model).await
+        {
+            log::error!(
+                "Failed to enforce retention in organization {}: {}",
+                organization_id,
+                e.body
+            );
+        }
+    }
+
+    Ok(())
+}
+
+async fn enforce_organization_retention(
+    app_state: &web::Data<AppState>,
+    s3_client: &web::Data<s3::Client>,
+    organization_model: organization::Model,
+) -> Result<(), ApiResponse> {
+    let channels = channel::Entity::find()
+        .filter(channel::Column::OrganizationId.eq(organization_model.id))
+        .all(&app_state.db)
+        .await
+        .map_err(|e| ApiResponse::new(500, e.to_string()))?;
+
+    for channel_model in channels {
+        let Some(retention_days) = channel_model
+            .message_retention_days
+            .or(organization_model.message_retention_days)
+        else {
+            continue;
+        };
+
+        let cutoff = get_cutoff(retention_days)?;
+
+        let retention_scope = RetentionScope {
+            organization_id: organization_model.id,
+            recipient_type: "CHANNEL",
+            reference_id: Some(channel_model.id),
+            cutoff,
+            message_condition: Condition::all()
+                .add(message::Column::RecipientType.eq("CHANNEL"))
+                .add(message::Column::ReferenceId.eq(channel_model.id)),
+            user_channel_view_condition: Condition::all()
+                .add(user_channel_view::Column::RecipientType.eq("CHANNEL"))
+                .add(user_channel_view::Column::ReferenceId.eq(channel_model.id)),
+        };
+
+        enforce_retention(app_state, s3_client, retention_scope).await?;
+    }
+
+    let Some(dm_retention_days) = organization_model.dm_retention_days else {
+        return Ok(());
+    };
+
+    let cutoff = get_cutoff(dm_retention_days)?;
+
+    let user_retention_scope = RetentionScope {
+        organization_id: organization_model.id,
+        recipient_type: "USER",
+        reference_id: None,
+        cutoff,
+        message_condition: Condition::all()
+            .add(message::Column::RecipientType.eq("USER"))
+            .add(message::Column::UserId.in_subquery(get_user_ids_query(organization_model.id))),
+        user_channel_view_condition: Condition::all()
+            .add(user_channel_view::Column::RecipientType.eq("USER"))
+            .add(
+                user_channel_view::Column::UserId
+                    .in_subquery(get_user_ids_query(organization_model.id)),
+            ),
+    };
+
+    enforce_retention(app_state, s3_client, user_retention_scope).await?;
+
+    let conversation_retention_scope = RetentionScope {
+        organization_id: organization_model.id,
+        recipient_type: "CONVERSATION",
+        reference_id: None,
+        cutoff,
+        message_condition: Condition::all()
+            .add(message::Column::RecipientType.eq("CONVERSATION"))
+            .add(
+                message::Column::ReferenceId
+                    .in_subquery(get_conversation_ids_query(organization_model.id)),
+            ),
+        user_channel_view_condition: Condition::all()
+            .add(user_channel_view::Column::RecipientType.eq("CONVERSATION"))
+            .add(
+                user_channel_view::Column::ReferenceId
+                    .in_subquery(get_conversation_ids_query(organization_model.id)),
+            ),
+    };
+
+    enforce_retention(app_state, s3_client, conversation_retention_scope).await?;
+
+    Ok(())
+}
+
+async fn enforce_retention(
+    app_state: &web::Data<AppState>,
+    s3_client: &web::Data<s3::Client>,
+    retention_scope: RetentionScope,
+) -> Result<(), ApiResponse> {
+    let RetentionScope {
+        organization_id,
+        recipient_type,
+        reference_id,
+        cutoff,
+        message_condition,
+        user_channel_view_condition,
+    } = retention_scope;
+
+    let mut retention_counts = RetentionCounts::default();
+
+    loop {
+        let message_ids: Vec<Uuid> = message::Entity::find()
+            .select_only()
+            .column(message::Column::Id)
+            .filter(message_condition.clone())
+            .filter(message::Column::DateCreated.lt(cutoff))
+            // Purging a thread root takes its replies with it, so a root is kept
+            // until its newest reply is past the cutoff too
+            .filter(message::Column::Id.not_in_subquery(get_recent_parent_ids_query(cutoff)))
+            .limit(RETENTION_BATCH_SIZE)
+            .into_tuple()
+            .all(&app_state.db)
+            .await
+            .map_err(|e| ApiResponse::new(500, e.to_string()))?;
+
+        if message_ids.is_empty() {
+            break;
+        }
+
+        let purged_messages = purge_messages(app_state, s3_client, message_ids).await?;
+
+        retention_counts.messages_deleted += purged_messages.messages.len() as u64;
+        retention_counts.media_deleted += purged_messages.media_deleted;
+    }
+
+    // Views last touched before the cutoff only point at history that is gone,
+    // unless they still hold an active mute
+    retention_counts.user_channel_views_deleted = user_channel_view::Entity::delete_many()
+        .filter(user_channel_view_condition)
+        .filter(user_channel_view::Column::LastViewed.lt(cutoff))
+        .filter(
+            Condition::any()
+                .add(user_channel_view::Column::MutedUntil.is_null())
+                .add(user_channel_view::Column::MutedUntil.lt(Utc::now().naive_utc())),
+        )
+        .exec(&app_state.db)
+        .await
+        .map_err(|e| ApiResponse::new(500, e.to_string()))?
+        .rows_affected;
+
+    if retention_counts.messages_deleted == 0 && retention_counts.user_channel_views_deleted == 0 {
+        return Ok(());
+    }
+
+    retention_action::ActiveModel {
+        id: Set(Uuid::new_v4()),
+        organization_id: Set(organization_id),
+        recipient_type: Set(recipient_type.to_string()),
+        reference_id: Set(reference_id),
+        cutoff: Set(cutoff),
+        messages_deleted: Set(retention_counts.messages_deleted as i64),
+        media_deleted: Set(retention_counts.media_deleted as i64),
+        user_channel_views_deleted: Set(retention_counts.user_channel_views_deleted as i64),
+        date_created: Set(Utc::now().naive_utc()),
+    }
+    .insert(&app_state.db)
+    .await
+    .map_err(|e| ApiResponse::new(500, e.to_string()))?;
+
+    log::info!(
+        "Retention removed {} messages from {} {} in organization {}",
+        retention_counts.messages_deleted,
+        recipient_type,
+        reference_id.map(|id| id.to_string()).unwrap_or_default(),
+        organization_id
+    );
+
+    Ok(())
+}
+
+fn get_cutoff(retention_days: i64) -> Result<NaiveDateTime, ApiResponse> {
+    chrono::TimeDelta::try_days(retention_days)
+        .and_then(|retention| Utc::now().naive_utc().checked_sub_signed(retention))
+        .ok_or(ApiResponse::new(
+            500,
+            format!("Retention of {} days is out of range", retention_days),
+        ))
+}
+
+fn get_recent_parent_ids_query(cutoff: NaiveDateTime) -> SelectStatement {
+    Query::select()
+        .column(message::Column::ParentId)
+        .from(message::Entity)
+        .and_where(message::Column::ParentId.is_not_null())
+        .and_where(message::Column::DateCreated.gte(cutoff))
+        .to_owned()
+}
+
+fn get_user_ids_query(organization_id: Uuid) -> SelectStatement {
+    Query::select()
+        .column(user::Column::Id)
+        .from(user::Entity)
+        .and_where(user::Column::OrganizationId.eq(organization_id))
+        .to_owned()
+}
+
+fn get_conversation_ids_query(organization_id: Uuid) -> SelectStatement {
+    Query::select()
+        .column(conversation::Column::Id)
+        .from(conversation::Entity)
+        .and_where(conversation::Column::OrganizationId.eq(organization_id))
+        .to_owned()
+}
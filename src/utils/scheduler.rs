@@ -0,0 +1,54 @@
+use crate::routes::handlers::message_handler::delete_expired_messages;
+use crate::routes::handlers::scheduled_message_handler::publish_due_scheduled_messages;
+use crate::utils::app_state::AppState;
+use crate::utils::chat::ChatRoom;
+use crate::utils::retention::enforce_retention_policies;
+use crate::utils::s3;
+use actix_web::web;
+use log::error;
+use std::sync::Arc;
+use std::time::Duration;
+
+const SCHEDULER_INTERVAL: Duration = Duration::from_secs(10);
+const RETENTION_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// Runs the periodic background jobs for the lifetime of the server.
+pub fn start_scheduler(
+    app_state: web::Data<AppState>,
+    chat_room: web::Data<Arc<ChatRoom>>,
+    s3_client: web::Data<s3::Client>,
+) {
+    // Retention sweeps whole organizations, so it runs far less often
+    let retention_app_state = app_state.clone();
+    let retention_s3_client = s3_client.clone();
+
+    actix_web::rt::spawn(async move {
+        let mut interval = actix_web::rt::time::interval(RETENTION_INTERVAL);
+
+        loop {
+            interval.tick().await;
+
+            if let Err(e) =
+                enforce_retention_policies(&retention_app_state, &retention_s3_client).await
+            {
+                error!("Failed to enforce retention policies: {}", e.body);
+            }
+        }
+    });
+
+    actix_web::rt::spawn(async move {
+        let mut interval = actix_web::rt::time::interval(SCHEDULER_INTERVAL);
+
+        loop {
+            interval.tick().await;
+
+            if let Err(e) = publish_due_scheduled_messages(&app_state, &chat_room).await {
+                error!("Failed to publish scheduled messages: {}", e.body);
+            }
+
+            if let Err(e) = delete_expired_messages(&app_state, &chat_room, &s3_client).await {
+                error!("Failed to delete expired messages: {}", e.body);
+            }
+        }
+    });
+}
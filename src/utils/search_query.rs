@@ -0,0 +1,460 @@
+use crate::utils::api_response::ApiResponse;
+use chrono::{NaiveDate, NaiveDateTime};
+use entity::{channel, media, message, message_reaction, user};
+use sea_orm::sea_query::{Expr, Func, Query, SelectStatement};
+use sea_orm::{ColumnTrait, Condition};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[derive(Debug, PartialEq)]
+pub enum SearchTermKind {
+    Word(String),
+    Phrase(String),
+    From(String),
+    InChannel(String),
+    InUser(String),
+    Has(SearchHas),
+    Before(NaiveDate),
+    After(NaiveDate),
+    On(NaiveDate),
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum SearchHas {
+    File,
+    Image,
+    Link,
+    Reaction,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct SearchTerm {
+    pub kind: SearchTermKind,
+    pub negated: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct SearchQueryError {
+    pub message: String,
+    pub token: String,
+    pub start: usize,
+    pub end: usize,
+}
+
+impl From<SearchQueryError> for ApiResponse {
+    fn from(error: SearchQueryError) -> Self {
+        ApiResponse::new(400, serde_json::to_string(&error).unwrap())
+    }
+}
+
+const IMAGE_EXTENSIONS: [&str; 6] = ["png", "jpg", "jpeg", "gif", "webp", "svg"];
+
+/// Parses a query such as `from:@alice in:#release has:file before:2026-09-01
+/// "exact phrase" -excluded`. Offsets in errors are character positions.
+pub fn parse_search_query(input: &str) -> Result<Vec<SearchTerm>, SearchQueryError> {
+    let chars: Vec<char> = input.chars().collect();
+    let mut terms = vec![];
+    let mut position = 0;
+
+    while position < chars.len() {
+        if chars[position].is_whitespace() {
+            position += 1;
+            continue;
+        }
+
+        let start = position;
+        let negated = chars[position] == '-'
+            && chars
+                .get(position + 1)
+                .is_some_and(|next| !next.is_whitespace());
+
+        if negated {
+            position += 1;
+        }
+
+        if chars[position] == '"' {
+            let closing_quote = chars[position + 1..]
+                .iter()
+                .position(|character| *character == '"')
+                .map(|offset| position + 1 + offset);
+
+            let Some(closing_quote) = closing_quote else {
+                return Err(search_query_error(
+                    &chars,
+                    start,
+                    chars.len(),
+                    "Unterminated quoted phrase.",
+                ));
+            };
+
+            let phrase: String = chars[position + 1..closing_quote].iter().collect();
+            position = closing_quote + 1;
+
+            if phrase.trim().is_empty() {
+                return Err(search_query_error(
+                    &chars,
+                    start,
+                    position,
+                    "Quoted phrase is empty.",
+                ));
+            }
+
+            terms.push(SearchTerm {
+                kind: SearchTermKind::Phrase(phrase),
+                negated,
+            });
+            continue;
+        }
+
+        let token_start = position;
+
+        while position < chars.len() && !chars[position].is_whitespace() {
+            position += 1;
+        }
+
+        let token: String = chars[token_start..position].iter().collect();
+
+        let kind = match token.split_once(':') {
+            Some((operator, value)) => parse_operator(operator, value)
+                .map_err(|message| search_query_error(&chars, start, position, &message))?,
+            None => None,
+        }
+        .unwrap_or(SearchTermKind::Word(token));
+
+        terms.push(SearchTerm { kind, negated });
+    }
+
+    Ok(terms)
+}
+
+fn parse_operator(operator: &str, value: &str) -> Result<Option<SearchTermKind>, String> {
+    let kind = match operator {
+        "from" => SearchTermKind::From(parse_username(value)?),
+        "in" => match value.chars().next() {
+            Some('#') if value.len() > 1 => SearchTermKind::InChannel(value[1..].to_string()),
+            Some('@') => SearchTermKind::InUser(parse_username(value)?),
+            _ => return Err("in: expects a #channel or an @user.".to_string()),
+        },
+        "has" => match value {
+            "file" | "media" | "attachment" => SearchTermKind::Has(SearchHas::File),
+            "image" => SearchTermKind::Has(SearchHas::Image),
+            "link" => SearchTermKind::Has(SearchHas::Link),
+            "reaction" => SearchTermKind::Has(SearchHas::Reaction),
+            _ => {
+                return Err(
+                    "has: expects one of file, media, attachment, image, link or reaction."
+                        .to_string(),
+                )
+            }
+        },
+        "before" => SearchTermKind::Before(parse_date(operator, value)?),
+        "after" => SearchTermKind::After(parse_date(operator, value)?),
+        "on" => SearchTermKind::On(parse_date(operator, value)?),
+        _ => return Ok(None),
+    };
+
+    Ok(Some(kind))
+}
+
+fn parse_username(value: &str) -> Result<String, String> {
+    let username = value.strip_prefix('@').unwrap_or(value);
+
+    if username.is_empty() {
+        return Err("Expected a username after @.".to_string());
+    }
+
+    Ok(username.to_string())
+}
+
+fn parse_date(operator: &str, value: &str) -> Result<NaiveDate, String> {
+    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .map_err(|_| format!("{}: expects a date formatted as YYYY-MM-DD.", operator))
+}
+
+fn search_query_error(chars: &[char], start: usize, end: usize, message: &str) -> SearchQueryError {
+    SearchQueryError {
+        message: message.to_string(),
+        token: chars[start..end].iter().collect(),
+        start,
+        end,
+    }
+}
+
+/// Reassembles the free-text part of the query in `websearch_to_tsquery` syntax.
+pub fn get_search_text(terms: &[SearchTerm]) -> String {
+    terms
+        .iter()
+        .filter_map(|term| {
+            let text = match &term.kind {
+                SearchTermKind::Word(word) => word.replace('"', ""),
+                SearchTermKind::Phrase(phrase) => format!("\"{}\"", phrase),
+                _ => return None,
+            };
+
+            Some(if term.negated {
+                format!("-{}", text)
+            } else {
+                text
+            })
+        })
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+/// Compiles the operator terms to a condition over `message`. Users and channels
+/// are resolved by name within the caller's organization.
+pub fn get_search_condition(
+    terms: &[SearchTerm],
+    organization_id: Uuid,
+    user_id: Uuid,
+) -> Condition {
+    let mut from_condition = Condition::any();
+    let mut in_condition = Condition::any();
+    let mut condition = Condition::all();
+
+    for term in terms {
+        let term_condition = match &term.kind {
+            SearchTermKind::Word(_) | SearchTermKind::Phrase(_) => continue,
+            SearchTermKind::From(username) => Condition::all().add(
+                message::Column::UserId
+                    .in_subquery(get_user_ids_by_username(username, organization_id)),
+            ),
+            SearchTermKind::InChannel(channel_name) => Condition::all()
+                .add(message::Column::RecipientType.eq("CHANNEL"))
+                .add(
+                    message::Column::ReferenceId.in_subquery(
+                        Query::select()
+                            .column(channel::Column::Id)
+                            .from(channel::Entity)
+                            .and_where(channel::Column::Name.eq(channel_name.as_str()))
+                            .and_where(channel::Column::OrganizationId.eq(organization_id))
+                            .and_where(channel::Column::Deleted.eq(false))
+                            .to_owned(),
+                    ),
+                ),
+            SearchTermKind::InUser(username) => {
+                Condition::all()
+                    .add(message::Column::RecipientType.eq("USER"))
+                    .add(
+                        Condition::any()
+                            .add(
+                                Condition::all()
+                                    .add(message::Column::UserId.eq(user_id))
+                                    .add(message::Column::ReferenceId.in_subquery(
+                                        get_user_ids_by_username(username, organization_id),
+                                    )),
+                            )
+                            .add(
+                                Condition::all()
+                                    .add(message::Column::UserId.in_subquery(
+                                        get_user_ids_by_username(username, organization_id),
+                                    ))
+                                    .add(message::Column::ReferenceId.eq(user_id)),
+                            ),
+                    )
+            }
+            SearchTermKind::Has(search_has) => get_has_condition(*search_has),
+            SearchTermKind::Before(date) => {
+                Condition::all().add(message::Column::DateCreated.lt(start_of_day(*date)))
+            }
+            SearchTermKind::After(date) => Condition::all()
+                .add(message::Column::DateCreated.gte(start_of_day(*date + chrono::Days::new(1)))),
+            SearchTermKind::On(date) => Condition::all()
+                .add(message::Column::DateCreated.gte(start_of_day(*date)))
+                .add(message::Column::DateCreated.lt(start_of_day(*date + chrono::Days::new(1)))),
+        };
+
+        let term_condition = if term.negated {
+            term_condition.not()
+        } else {
+            term_condition
+        };
+
+        // Repeated from: and in: terms widen the search, everything else narrows it.
+        match (&term.kind, term.negated) {
+            (SearchTermKind::From(_), false) => from_condition = from_condition.add(term_condition),
+            (SearchTermKind::InChannel(_) | SearchTermKind::InUser(_), false) => {
+                in_condition = in_condition.add(term_condition)
+            }
+            _ => condition = condition.add(term_condition),
+        }
+    }
+
+    if !from_condition.is_empty() {
+        condition = condition.add(from_condition);
+    }
+
+    if !in_condition.is_empty() {
+        condition = condition.add(in_condition);
+    }
+
+    condition
+}
+
+pub fn get_has_condition(search_has: SearchHas) -> Condition {
+    match search_has {
+        SearchHas::File => Condition::all().add(message::Column::Id.in_subquery(get_media_query())),
+        SearchHas::Image => {
+            let mut image_condition = Condition::any();
+
+            for extension in IMAGE_EXTENSIONS {
+                image_condition = image_condition.add(
+                    Expr::expr(Func::lower(Expr::col(media::Column::FileName)))
+                        .like(format!("%.{}", extension)),
+                );
+            }
+
+            Condition::all().add(
+                message::Column::Id
+                    .in_subquery(get_media_query().cond_where(image_condition).to_owned()),
+            )
+        }
+        SearchHas::Link => Condition::any()
+            .add(message::Column::Content.contains("http://"))
+            .add(message::Column::Content.contains("https://")),
+        SearchHas::Reaction => Condition::all().add(
+            message::Column::Id.in_subquery(
+                Query::select()
+                    .column(message_reaction::Column::MessageId)
+                    .from(message_reaction::Entity)
+                    .to_owned(),
+            ),
+        ),
+    }
+}
+
+fn get_media_query() -> SelectStatement {
+    Query::select()
+        .column(media::Column::MessageId)
+        .from(media::Entity)
+        .and_where(media::Column::MessageId.is_not_null())
+        .and_where(media::Column::Deleted.eq(false))
+        .to_owned()
+}
+
+fn get_user_ids_by_username(username: &str, organization_id: Uuid) -> SelectStatement {
+    Query::select()
+        .column(user::Column::Id)
+        .from(user::Entity)
+        .and_where(user::Column::Username.eq(username))
+        .and_where(user::Column::OrganizationId.eq(organization_id))
+        .to_owned()
+}
+
+fn start_of_day(date: NaiveDate) -> NaiveDateTime {
+    date.and_hms_opt(0, 0, 0).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn word(word: &str, negated: bool) -> SearchTerm {
+        SearchTerm {
+            kind: SearchTermKind::Word(word.to_string()),
+            negated,
+        }
+    }
+
+    fn parse_kinds(input: &str) -> Vec<SearchTermKind> {
+        parse_search_query(input)
+            .unwrap()
+            .into_iter()
+            .map(|term| term.kind)
+            .collect()
+    }
+
+    #[test]
+    fn parses_empty_input() {
+        assert!(parse_search_query("").unwrap().is_empty());
+        assert!(parse_search_query("   \t ").unwrap().is_empty());
+    }
+
+    #[test]
+    fn parses_words_phrases_and_negation() {
+        let terms = parse_search_query(r#"release -draft "exact phrase" -"not this""#).unwrap();
+
+        assert_eq!(
+            terms,
+            vec![
+                word("release", false),
+                word("draft", true),
+                SearchTerm {
+                    kind: SearchTermKind::Phrase("exact phrase".to_string()),
+                    negated: false,
+                },
+                SearchTerm {
+                    kind: SearchTermKind::Phrase("not this".to_string()),
+                    negated: true,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn keeps_a_lone_dash_as_a_word() {
+        assert_eq!(parse_search_query("a - b").unwrap()[1], word("-", false));
+    }
+
+    #[test]
+    fn parses_operators() {
+        assert_eq!(
+            parse_kinds("from:@alice from:bob in:#release in:@carol has:attachment on:2026-09-01"),
+            vec![
+                SearchTermKind::From("alice".to_string()),
+                SearchTermKind::From("bob".to_string()),
+                SearchTermKind::InChannel("release".to_string()),
+                SearchTermKind::InUser("carol".to_string()),
+                SearchTermKind::Has(SearchHas::File),
+                SearchTermKind::On(NaiveDate::from_ymd_opt(2026, 9, 1).unwrap()),
+            ]
+        );
+    }
+
+    #[test]
+    fn keeps_unknown_operators_as_words() {
+        assert_eq!(
+            parse_kinds("https://example.com"),
+            vec![SearchTermKind::Word("https://example.com".to_string())]
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_operator_values() {
+        for input in [
+            "from:@",
+            "in:#",
+            "in:release",
+            "has:video",
+            "before:09-01-2026",
+        ] {
+            let error = parse_search_query(input).unwrap_err();
+            assert_eq!(error.token, input);
+            assert_eq!((error.start, error.end), (0, input.chars().count()));
+        }
+    }
+
+    #[test]
+    fn reports_character_offsets_for_multi_byte_input() {
+        let error = parse_search_query("héllo \"wörld").unwrap_err();
+
+        assert_eq!(error.message, "Unterminated quoted phrase.");
+        assert_eq!(error.token, "\"wörld");
+        assert_eq!((error.start, error.end), (6, 12));
+    }
+
+    #[test]
+    fn rejects_empty_phrases() {
+        let error = parse_search_query("a -\"  \" b").unwrap_err();
+
+        assert_eq!(error.message, "Quoted phrase is empty.");
+        assert_eq!((error.start, error.end), (2, 7));
+    }
+
+    #[test]
+    fn reassembles_the_free_text() {
+        let terms = parse_search_query(r#"from:@alice wo"rd -draft "exact phrase""#).unwrap();
+
+        assert_eq!(get_search_text(&terms), r#"word -draft "exact phrase""#);
+    }
+}
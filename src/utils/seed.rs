@@ -107,6 +107,7 @@ pub async fn seed_data(db: &DatabaseConnection) {
                         date_created: Set(Utc::now().naive_utc()),
                         message_type: Set("MESSAGE".to_string()),
                         deleted: Set(false),
+                        ..Default::default()
                     }
                     .insert(db)
                     .await
@@ -0,0 +1,12 @@
+use serde::{Deserialize, Deserializer};
+
+/// Used with `#[serde(default)]` on an `Option<Option<T>>` so a missing field
+/// stays `None` while an explicit `null` becomes `Some(None)`. PATCH handlers
+/// use this to keep stored values the request doesn't mention.
+pub fn deserialize_some<'de, T, D>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    T: Deserialize<'de>,
+    D: Deserializer<'de>,
+{
+    T::deserialize(deserializer).map(Some)
+}
@@ -0,0 +1,231 @@
+use crate::routes::handlers::message_handler::{send_message_as_user, MessageSendDTO};
+use crate::routes::handlers::presence_handler::{send_typing_indicator, ChannelIndicateDTO};
+use crate::routes::handlers::user_channel_view_handler::{
+    check_user_can_view_conversation, mark_conversation_read, ChannelViewDTO,
+};
+use crate::utils::api_response::ApiResponse;
+use crate::utils::app_state::AppState;
+use crate::utils::chat::ChatRoom;
+use actix_web::web;
+use entity::user;
+use log::error;
+use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::sync::Arc;
+use uuid::Uuid;
+
+/// Clients send this with every command so the protocol can change without
+/// breaking older ones.
+const SOCKET_PROTOCOL_VERSION: u32 = 1;
+
+#[derive(Deserialize)]
+struct SocketRequestDTO {
+    request_id: String,
+    #[serde(flatten)]
+    command: SocketCommand,
+}
+
+#[derive(Deserialize)]
+#[serde(
+    tag = "command",
+    content = "payload",
+    rename_all = "SCREAMING_SNAKE_CASE"
+)]
+enum SocketCommand {
+    SendMessage(Box<MessageSendDTO>),
+    Typing(ChannelIndicateDTO),
+    MarkRead(ChannelViewDTO),
+    Subscribe(ChannelViewDTO),
+    Unsubscribe(ChannelViewDTO),
+}
+
+#[derive(Serialize)]
+struct SocketAckDTO {
+    message_type: String,
+    request_id: String,
+    data: Value,
+}
+
+#[derive(Serialize)]
+struct SocketErrorDTO {
+    message_type: String,
+    request_id: Option<String>,
+    status_code: u16,
+    error: String,
+}
+
+/// Whether the user exists and hasn't been deleted.
+pub(crate) async fn get_user_is_active(
+    db: &DatabaseConnection,
+    user_id: Uuid,
+) -> Result<bool, ApiResponse> {
+    let user_model = user::Entity::find()
+        .filter(user::Column::Id.eq(user_id))
+        .filter(user::Column::Deleted.eq(false))
+        .one(db)
+        .await
+        .map_err(|e| ApiResponse::new(500, e.to_string()))?;
+
+    Ok(user_model.is_some())
+}
+
+/// Runs a command sent over the socket and returns the ACK or ERROR frame
+/// for the session that sent it, or None when the user is gone and the
+/// session should close.
+pub(crate) async fn handle_socket_command(
+    app_state: &web::Data<AppState>,
+    chat_room: &web::Data<Arc<ChatRoom>>,
+    user_id: Uuid,
+    session_id: Uuid,
+    text: &str,
+) -> Option<String> {
+    // The socket outlives the checks made when it connected
+    match get_user_is_active(&app_state.db, user_id).await {
+        Ok(true) => {}
+        Ok(false) => return None,
+        Err(e) => return Some(get_error_frame(None, e)),
+    }
+
+    Some(run_socket_request(app_state, chat_room, user_id, session_id, text).await)
+}
+
+async fn run_socket_request(
+    app_state: &web::Data<AppState>,
+    chat_room: &web::Data<Arc<ChatRoom>>,
+    user_id: Uuid,
+    session_id: Uuid,
+    text: &str,
+) -> String {
+    let value: Value = match serde_json::from_str(text) {
+        Ok(value) => value,
+        Err(e) => return get_error_frame(None, ApiResponse::new(400, e.to_string())),
+    };
+
+    // The request id is read first so even malformed commands can be answered
+    let request_id = value
+        .get("request_id")
+        .and_then(Value::as_str)
+        .map(str::to_string);
+
+    if value.get("version").and_then(Value::as_u64) != Some(SOCKET_PROTOCOL_VERSION as u64) {
+        return get_error_frame(
+            request_id,
+            ApiResponse::new(
+                400,
+                format!("Protocol version must be {}.", SOCKET_PROTOCOL_VERSION),
+            ),
+        );
+    }
+
+    let request_dto: SocketRequestDTO = match serde_json::from_value(value) {
+        Ok(request_dto) => request_dto,
+        Err(e) => return get_error_frame(request_id, ApiResponse::new(400, e.to_string())),
+    };
+
+    match run_socket_command(
+        app_state,
+        chat_room,
+        user_id,
+        session_id,
+        request_dto.command,
+    )
+    .await
+    {
+        Ok(data) => serde_json::to_string(&SocketAckDTO {
+            message_type: "ACK".to_string(),
+            request_id: request_dto.request_id,
+            data,
+        })
+        .unwrap(),
+        Err(e) => get_error_frame(Some(request_dto.request_id), e),
+    }
+}
+
+/// Commands go through the same checks as their REST endpoints. There is no
+/// request behind them, so permissions are checked by user id.
+async fn run_socket_command(
+    app_state: &web::Data<AppState>,
+    chat_room: &web::Data<Arc<ChatRoom>>,
+    user_id: Uuid,
+    session_id: Uuid,
+    command: SocketCommand,
+) -> Result<Value, ApiResponse> {
+    match command {
+        SocketCommand::SendMessage(message_send_dto) => {
+            let sent_message_dto =
+                send_message_as_user(app_state, chat_room, None, user_id, *message_send_dto)
+                    .await?;
+
+            Ok(serde_json::to_value(&sent_message_dto).unwrap())
+        }
+        SocketCommand::Typing(channel_indicate_dto) => {
+            let indicate_dto =
+                send_typing_indicator(app_state, chat_room, user_id, &channel_indicate_dto).await?;
+
+            Ok(serde_json::to_value(&indicate_dto).unwrap())
+        }
+        SocketCommand::MarkRead(channel_view_dto) => {
+            mark_conversation_read(
+                app_state,
+                chat_room,
+                None,
+                user_id,
+                &channel_view_dto.recipient_type,
+                channel_view_dto.reference_id,
+            )
+            .await?;
+
+            Ok(serde_json::to_value(&channel_view_dto).unwrap())
+        }
+        SocketCommand::Subscribe(channel_view_dto) => {
+            check_user_can_view_conversation(
+                app_state,
+                None,
+                user_id,
+                &channel_view_dto.recipient_type,
+                channel_view_dto.reference_id,
+            )
+            .await?;
+
+            chat_room.set_subscribed(
+                user_id,
+                session_id,
+                &channel_view_dto.recipient_type,
+                channel_view_dto.reference_id,
+                true,
+            );
+
+            Ok(serde_json::to_value(&channel_view_dto).unwrap())
+        }
+        SocketCommand::Unsubscribe(channel_view_dto) => {
+            chat_room.set_subscribed(
+                user_id,
+                session_id,
+                &channel_view_dto.recipient_type,
+                channel_view_dto.reference_id,
+                false,
+            );
+
+            Ok(serde_json::to_value(&channel_view_dto).unwrap())
+        }
+    }
+}
+
+/// Internal errors are logged and hidden from the client, as they are over REST.
+fn get_error_frame(request_id: Option<String>, e: ApiResponse) -> String {
+    let error = if e.status_code == 500 {
+        error!("Internal Server Error: {}", e.body);
+        "An error occurred".to_string()
+    } else {
+        e.body
+    };
+
+    serde_json::to_string(&SocketErrorDTO {
+        message_type: "ERROR".to_string(),
+        request_id,
+        status_code: e.status_code,
+        error,
+    })
+    .unwrap()
+}
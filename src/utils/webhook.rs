@@ -0,0 +1,167 @@
+use reqwest::dns::{Addrs, Name, Resolve, Resolving};
+use reqwest::{redirect, Client, Url};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::sync::Arc;
+use std::time::Duration;
+
+const WEBHOOK_TIMEOUT_SECONDS: u64 = 5;
+
+/// Largest webhook response body that is read, in bytes.
+pub(crate) const MAX_WEBHOOK_RESPONSE_BYTES: usize = 64 * 1024;
+
+/// Resolves webhook hosts and refuses the ones pointing into the private
+/// network, so a DNS record can't be changed after registration to reach it.
+struct PublicAddressResolver;
+
+impl Resolve for PublicAddressResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        Box::pin(async move {
+            let addrs = resolve_public_addrs(name.as_str(), 0).await?;
+
+            Ok(Box::new(addrs.into_iter()) as Addrs)
+        })
+    }
+}
+
+/// Client shared by every webhook call. Redirects are not followed since
+/// they could point at an address that was never checked.
+pub fn build_webhook_client() -> Client {
+    Client::builder()
+        .timeout(Duration::from_secs(WEBHOOK_TIMEOUT_SECONDS))
+        .redirect(redirect::Policy::none())
+        .dns_resolver(Arc::new(PublicAddressResolver))
+        .build()
+        .unwrap()
+}
+
+/// Parses a webhook URL and checks that it is http(s) and that its host
+/// only resolves to public addresses.
+pub(crate) async fn check_webhook_url(webhook_url: &str) -> Result<Url, String> {
+    let url = Url::parse(webhook_url)
+        .ok()
+        .filter(|url| url.scheme() == "http" || url.scheme() == "https")
+        .ok_or("Webhook URL must be an http or https URL.".to_string())?;
+
+    let host = url
+        .host_str()
+        .ok_or("Webhook URL must have a host.".to_string())?;
+
+    let port = url.port_or_known_default().unwrap_or(0);
+
+    // IPv6 hosts keep their brackets in the URL
+    match host
+        .trim_start_matches('[')
+        .trim_end_matches(']')
+        .parse::<IpAddr>()
+    {
+        Ok(ip) if !is_public_ip(ip) => {
+            return Err("Webhook URL must point to a public address.".to_string())
+        }
+        Ok(_) => {}
+        Err(_) => {
+            resolve_public_addrs(host, port)
+                .await
+                .map_err(|e| e.to_string())?;
+        }
+    }
+
+    Ok(url)
+}
+
+async fn resolve_public_addrs(
+    host: &str,
+    port: u16,
+) -> Result<Vec<SocketAddr>, Box<dyn std::error::Error + Send + Sync>> {
+    let addrs: Vec<SocketAddr> = tokio::net::lookup_host((host, port))
+        .await
+        .map_err(|_| format!("Webhook host {} could not be resolved.", host))?
+        .collect();
+
+    if addrs.is_empty() || addrs.iter().any(|addr| !is_public_ip(addr.ip())) {
+        return Err("Webhook URL must point to a public address.".into());
+    }
+
+    Ok(addrs)
+}
+
+/// Whether the address is reachable on the public internet. Loopback,
+/// private, link-local (including cloud metadata at 169.254.169.254) and
+/// other special-purpose ranges are not.
+pub(crate) fn is_public_ip(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => is_public_ipv4(ip),
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(ip) => is_public_ipv4(ip),
+            None => is_public_ipv6(ip),
+        },
+    }
+}
+
+fn is_public_ipv4(ip: Ipv4Addr) -> bool {
+    let [a, b, c, _] = ip.octets();
+
+    !(ip.is_unspecified()
+        || ip.is_loopback()
+        || ip.is_private()
+        || ip.is_link_local()
+        || ip.is_broadcast()
+        || ip.is_documentation()
+        || ip.is_multicast()
+        // 0.0.0.0/8 "this network"
+        || a == 0
+        // 100.64.0.0/10 carrier-grade NAT
+        || (a == 100 && (64..128).contains(&b))
+        // 192.0.0.0/24 protocol assignments
+        || (a == 192 && b == 0 && c == 0)
+        // 198.18.0.0/15 benchmarking
+        || (a == 198 && (18..20).contains(&b))
+        // 240.0.0.0/4 reserved
+        || a >= 240)
+}
+
+fn is_public_ipv6(ip: Ipv6Addr) -> bool {
+    let first_segment = ip.segments()[0];
+
+    !(ip.is_unspecified()
+        || ip.is_loopback()
+        || ip.is_multicast()
+        // fc00::/7 unique local
+        || (first_segment & 0xfe00) == 0xfc00
+        // fe80::/10 link-local
+        || (first_segment & 0xffc0) == 0xfe80
+        // 2001:db8::/32 documentation
+        || (first_segment == 0x2001 && ip.segments()[1] == 0x0db8))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn is_public(ip: &str) -> bool {
+        is_public_ip(ip.parse().unwrap())
+    }
+
+    #[test]
+    fn accepts_public_addresses() {
+        assert!(is_public("93.184.216.34"));
+        assert!(is_public("1.1.1.1"));
+        assert!(is_public("2606:4700:4700::1111"));
+    }
+
+    #[test]
+    fn rejects_internal_addresses() {
+        assert!(!is_public("127.0.0.1"));
+        assert!(!is_public("10.0.0.5"));
+        assert!(!is_public("172.16.0.1"));
+        assert!(!is_public("192.168.1.1"));
+        assert!(!is_public("169.254.169.254"));
+        assert!(!is_public("100.64.0.1"));
+        assert!(!is_public("0.0.0.0"));
+        assert!(!is_public("::1"));
+        assert!(!is_public("::"));
+        assert!(!is_public("fd00::1"));
+        assert!(!is_public("fe80::1"));
+        assert!(!is_public("::ffff:127.0.0.1"));
+        assert!(!is_public("::ffff:169.254.169.254"));
+    }
+}